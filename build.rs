@@ -0,0 +1,7 @@
+fn main() {
+    // use the bundled protoc so building does not depend on a system install
+    let protoc = protoc_bin_vendored::protoc_bin_path().expect("no bundled protoc for this platform");
+    std::env::set_var("PROTOC", protoc);
+    println!("cargo:rerun-if-changed=proto/posts.proto");
+    prost_build::compile_protos(&["proto/posts.proto"], &["proto"]).expect("could not compile protos");
+}
@@ -0,0 +1,32 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "ab_assignments")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub test_id: u64,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub session_id: String,
+    pub variant: String,
+    pub assigned_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::ab_test::Entity",
+        from = "Column::TestId",
+        to = "super::ab_test::Column::Id",
+        on_delete = "Cascade"
+    )]
+    AbTest,
+}
+
+impl Related<super::ab_test::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AbTest.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
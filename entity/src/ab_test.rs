@@ -0,0 +1,28 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "ab_tests")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: u64,
+    #[sea_orm(unique)]
+    pub name: String,
+    /// JSON array of the variant strings.
+    pub variants: Json,
+    pub traffic_percent: u8,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::ab_assignment::Entity")]
+    AbAssignment,
+}
+
+impl Related<super::ab_assignment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AbAssignment.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,32 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::tenant::TenantScoped;
+
+/// One page view with where its reader was, as far as the GeoIP database knows; the
+/// address itself is never stored.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "access_logs")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: u64,
+    #[serde(skip)]
+    pub tenant_id: String,
+    pub path: String,
+    /// ISO 3166-1 alpha-2 code, e.g. `DE`.
+    pub country_code: Option<String>,
+    /// English name of the city.
+    pub city_name: Option<String>,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl TenantScoped for Entity {
+    fn tenant_column() -> Column {
+        Column::TenantId
+    }
+}
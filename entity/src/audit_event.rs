@@ -0,0 +1,39 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    #[sea_orm(string_value = "created")]
+    Created,
+    #[sea_orm(string_value = "updated")]
+    Updated,
+    #[sea_orm(string_value = "deleted")]
+    Deleted,
+}
+
+/// One change to a record, kept after the record itself is gone.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "audit_events")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: u64,
+    /// Kind of record that changed, e.g. `post`.
+    pub entity_type: String,
+    pub entity_id: u64,
+    pub action: AuditAction,
+    /// JSON object of the affected fields: their values for `created` and `deleted`,
+    /// `{"old": ..., "new": ...}` pairs for `updated`.
+    pub changed_fields: Json,
+    pub performed_by: Option<u64>,
+    pub performed_at: DateTimeUtc,
+    /// Tenant of the record that changed.
+    #[serde(skip)]
+    pub tenant_id: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1 +1,18 @@
-pub mod post;
\ No newline at end of file
+pub mod ab_assignment;
+pub mod ab_test;
+pub mod access_log;
+pub mod annotation;
+pub mod audit_event;
+pub mod bookmark;
+pub mod feature_flag;
+pub mod post;
+pub mod post_permission;
+pub mod post_relation;
+pub mod post_revision;
+pub mod reading_list_item;
+pub mod reading_progress;
+pub mod scheduled_feature;
+pub mod site_announcement;
+pub mod site_setting;
+pub mod tenant;
+pub mod user;
@@ -1,5 +1,22 @@
 use sea_orm::entity::prelude::*;
+use sea_orm::ActiveValue;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::tenant::TenantScoped;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "lowercase")]
+pub enum PostStatus {
+    #[default]
+    #[sea_orm(string_value = "draft")]
+    Draft,
+    #[sea_orm(string_value = "published")]
+    Published,
+    #[sea_orm(string_value = "archived")]
+    Archived,
+}
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
 #[sea_orm(table_name = "posts")]
@@ -10,10 +27,86 @@ pub struct Model {
     pub title: String,
     #[sea_orm(column_type = "Text")]
     pub text: String,
+    #[serde(skip_deserializing)]
+    pub featured_image: Option<String>,
+    #[serde(skip_deserializing)]
+    pub featured_image_webp: Option<String>,
+    #[serde(skip_deserializing)]
+    pub created_at: DateTimeUtc,
+    #[serde(default)]
+    pub status: PostStatus,
+    /// Whether `text` holds the sealed ciphertext rather than the plaintext.
+    #[serde(skip_deserializing)]
+    pub is_encrypted: bool,
+    /// `content_hash` of `title` and the stored `text`, kept up to date by `before_save`.
+    #[serde(skip)]
+    pub content_hash: String,
+    /// Id of the post in the system it is synced from by `POST /api/v1/posts/upsert`.
+    #[serde(skip_deserializing)]
+    pub external_id: Option<String>,
+    /// Position set by `POST /api/v1/posts/reorder`; lists use it with `use_custom_order=1`.
+    #[serde(skip_deserializing)]
+    pub sort_order: i32,
+    #[serde(skip_deserializing)]
+    pub is_featured: bool,
+    /// When the post stops being featured; `None` features it until it is unfeatured.
+    #[serde(skip_deserializing)]
+    pub featured_until: Option<DateTimeUtc>,
+    /// When the post is archived if it is still published; cleared once it has been.
+    #[serde(skip_deserializing)]
+    pub expires_at: Option<DateTimeUtc>,
+    /// Block document shown instead of `text` when set, see `PUT /api/v1/posts/{id}/blocks`.
+    #[serde(skip_deserializing)]
+    pub blocks: Option<Json>,
+    /// Token of the link previewing the post at `/preview/{token}`; never sent to clients.
+    #[serde(skip)]
+    pub share_token: Option<String>,
+    #[serde(skip)]
+    pub share_token_expires_at: Option<DateTimeUtc>,
+    /// Id of the tenant the post belongs to.
+    #[serde(skip)]
+    pub tenant_id: String,
+}
+
+/// Hex SHA-256 of `title` followed by `text`.
+pub fn content_hash(title: &str, text: &str) -> String {
+    format!("{:x}", Sha256::new().chain_update(title).chain_update(text).finalize())
+}
+
+impl Model {
+    /// Whether `title` and `text` are still what was hashed when the post was saved.
+    pub fn has_valid_content_hash(&self) -> bool {
+        self.content_hash == content_hash(&self.title, &self.text)
+    }
+
+    /// Whether the post is featured at `now`, which excludes a featuring that has expired.
+    pub fn is_currently_featured(&self, now: DateTimeUtc) -> bool {
+        self.is_featured && self.featured_until.is_none_or(|until| until > now)
+    }
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {}
 
-impl ActiveModelBehavior for ActiveModel {}
+impl TenantScoped for Entity {
+    fn tenant_column() -> Column {
+        Column::TenantId
+    }
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(mut self, _db: &C, _insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if self.title.is_set() || self.text.is_set() {
+            if let (ActiveValue::Set(title) | ActiveValue::Unchanged(title),
+                    ActiveValue::Set(text) | ActiveValue::Unchanged(text)) = (&self.title, &self.text) {
+                self.content_hash = ActiveValue::Set(content_hash(title, text));
+            }
+        }
+        Ok(self)
+    }
+}
 
@@ -0,0 +1,47 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "lowercase")]
+pub enum RelationType {
+    #[sea_orm(string_value = "related")]
+    Related,
+    /// The related post should be read first.
+    #[sea_orm(string_value = "prerequisite")]
+    Prerequisite,
+    /// The related post continues this one.
+    #[sea_orm(string_value = "sequel")]
+    Sequel,
+}
+
+/// A link an author made from one post to another; links go one way.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "post_relations")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub post_id: u64,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub related_post_id: u64,
+    pub relation_type: RelationType,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::post::Entity",
+        from = "Column::PostId",
+        to = "super::post::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Post,
+    #[sea_orm(
+        belongs_to = "super::post::Entity",
+        from = "Column::RelatedPostId",
+        to = "super::post::Column::Id",
+        on_delete = "Cascade"
+    )]
+    RelatedPost,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
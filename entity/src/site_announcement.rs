@@ -0,0 +1,27 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A notice shown at the top of every page of its tenant while it is active. At most one
+/// announcement per tenant is active at a time.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "site_announcements")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: u64,
+    #[sea_orm(column_type = "Text")]
+    pub message: String,
+    pub active: bool,
+    /// CSS hex color of the banner, such as `#fff3cd`.
+    pub bg_color: String,
+    #[serde(skip_deserializing)]
+    pub created_at: DateTimeUtc,
+    /// Id of the tenant the announcement is shown to.
+    #[serde(skip)]
+    pub tenant_id: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,22 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A blog hosted on the instance; requests pick theirs with `X-Tenant-ID` or a subdomain.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "tenants")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+/// Entities whose rows belong to one tenant, so queries for a request can be scoped to its
+/// tenant.
+pub trait TenantScoped: EntityTrait {
+    fn tenant_column() -> Self::Column;
+}
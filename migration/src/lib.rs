@@ -0,0 +1,62 @@
+pub use sea_orm_migration::prelude::*;
+
+mod m20230101_000001_create_tables;
+mod m20230101_000002_create_feature_flags;
+mod m20230101_000003_create_ab_tests;
+mod m20230101_000004_add_post_created_at;
+mod m20230101_000005_add_post_status;
+mod m20230101_000006_create_post_revisions;
+mod m20230101_000007_create_audit_events;
+mod m20230101_000008_add_post_encryption;
+mod m20230101_000009_add_post_content_hash;
+mod m20230101_000010_create_site_settings;
+mod m20230101_000011_add_post_external_id;
+mod m20230101_000012_create_post_relations;
+mod m20230101_000013_add_post_sort_order;
+mod m20230101_000014_create_reading_list_items;
+mod m20230101_000015_add_post_featured;
+mod m20230101_000016_create_site_announcements;
+mod m20230101_000017_add_post_full_text_index;
+mod m20230101_000018_create_tenants;
+mod m20230101_000019_create_scheduled_features;
+mod m20230101_000020_create_access_logs;
+mod m20230101_000021_add_post_expires_at;
+mod m20230101_000022_add_post_blocks;
+mod m20230101_000023_add_post_share_token;
+mod m20230101_000024_add_audit_event_tenant;
+mod m20230101_000025_add_announcement_tenant;
+
+pub struct Migrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![
+            Box::new(m20230101_000001_create_tables::Migration),
+            Box::new(m20230101_000002_create_feature_flags::Migration),
+            Box::new(m20230101_000003_create_ab_tests::Migration),
+            Box::new(m20230101_000004_add_post_created_at::Migration),
+            Box::new(m20230101_000005_add_post_status::Migration),
+            Box::new(m20230101_000006_create_post_revisions::Migration),
+            Box::new(m20230101_000007_create_audit_events::Migration),
+            Box::new(m20230101_000008_add_post_encryption::Migration),
+            Box::new(m20230101_000009_add_post_content_hash::Migration),
+            Box::new(m20230101_000010_create_site_settings::Migration),
+            Box::new(m20230101_000011_add_post_external_id::Migration),
+            Box::new(m20230101_000012_create_post_relations::Migration),
+            Box::new(m20230101_000013_add_post_sort_order::Migration),
+            Box::new(m20230101_000014_create_reading_list_items::Migration),
+            Box::new(m20230101_000015_add_post_featured::Migration),
+            Box::new(m20230101_000016_create_site_announcements::Migration),
+            Box::new(m20230101_000017_add_post_full_text_index::Migration),
+            Box::new(m20230101_000018_create_tenants::Migration),
+            Box::new(m20230101_000019_create_scheduled_features::Migration),
+            Box::new(m20230101_000020_create_access_logs::Migration),
+            Box::new(m20230101_000021_add_post_expires_at::Migration),
+            Box::new(m20230101_000022_add_post_blocks::Migration),
+            Box::new(m20230101_000023_add_post_share_token::Migration),
+            Box::new(m20230101_000024_add_audit_event_tenant::Migration),
+            Box::new(m20230101_000025_add_announcement_tenant::Migration),
+        ]
+    }
+}
@@ -0,0 +1,115 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::ConnectionTrait;
+
+/// The schema of `sql/example.sql` as it stood when migrations were introduced.
+/// Tables that already exist are left alone so databases created from that file can
+/// adopt migrations.
+const CREATE_TABLES: &[&str] = &[
+    r#"
+    create table if not exists posts
+    (
+        id    bigint(20) unsigned auto_increment COMMENT 'primary key',
+        title varchar(255) not null DEFAULT '' COMMENT 'title',
+        text  varchar(255) not null DEFAULT '' COMMENT 'text',
+        featured_image varchar(255) null COMMENT 'featured image path relative to the upload dir',
+        featured_image_webp varchar(255) null COMMENT 'webp variant of the featured image, relative to the upload dir',
+        PRIMARY KEY (id),
+        KEY   index_title (title)
+    ) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='posts table'
+    "#,
+    r#"
+    create table if not exists reading_progress
+    (
+        user_id        bigint(20) unsigned not null COMMENT 'reader',
+        post_id        bigint(20) unsigned not null COMMENT 'post being read',
+        scroll_percent tinyint(3) unsigned not null DEFAULT 0 COMMENT 'last scroll position in percent',
+        updated_at     timestamp not null DEFAULT CURRENT_TIMESTAMP COMMENT 'last update',
+        PRIMARY KEY (user_id, post_id),
+        CONSTRAINT fk_reading_progress_post FOREIGN KEY (post_id) REFERENCES posts (id) ON DELETE CASCADE
+    ) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='reading progress table'
+    "#,
+    r#"
+    create table if not exists bookmarks
+    (
+        user_id    bigint(20) unsigned not null COMMENT 'owner of the bookmark',
+        post_id    bigint(20) unsigned not null COMMENT 'bookmarked post',
+        created_at timestamp not null DEFAULT CURRENT_TIMESTAMP COMMENT 'created time',
+        PRIMARY KEY (user_id, post_id),
+        CONSTRAINT fk_bookmarks_post FOREIGN KEY (post_id) REFERENCES posts (id) ON DELETE CASCADE
+    ) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='bookmarks table'
+    "#,
+    r#"
+    create table if not exists annotations
+    (
+        id           bigint(20) unsigned auto_increment COMMENT 'primary key',
+        user_id      bigint(20) unsigned not null COMMENT 'author of the annotation',
+        post_id      bigint(20) unsigned not null COMMENT 'annotated post',
+        start_offset bigint(20) unsigned not null COMMENT 'first highlighted UTF-16 code unit',
+        end_offset   bigint(20) unsigned not null COMMENT 'UTF-16 code unit after the highlight',
+        note         text null COMMENT 'optional note',
+        created_at   timestamp not null DEFAULT CURRENT_TIMESTAMP COMMENT 'created time',
+        PRIMARY KEY (id),
+        KEY          index_user_post (user_id, post_id),
+        CONSTRAINT fk_annotations_post FOREIGN KEY (post_id) REFERENCES posts (id) ON DELETE CASCADE
+    ) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='annotations table'
+    "#,
+    r#"
+    create table if not exists users
+    (
+        id             bigint(20) unsigned auto_increment COMMENT 'primary key',
+        username       varchar(64) not null COMMENT 'login name',
+        password_hash  varchar(255) null COMMENT 'argon2 PHC string, null for users who only log in through GitHub',
+        github_id      varchar(32) null COMMENT 'GitHub user id',
+        is_admin       tinyint(1) not null DEFAULT 0 COMMENT 'may use the admin area',
+        totp_secret    varchar(64) null COMMENT 'base32 TOTP secret',
+        totp_enabled   tinyint(1) not null DEFAULT 0 COMMENT 'whether the TOTP secret has been confirmed',
+        totp_last_step bigint(20) unsigned null COMMENT 'time step of the last accepted code',
+        PRIMARY KEY (id),
+        UNIQUE KEY     index_username (username),
+        UNIQUE KEY     index_github_id (github_id)
+    ) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='users table'
+    "#,
+    r#"
+    create table if not exists post_permissions
+    (
+        user_id    bigint(20) unsigned not null COMMENT 'user the permission is granted to',
+        post_id    bigint(20) unsigned not null COMMENT 'post the permission applies to',
+        permission varchar(16) not null COMMENT 'read, write or admin',
+        PRIMARY KEY (user_id, post_id),
+        CONSTRAINT fk_post_permissions_post FOREIGN KEY (post_id) REFERENCES posts (id) ON DELETE CASCADE
+    ) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='per-post permissions table'
+    "#,
+];
+
+/// In reverse creation order, so each table is dropped before the tables it references.
+const TABLES: &[&str] = &[
+    "post_permissions",
+    "users",
+    "annotations",
+    "bookmarks",
+    "reading_progress",
+    "posts",
+];
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        for statement in CREATE_TABLES {
+            conn.execute_unprepared(statement).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in TABLES {
+            manager
+                .drop_table(Table::drop().table(Alias::new(table)).to_owned())
+                .await?;
+        }
+        Ok(())
+    }
+}
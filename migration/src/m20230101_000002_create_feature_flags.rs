@@ -0,0 +1,47 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(FeatureFlags::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(FeatureFlags::Name).string_len(64).not_null().primary_key())
+                    .col(ColumnDef::new(FeatureFlags::Enabled).boolean().not_null().default(false))
+                    .col(
+                        ColumnDef::new(FeatureFlags::RolloutPercent)
+                            .tiny_unsigned()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(FeatureFlags::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .extra("DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(FeatureFlags::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum FeatureFlags {
+    Table,
+    Name,
+    Enabled,
+    RolloutPercent,
+    UpdatedAt,
+}
@@ -0,0 +1,85 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AbTests::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AbTests::Id)
+                            .big_unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AbTests::Name).string_len(64).not_null().unique_key())
+                    .col(ColumnDef::new(AbTests::Variants).json().not_null())
+                    .col(
+                        ColumnDef::new(AbTests::TrafficPercent)
+                            .tiny_unsigned()
+                            .not_null()
+                            .default(100),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(AbAssignments::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(AbAssignments::TestId).big_unsigned().not_null())
+                    .col(ColumnDef::new(AbAssignments::SessionId).string_len(64).not_null())
+                    .col(ColumnDef::new(AbAssignments::Variant).string_len(255).not_null())
+                    .col(
+                        ColumnDef::new(AbAssignments::AssignedAt)
+                            .timestamp()
+                            .not_null()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .primary_key(Index::create().col(AbAssignments::TestId).col(AbAssignments::SessionId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_ab_assignments_test")
+                            .from(AbAssignments::Table, AbAssignments::TestId)
+                            .to(AbTests::Table, AbTests::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AbAssignments::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(AbTests::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum AbTests {
+    Table,
+    Id,
+    Name,
+    Variants,
+    TrafficPercent,
+}
+
+#[derive(Iden)]
+enum AbAssignments {
+    Table,
+    TestId,
+    SessionId,
+    Variant,
+    AssignedAt,
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+/// Existing posts get the time the migration runs, as their real creation time is unknown.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Posts::Table)
+                    .add_column(
+                        ColumnDef::new(Posts::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(Table::alter().table(Posts::Table).drop_column(Posts::CreatedAt).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Posts {
+    Table,
+    CreatedAt,
+}
@@ -0,0 +1,57 @@
+use sea_orm_migration::prelude::*;
+
+/// Adds `posts.status`. Posts had no publication state before, and every existing post was
+/// listed publicly, so they all become `published`; new posts start as `draft`.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Posts::Table)
+                    .add_column(
+                        ColumnDef::new(Posts::Status)
+                            .string_len(16)
+                            .not_null()
+                            .default("published"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Posts::Table)
+                    .modify_column(ColumnDef::new(Posts::Status).string_len(16).not_null().default("draft"))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("index_status")
+                    .table(Posts::Table)
+                    .col(Posts::Status)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("index_status").table(Posts::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(Table::alter().table(Posts::Table).drop_column(Posts::Status).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Posts {
+    Table,
+    Status,
+}
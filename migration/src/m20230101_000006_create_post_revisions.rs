@@ -0,0 +1,66 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PostRevisions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PostRevisions::Id)
+                            .big_unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PostRevisions::PostId).big_unsigned().not_null())
+                    .col(ColumnDef::new(PostRevisions::UserId).big_unsigned().not_null())
+                    .col(ColumnDef::new(PostRevisions::Title).string_len(255).not_null())
+                    .col(ColumnDef::new(PostRevisions::Text).text().not_null())
+                    .col(
+                        ColumnDef::new(PostRevisions::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .index(Index::create().name("index_post").col(PostRevisions::PostId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_post_revisions_post")
+                            .from(PostRevisions::Table, PostRevisions::PostId)
+                            .to(Posts::Table, Posts::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PostRevisions::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum PostRevisions {
+    Table,
+    Id,
+    PostId,
+    UserId,
+    Title,
+    Text,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum Posts {
+    Table,
+    Id,
+}
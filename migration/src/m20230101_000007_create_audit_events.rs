@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditEvents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AuditEvents::Id)
+                            .big_unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AuditEvents::EntityType).string_len(32).not_null())
+                    .col(ColumnDef::new(AuditEvents::EntityId).big_unsigned().not_null())
+                    .col(ColumnDef::new(AuditEvents::Action).string_len(16).not_null())
+                    .col(ColumnDef::new(AuditEvents::ChangedFields).json().not_null())
+                    .col(ColumnDef::new(AuditEvents::PerformedBy).big_unsigned().null())
+                    .col(
+                        ColumnDef::new(AuditEvents::PerformedAt)
+                            .timestamp()
+                            .not_null()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .index(
+                        Index::create()
+                            .name("index_entity")
+                            .col(AuditEvents::EntityType)
+                            .col(AuditEvents::EntityId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditEvents::Table).to_owned())
+            .await
+    }
+}
+
+/// No foreign keys: events outlive the records and users they mention.
+#[derive(Iden)]
+enum AuditEvents {
+    Table,
+    Id,
+    EntityType,
+    EntityId,
+    Action,
+    ChangedFields,
+    PerformedBy,
+    PerformedAt,
+}
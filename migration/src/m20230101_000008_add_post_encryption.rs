@@ -0,0 +1,51 @@
+use sea_orm_migration::prelude::*;
+
+/// Encrypted text is base64 and a third longer than the plaintext, so `text` also grows
+/// from varchar(255) to the TEXT the entity already declares.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Posts::Table)
+                    .modify_column(ColumnDef::new(Posts::Text).text().not_null())
+                    .add_column(
+                        ColumnDef::new(Posts::IsEncrypted)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // texts longer than 255 characters do not survive this
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Posts::Table)
+                    .drop_column(Posts::IsEncrypted)
+                    .modify_column(
+                        ColumnDef::new(Posts::Text)
+                            .string_len(255)
+                            .not_null()
+                            .default(""),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Posts {
+    Table,
+    Text,
+    IsEncrypted,
+}
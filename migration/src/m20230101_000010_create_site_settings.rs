@@ -0,0 +1,43 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SiteSettings::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(SiteSettings::Key).string_len(64).not_null().primary_key())
+                    .col(ColumnDef::new(SiteSettings::Value).string_len(255).not_null())
+                    .to_owned(),
+            )
+            .await?;
+        // the page size the server used before it became a setting
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(SiteSettings::Table)
+                    .columns([SiteSettings::Key, SiteSettings::Value])
+                    .values_panic(["posts_per_page".into(), "5".into()])
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SiteSettings::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum SiteSettings {
+    Table,
+    Key,
+    Value,
+}
@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+
+/// Adds `posts.external_id`, the id a post has in the system it is synced from. It is
+/// unique so `POST /api/v1/posts/upsert` can match posts on it; posts written here have
+/// none.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Posts::Table)
+                    .add_column(ColumnDef::new(Posts::ExternalId).string_len(255).null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("index_external_id")
+                    .table(Posts::Table)
+                    .col(Posts::ExternalId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("index_external_id").table(Posts::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(Table::alter().table(Posts::Table).drop_column(Posts::ExternalId).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Posts {
+    Table,
+    ExternalId,
+}
@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PostRelations::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(PostRelations::PostId).big_unsigned().not_null())
+                    .col(ColumnDef::new(PostRelations::RelatedPostId).big_unsigned().not_null())
+                    .col(ColumnDef::new(PostRelations::RelationType).string_len(16).not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(PostRelations::PostId)
+                            .col(PostRelations::RelatedPostId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_post_relations_post")
+                            .from(PostRelations::Table, PostRelations::PostId)
+                            .to(Posts::Table, Posts::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_post_relations_related_post")
+                            .from(PostRelations::Table, PostRelations::RelatedPostId)
+                            .to(Posts::Table, Posts::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PostRelations::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum PostRelations {
+    Table,
+    PostId,
+    RelatedPostId,
+    RelationType,
+}
+
+#[derive(Iden)]
+enum Posts {
+    Table,
+    Id,
+}
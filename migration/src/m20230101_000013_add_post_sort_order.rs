@@ -0,0 +1,44 @@
+use sea_orm_migration::prelude::*;
+
+/// Adds `posts.sort_order`, the position editors give a post with `POST /api/v1/posts/reorder`.
+/// Every post starts at 0, so lists ordered by it fall back to id order until reordered.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Posts::Table)
+                    .add_column(ColumnDef::new(Posts::SortOrder).integer().not_null().default(0))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("index_sort_order")
+                    .table(Posts::Table)
+                    .col(Posts::SortOrder)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("index_sort_order").table(Posts::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(Table::alter().table(Posts::Table).drop_column(Posts::SortOrder).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Posts {
+    Table,
+    SortOrder,
+}
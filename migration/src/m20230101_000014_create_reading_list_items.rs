@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ReadingListItems::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(ReadingListItems::UserId).big_unsigned().not_null())
+                    .col(ColumnDef::new(ReadingListItems::PostId).big_unsigned().not_null())
+                    .col(ColumnDef::new(ReadingListItems::Position).integer().not_null())
+                    .col(
+                        ColumnDef::new(ReadingListItems::AddedAt)
+                            .timestamp()
+                            .not_null()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(ReadingListItems::UserId)
+                            .col(ReadingListItems::PostId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_reading_list_items_post")
+                            .from(ReadingListItems::Table, ReadingListItems::PostId)
+                            .to(Posts::Table, Posts::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ReadingListItems::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum ReadingListItems {
+    Table,
+    UserId,
+    PostId,
+    Position,
+    AddedAt,
+}
+
+#[derive(Iden)]
+enum Posts {
+    Table,
+    Id,
+}
@@ -0,0 +1,52 @@
+use sea_orm_migration::prelude::*;
+
+/// Adds `posts.is_featured` and `posts.featured_until`. A featured post without a
+/// deadline stays featured until it is unfeatured.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Posts::Table)
+                    .add_column(ColumnDef::new(Posts::IsFeatured).boolean().not_null().default(false))
+                    .add_column(ColumnDef::new(Posts::FeaturedUntil).timestamp().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("index_is_featured")
+                    .table(Posts::Table)
+                    .col(Posts::IsFeatured)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("index_is_featured").table(Posts::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Posts::Table)
+                    .drop_column(Posts::IsFeatured)
+                    .drop_column(Posts::FeaturedUntil)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Posts {
+    Table,
+    IsFeatured,
+    FeaturedUntil,
+}
@@ -0,0 +1,40 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::ConnectionTrait;
+
+/// MySQL has no partial indexes, so `active_key` is 1 for the active announcement and
+/// NULL for the others, and its unique index allows only one active row.
+const CREATE_TABLE: &str = r#"
+    create table if not exists site_announcements
+    (
+        id         bigint(20) unsigned auto_increment COMMENT 'primary key',
+        message    text not null COMMENT 'banner text',
+        active     tinyint(1) not null DEFAULT 0 COMMENT 'whether the banner is shown',
+        bg_color   varchar(7) not null DEFAULT '#fff3cd' COMMENT 'banner background, css hex color',
+        created_at timestamp not null DEFAULT CURRENT_TIMESTAMP COMMENT 'created time',
+        active_key tinyint(1) GENERATED ALWAYS AS (IF(active, 1, NULL)) STORED COMMENT 'unique while active',
+        PRIMARY KEY (id),
+        UNIQUE KEY index_active_key (active_key)
+    ) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='site announcements table'
+"#;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.get_connection().execute_unprepared(CREATE_TABLE).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SiteAnnouncements::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum SiteAnnouncements {
+    Table,
+}
@@ -0,0 +1,39 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DbBackend};
+
+/// Indexes the title and text of posts for `search::full_text_search`, with the index type
+/// of each backend. SQLite has none, and its searches scan the table.
+const MYSQL_UP: &[&str] = &["ALTER TABLE posts ADD FULLTEXT INDEX index_full_text (title, text)"];
+const MYSQL_DOWN: &[&str] = &["ALTER TABLE posts DROP INDEX index_full_text"];
+const POSTGRES_UP: &[&str] = &[
+    "ALTER TABLE posts ADD COLUMN ts_vector tsvector GENERATED ALWAYS AS \
+     (to_tsvector('english', coalesce(title, '') || ' ' || coalesce(text, ''))) STORED",
+    "CREATE INDEX index_ts_vector ON posts USING GIN (ts_vector)",
+];
+const POSTGRES_DOWN: &[&str] = &["DROP INDEX index_ts_vector", "ALTER TABLE posts DROP COLUMN ts_vector"];
+
+async fn run(manager: &SchemaManager<'_>, mysql: &[&str], postgres: &[&str]) -> Result<(), DbErr> {
+    let statements = match manager.get_database_backend() {
+        DbBackend::MySql => mysql,
+        DbBackend::Postgres => postgres,
+        DbBackend::Sqlite => &[],
+    };
+    for statement in statements {
+        manager.get_connection().execute_unprepared(statement).await?;
+    }
+    Ok(())
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        run(manager, MYSQL_UP, POSTGRES_UP).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        run(manager, MYSQL_DOWN, POSTGRES_DOWN).await
+    }
+}
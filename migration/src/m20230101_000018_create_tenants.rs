@@ -0,0 +1,48 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::ConnectionTrait;
+
+/// Adds `tenants` and `posts.tenant_id`. Existing posts belong to the `default` tenant,
+/// which requests without a tenant use. External ids become unique per tenant.
+const UP: &[&str] = &[
+    r#"
+    create table if not exists tenants
+    (
+        id   varchar(64) not null COMMENT 'tenant id, as sent in X-Tenant-ID or the subdomain',
+        name varchar(255) not null COMMENT 'name of the blog',
+        PRIMARY KEY (id)
+    ) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='tenants table'
+    "#,
+    "INSERT INTO tenants (id, name) VALUES ('default', 'Default')",
+    r#"
+    ALTER TABLE posts
+        ADD COLUMN tenant_id varchar(64) not null DEFAULT 'default' COMMENT 'tenant the post belongs to',
+        ADD KEY index_tenant (tenant_id),
+        ADD CONSTRAINT fk_posts_tenant FOREIGN KEY (tenant_id) REFERENCES tenants (id)
+    "#,
+    "ALTER TABLE posts DROP INDEX index_external_id, ADD UNIQUE KEY index_external_id (tenant_id, external_id)",
+];
+const DOWN: &[&str] = &[
+    "ALTER TABLE posts DROP INDEX index_external_id, ADD UNIQUE KEY index_external_id (external_id)",
+    "ALTER TABLE posts DROP FOREIGN KEY fk_posts_tenant, DROP KEY index_tenant, DROP COLUMN tenant_id",
+    "DROP TABLE tenants",
+];
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for statement in UP {
+            manager.get_connection().execute_unprepared(statement).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for statement in DOWN {
+            manager.get_connection().execute_unprepared(statement).await?;
+        }
+        Ok(())
+    }
+}
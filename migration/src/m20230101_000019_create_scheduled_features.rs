@@ -0,0 +1,81 @@
+use sea_orm_migration::prelude::*;
+
+/// Adds `scheduled_features`, the post of the day of each tenant.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScheduledFeatures::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ScheduledFeatures::Id)
+                            .big_unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ScheduledFeatures::TenantId).string_len(64).not_null())
+                    .col(ColumnDef::new(ScheduledFeatures::PostId).big_unsigned().not_null())
+                    .col(ColumnDef::new(ScheduledFeatures::Date).date().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_scheduled_features_post")
+                            .from(ScheduledFeatures::Table, ScheduledFeatures::PostId)
+                            .to(Posts::Table, Posts::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_scheduled_features_tenant")
+                            .from(ScheduledFeatures::Table, ScheduledFeatures::TenantId)
+                            .to(Tenants::Table, Tenants::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("index_tenant_date")
+                    .table(ScheduledFeatures::Table)
+                    .col(ScheduledFeatures::TenantId)
+                    .col(ScheduledFeatures::Date)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ScheduledFeatures::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum ScheduledFeatures {
+    Table,
+    Id,
+    TenantId,
+    PostId,
+    Date,
+}
+
+#[derive(Iden)]
+enum Posts {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Tenants {
+    Table,
+    Id,
+}
@@ -0,0 +1,72 @@
+use sea_orm_migration::prelude::*;
+
+/// Adds `access_logs`, the page views recorded with their readers' locations.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AccessLogs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AccessLogs::Id)
+                            .big_unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AccessLogs::TenantId).string_len(64).not_null())
+                    .col(ColumnDef::new(AccessLogs::Path).string_len(255).not_null())
+                    .col(ColumnDef::new(AccessLogs::CountryCode).string_len(2).null())
+                    .col(ColumnDef::new(AccessLogs::CityName).string_len(255).null())
+                    .col(
+                        ColumnDef::new(AccessLogs::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .index(
+                        Index::create()
+                            .name("index_tenant_created_at")
+                            .col(AccessLogs::TenantId)
+                            .col(AccessLogs::CreatedAt),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_access_logs_tenant")
+                            .from(AccessLogs::Table, AccessLogs::TenantId)
+                            .to(Tenants::Table, Tenants::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AccessLogs::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum AccessLogs {
+    Table,
+    Id,
+    TenantId,
+    Path,
+    CountryCode,
+    CityName,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum Tenants {
+    Table,
+    Id,
+}
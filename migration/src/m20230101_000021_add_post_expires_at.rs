@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+/// Adds `posts.expires_at`, when a published post is archived by the expiry task.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Posts::Table)
+                    .add_column(ColumnDef::new(Posts::ExpiresAt).timestamp().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("index_status_expires_at")
+                    .table(Posts::Table)
+                    .col(Posts::Status)
+                    .col(Posts::ExpiresAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("index_status_expires_at").table(Posts::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Posts::Table)
+                    .drop_column(Posts::ExpiresAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Posts {
+    Table,
+    Status,
+    ExpiresAt,
+}
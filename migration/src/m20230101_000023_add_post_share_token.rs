@@ -0,0 +1,53 @@
+use sea_orm_migration::prelude::*;
+
+/// Adds `posts.share_token` and `posts.share_token_expires_at`, for the preview links of
+/// unpublished posts.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Posts::Table)
+                    .add_column(ColumnDef::new(Posts::ShareToken).string_len(64).null())
+                    .add_column(ColumnDef::new(Posts::ShareTokenExpiresAt).timestamp().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("index_share_token")
+                    .table(Posts::Table)
+                    .col(Posts::ShareToken)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("index_share_token").table(Posts::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Posts::Table)
+                    .drop_column(Posts::ShareToken)
+                    .drop_column(Posts::ShareTokenExpiresAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Posts {
+    Table,
+    ShareToken,
+    ShareTokenExpiresAt,
+}
@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::ConnectionTrait;
+
+/// Adds `audit_events.tenant_id`. Events about existing posts take the tenant of their
+/// post; the rest, such as those of deleted posts, belong to the `default` tenant.
+const UP: &[&str] = &[
+    r#"
+    ALTER TABLE audit_events
+        ADD COLUMN tenant_id varchar(64) not null DEFAULT 'default' COMMENT 'tenant of the record that changed',
+        ADD KEY index_tenant_id (tenant_id, id),
+        ADD CONSTRAINT fk_audit_events_tenant FOREIGN KEY (tenant_id) REFERENCES tenants (id) ON DELETE CASCADE
+    "#,
+    r#"
+    UPDATE audit_events
+        JOIN posts ON audit_events.entity_type = 'post' AND posts.id = audit_events.entity_id
+        SET audit_events.tenant_id = posts.tenant_id
+    "#,
+];
+const DOWN: &[&str] = &[
+    "ALTER TABLE audit_events DROP FOREIGN KEY fk_audit_events_tenant, DROP KEY index_tenant_id, DROP COLUMN tenant_id",
+];
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for statement in UP {
+            manager.get_connection().execute_unprepared(statement).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for statement in DOWN {
+            manager.get_connection().execute_unprepared(statement).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::ConnectionTrait;
+
+/// Adds `site_announcements.tenant_id`, existing announcements belonging to the `default`
+/// tenant, and allows one active announcement per tenant rather than per site.
+const UP: &[&str] = &[
+    r#"
+    ALTER TABLE site_announcements
+        ADD COLUMN tenant_id varchar(64) not null DEFAULT 'default' COMMENT 'tenant the banner is shown to',
+        DROP KEY index_active_key,
+        ADD UNIQUE KEY index_active_key (tenant_id, active_key),
+        ADD CONSTRAINT fk_site_announcements_tenant FOREIGN KEY (tenant_id) REFERENCES tenants (id) ON DELETE CASCADE
+    "#,
+];
+const DOWN: &[&str] = &[
+    "UPDATE site_announcements SET active = 0 WHERE tenant_id <> 'default'",
+    r#"
+    ALTER TABLE site_announcements
+        DROP FOREIGN KEY fk_site_announcements_tenant,
+        DROP KEY index_active_key,
+        ADD UNIQUE KEY index_active_key (active_key),
+        DROP COLUMN tenant_id
+    "#,
+];
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for statement in UP {
+            manager.get_connection().execute_unprepared(statement).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for statement in DOWN {
+            manager.get_connection().execute_unprepared(statement).await?;
+        }
+        Ok(())
+    }
+}
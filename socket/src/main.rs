@@ -1,7 +1,7 @@
 use my_server::echo_main;
 
 pub mod my_server {
-    use std::{io::{self, Write}, net::{TcpListener, TcpStream}, thread::spawn};
+    use std::{io, net::TcpListener, thread::spawn};
 
     pub fn echo_main(addr: &str) -> io::Result<()> {
         let listener = TcpListener::bind(addr)?;
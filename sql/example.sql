@@ -3,15 +3,270 @@ database example CHARACTER SET utf8 COLLATE utf8_general_ci;
 use
 example;
 
+-- tables are dropped and created parent first, so re-running the script needs the
+-- foreign key checks off
+SET FOREIGN_KEY_CHECKS = 0;
+
 DROP TABLE IF EXISTS posts;
+DROP TABLE IF EXISTS tenants;
+
+create table tenants
+(
+    id   varchar(64) not null COMMENT 'tenant id, as sent in X-Tenant-ID or the subdomain',
+    name varchar(255) not null COMMENT 'name of the blog',
+    PRIMARY KEY (id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='tenants table';
+
+INSERT INTO tenants (id, name) VALUES ('default', 'Default');
 
 create table posts
 (
     id    bigint(20) unsigned auto_increment COMMENT 'primary key',
     title varchar(255) not null DEFAULT '' COMMENT 'title',
-    text  varchar(255) not null DEFAULT '' COMMENT 'text',
+    text  text not null COMMENT 'text, or the base64 ciphertext when is_encrypted',
+    featured_image varchar(255) null COMMENT 'featured image path relative to the upload dir',
+    featured_image_webp varchar(255) null COMMENT 'webp variant of the featured image, relative to the upload dir',
+    created_at timestamp not null DEFAULT CURRENT_TIMESTAMP COMMENT 'created time',
+    status varchar(16) not null DEFAULT 'draft' COMMENT 'draft, published or archived',
+    is_encrypted tinyint(1) not null DEFAULT 0 COMMENT 'whether text is encrypted',
+    content_hash char(64) not null DEFAULT '' COMMENT 'sha-256 of title and stored text, hex',
+    external_id varchar(255) null COMMENT 'id in the system the post is synced from',
+    sort_order int(11) not null DEFAULT 0 COMMENT 'position set by editors, for custom ordered lists',
+    is_featured tinyint(1) not null DEFAULT 0 COMMENT 'whether the post is featured',
+    featured_until timestamp null COMMENT 'end of the featuring, null for no end',
+    expires_at timestamp null COMMENT 'when the published post is archived, null for never',
+    blocks json null COMMENT 'block document shown instead of text when set',
+    share_token varchar(64) null COMMENT 'token of the preview link, null when there is none',
+    share_token_expires_at timestamp null COMMENT 'when the preview link stops working',
+    tenant_id varchar(64) not null DEFAULT 'default' COMMENT 'tenant the post belongs to',
     PRIMARY KEY (id),
-    KEY   index_title (title)
+    UNIQUE KEY index_external_id (tenant_id, external_id),
+    UNIQUE KEY index_share_token (share_token),
+    KEY   index_title (title),
+    KEY   index_status (status),
+    KEY   index_status_expires_at (status, expires_at),
+    KEY   index_sort_order (sort_order),
+    KEY   index_is_featured (is_featured),
+    KEY   index_tenant (tenant_id),
+    FULLTEXT KEY index_full_text (title, text),
+    CONSTRAINT fk_posts_tenant FOREIGN KEY (tenant_id) REFERENCES tenants (id)
 ) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='posts table';
 
 
+DROP TABLE IF EXISTS reading_progress;
+
+create table reading_progress
+(
+    user_id        bigint(20) unsigned not null COMMENT 'reader',
+    post_id        bigint(20) unsigned not null COMMENT 'post being read',
+    scroll_percent tinyint(3) unsigned not null DEFAULT 0 COMMENT 'last scroll position in percent',
+    updated_at     timestamp not null DEFAULT CURRENT_TIMESTAMP COMMENT 'last update',
+    PRIMARY KEY (user_id, post_id),
+    CONSTRAINT fk_reading_progress_post FOREIGN KEY (post_id) REFERENCES posts (id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='reading progress table';
+
+DROP TABLE IF EXISTS bookmarks;
+
+create table bookmarks
+(
+    user_id    bigint(20) unsigned not null COMMENT 'owner of the bookmark',
+    post_id    bigint(20) unsigned not null COMMENT 'bookmarked post',
+    created_at timestamp not null DEFAULT CURRENT_TIMESTAMP COMMENT 'created time',
+    PRIMARY KEY (user_id, post_id),
+    CONSTRAINT fk_bookmarks_post FOREIGN KEY (post_id) REFERENCES posts (id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='bookmarks table';
+
+DROP TABLE IF EXISTS reading_list_items;
+
+create table reading_list_items
+(
+    user_id  bigint(20) unsigned not null COMMENT 'owner of the reading list',
+    post_id  bigint(20) unsigned not null COMMENT 'queued post',
+    position int(11) not null COMMENT 'place in the list, lowest first',
+    added_at timestamp not null DEFAULT CURRENT_TIMESTAMP COMMENT 'time the post was queued',
+    PRIMARY KEY (user_id, post_id),
+    CONSTRAINT fk_reading_list_items_post FOREIGN KEY (post_id) REFERENCES posts (id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='reading list table';
+
+DROP TABLE IF EXISTS scheduled_features;
+
+create table scheduled_features
+(
+    id        bigint(20) unsigned auto_increment COMMENT 'primary key',
+    tenant_id varchar(64) not null COMMENT 'tenant the post is picked for',
+    post_id   bigint(20) unsigned not null COMMENT 'post of the day',
+    date      date not null COMMENT 'day the post is featured',
+    PRIMARY KEY (id),
+    UNIQUE KEY index_tenant_date (tenant_id, date),
+    CONSTRAINT fk_scheduled_features_post FOREIGN KEY (post_id) REFERENCES posts (id) ON DELETE CASCADE,
+    CONSTRAINT fk_scheduled_features_tenant FOREIGN KEY (tenant_id) REFERENCES tenants (id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='post of the day table';
+
+DROP TABLE IF EXISTS access_logs;
+
+create table access_logs
+(
+    id           bigint(20) unsigned auto_increment COMMENT 'primary key',
+    tenant_id    varchar(64) not null COMMENT 'tenant whose page was viewed',
+    path         varchar(255) not null COMMENT 'viewed path',
+    country_code varchar(2) null COMMENT 'ISO 3166-1 alpha-2 country of the reader',
+    city_name    varchar(255) null COMMENT 'city of the reader',
+    created_at   timestamp not null DEFAULT CURRENT_TIMESTAMP COMMENT 'time of the view',
+    PRIMARY KEY (id),
+    KEY          index_tenant_created_at (tenant_id, created_at),
+    CONSTRAINT fk_access_logs_tenant FOREIGN KEY (tenant_id) REFERENCES tenants (id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='access log table';
+
+DROP TABLE IF EXISTS annotations;
+
+create table annotations
+(
+    id           bigint(20) unsigned auto_increment COMMENT 'primary key',
+    user_id      bigint(20) unsigned not null COMMENT 'author of the annotation',
+    post_id      bigint(20) unsigned not null COMMENT 'annotated post',
+    start_offset bigint(20) unsigned not null COMMENT 'first highlighted UTF-16 code unit',
+    end_offset   bigint(20) unsigned not null COMMENT 'UTF-16 code unit after the highlight',
+    note         text null COMMENT 'optional note',
+    created_at   timestamp not null DEFAULT CURRENT_TIMESTAMP COMMENT 'created time',
+    PRIMARY KEY (id),
+    KEY          index_user_post (user_id, post_id),
+    CONSTRAINT fk_annotations_post FOREIGN KEY (post_id) REFERENCES posts (id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='annotations table';
+
+
+DROP TABLE IF EXISTS users;
+
+create table users
+(
+    id             bigint(20) unsigned auto_increment COMMENT 'primary key',
+    username       varchar(64) not null COMMENT 'login name',
+    password_hash  varchar(255) null COMMENT 'argon2 PHC string, null for users who only log in through GitHub',
+    github_id      varchar(32) null COMMENT 'GitHub user id',
+    is_admin       tinyint(1) not null DEFAULT 0 COMMENT 'may use the admin area',
+    totp_secret    varchar(64) null COMMENT 'base32 TOTP secret',
+    totp_enabled   tinyint(1) not null DEFAULT 0 COMMENT 'whether the TOTP secret has been confirmed',
+    totp_last_step bigint(20) unsigned null COMMENT 'time step of the last accepted code',
+    PRIMARY KEY (id),
+    UNIQUE KEY     index_username (username),
+    UNIQUE KEY     index_github_id (github_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='users table';
+
+DROP TABLE IF EXISTS post_permissions;
+
+create table post_permissions
+(
+    user_id    bigint(20) unsigned not null COMMENT 'user the permission is granted to',
+    post_id    bigint(20) unsigned not null COMMENT 'post the permission applies to',
+    permission varchar(16) not null COMMENT 'read, write or admin',
+    PRIMARY KEY (user_id, post_id),
+    CONSTRAINT fk_post_permissions_post FOREIGN KEY (post_id) REFERENCES posts (id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='per-post permissions table';
+
+DROP TABLE IF EXISTS post_relations;
+
+create table post_relations
+(
+    post_id         bigint(20) unsigned not null COMMENT 'post the link is shown on',
+    related_post_id bigint(20) unsigned not null COMMENT 'post the link points to',
+    relation_type   varchar(16) not null COMMENT 'related, prerequisite or sequel',
+    PRIMARY KEY (post_id, related_post_id),
+    CONSTRAINT fk_post_relations_post FOREIGN KEY (post_id) REFERENCES posts (id) ON DELETE CASCADE,
+    CONSTRAINT fk_post_relations_related_post FOREIGN KEY (related_post_id) REFERENCES posts (id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='links between posts table';
+
+DROP TABLE IF EXISTS feature_flags;
+
+create table feature_flags
+(
+    name            varchar(64) not null COMMENT 'flag name',
+    enabled         tinyint(1) not null DEFAULT 0 COMMENT 'kill switch, overrides rollout_percent',
+    rollout_percent tinyint(3) unsigned not null DEFAULT 0 COMMENT 'share of users the flag is on for',
+    updated_at      timestamp not null DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT 'last update',
+    PRIMARY KEY (name)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='feature flags table';
+
+DROP TABLE IF EXISTS site_settings;
+
+create table site_settings
+(
+    `key`   varchar(64) not null COMMENT 'setting name',
+    `value` varchar(255) not null COMMENT 'setting value',
+    PRIMARY KEY (`key`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='site settings table';
+
+INSERT INTO site_settings (`key`, `value`) VALUES ('posts_per_page', '5');
+
+DROP TABLE IF EXISTS site_announcements;
+
+create table site_announcements
+(
+    id         bigint(20) unsigned auto_increment COMMENT 'primary key',
+    message    text not null COMMENT 'banner text',
+    active     tinyint(1) not null DEFAULT 0 COMMENT 'whether the banner is shown',
+    bg_color   varchar(7) not null DEFAULT '#fff3cd' COMMENT 'banner background, css hex color',
+    created_at timestamp not null DEFAULT CURRENT_TIMESTAMP COMMENT 'created time',
+    active_key tinyint(1) GENERATED ALWAYS AS (IF(active, 1, NULL)) STORED COMMENT 'unique while active',
+    tenant_id  varchar(64) not null DEFAULT 'default' COMMENT 'tenant the banner is shown to',
+    PRIMARY KEY (id),
+    UNIQUE KEY index_active_key (tenant_id, active_key),
+    CONSTRAINT fk_site_announcements_tenant FOREIGN KEY (tenant_id) REFERENCES tenants (id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='site announcements table';
+
+DROP TABLE IF EXISTS ab_tests;
+
+create table ab_tests
+(
+    id              bigint(20) unsigned auto_increment COMMENT 'primary key',
+    name            varchar(64) not null COMMENT 'test name, post-<id>-title for post title tests',
+    variants        json not null COMMENT 'array of variant strings',
+    traffic_percent tinyint(3) unsigned not null DEFAULT 100 COMMENT 'share of sessions in the test',
+    PRIMARY KEY (id),
+    UNIQUE KEY      index_name (name)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='a/b tests table';
+
+DROP TABLE IF EXISTS ab_assignments;
+
+create table ab_assignments
+(
+    test_id     bigint(20) unsigned not null COMMENT 'test the session is assigned in',
+    session_id  varchar(64) not null COMMENT 'visitor session',
+    variant     varchar(255) not null COMMENT 'assigned variant',
+    assigned_at timestamp not null DEFAULT CURRENT_TIMESTAMP COMMENT 'first assignment',
+    PRIMARY KEY (test_id, session_id),
+    CONSTRAINT fk_ab_assignments_test FOREIGN KEY (test_id) REFERENCES ab_tests (id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='a/b test assignments table';
+
+DROP TABLE IF EXISTS post_revisions;
+
+create table post_revisions
+(
+    id         bigint(20) unsigned auto_increment COMMENT 'primary key',
+    post_id    bigint(20) unsigned not null COMMENT 'revised post',
+    user_id    bigint(20) unsigned not null COMMENT 'user who made the change',
+    title      varchar(255) not null COMMENT 'title before the change',
+    text       text not null COMMENT 'text before the change',
+    created_at timestamp not null DEFAULT CURRENT_TIMESTAMP COMMENT 'time of the change',
+    PRIMARY KEY (id),
+    KEY        index_post (post_id),
+    CONSTRAINT fk_post_revisions_post FOREIGN KEY (post_id) REFERENCES posts (id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='post revisions table';
+
+DROP TABLE IF EXISTS audit_events;
+
+create table audit_events
+(
+    id             bigint(20) unsigned auto_increment COMMENT 'primary key',
+    entity_type    varchar(32) not null COMMENT 'kind of record that changed',
+    entity_id      bigint(20) unsigned not null COMMENT 'id of the record that changed',
+    action         varchar(16) not null COMMENT 'created, updated or deleted',
+    changed_fields json not null COMMENT 'affected fields and their values',
+    performed_by   bigint(20) unsigned COMMENT 'user who made the change',
+    performed_at   timestamp not null DEFAULT CURRENT_TIMESTAMP COMMENT 'time of the change',
+    tenant_id      varchar(64) not null DEFAULT 'default' COMMENT 'tenant of the record that changed',
+    PRIMARY KEY (id),
+    KEY            index_entity (entity_type, entity_id),
+    KEY            index_tenant_id (tenant_id, id),
+    CONSTRAINT fk_audit_events_tenant FOREIGN KEY (tenant_id) REFERENCES tenants (id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8 COMMENT='audit events table';
+
+SET FOREIGN_KEY_CHECKS = 1;
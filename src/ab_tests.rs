@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use actix_web::cookie::{time::Duration, Cookie};
+use actix_web::HttpRequest;
+use rand::distributions::{Alphanumeric, DistString};
+use sea_orm::{entity::*, query::*, sea_query::OnConflict, DatabaseConnection, DbErr};
+
+use entity::ab_assignment;
+use entity::ab_assignment::Entity as AbAssignment;
+use entity::ab_test;
+use entity::ab_test::Entity as AbTest;
+
+use crate::stable_hash;
+
+/// Name of the cookie that identifies a visitor across requests for assignment.
+pub const SESSION_COOKIE: &str = "ab_session";
+const SESSION_ID_LEN: usize = 32;
+const SESSION_TTL_DAYS: i64 = 365;
+
+/// Returns the visitor's session id, plus the cookie to set when the visitor is new.
+pub fn session(req: &HttpRequest) -> (String, Option<Cookie<'static>>) {
+    if let Some(cookie) = req.cookie(SESSION_COOKIE) {
+        return (cookie.value().to_owned(), None);
+    }
+    let session_id = Alphanumeric.sample_string(&mut rand::thread_rng(), SESSION_ID_LEN);
+    let cookie = Cookie::build(SESSION_COOKIE, session_id.clone())
+        .path("/")
+        .http_only(true)
+        .max_age(Duration::days(SESSION_TTL_DAYS))
+        .finish();
+    (session_id, Some(cookie))
+}
+
+/// Name of the test that tries out alternative titles for a post.
+pub fn title_test(post_id: u64) -> String {
+    format!("post-{}-title", post_id)
+}
+
+/// Returns the variant of each of `test_names` that `session_id` sees, leaving out tests
+/// that don't exist and those whose traffic share the session falls outside of.
+///
+/// The same session always gets the same variant; assignments are recorded the first
+/// time they are made. Takes one query to find the tests and one to record them, however
+/// many names there are.
+pub async fn assign_variants(test_names: &[String],
+                             session_id: &str,
+                             conn: &DatabaseConnection,
+) -> HashMap<String, String> {
+    match try_assign_variants(test_names, session_id, conn).await {
+        Ok(variants) => variants,
+        Err(err) => {
+            tracing::warn!(%err, "could not assign a/b test variants");
+            HashMap::new()
+        }
+    }
+}
+
+/// The variant of `test` for `session_id`, or `None` when the session doesn't take part.
+fn pick_variant(test: &ab_test::Model, session_id: &str) -> Result<Option<String>, DbErr> {
+    let variants: Vec<String> = match serde_json::from_value(test.variants.clone()) {
+        Ok(variants) => variants,
+        Err(_) => {
+            return Err(DbErr::Custom(format!("variants of {} are not a string array", test.name)));
+        }
+    };
+    let hash = stable_hash::fnv1a(
+        test.id.to_le_bytes().into_iter().chain(Some(b':')).chain(session_id.bytes()),
+    );
+    // the low digits pick whether the session takes part, the rest which variant it sees
+    if variants.is_empty() || hash % 100 >= test.traffic_percent as u64 {
+        return Ok(None);
+    }
+    Ok(Some(variants[(hash / 100 % variants.len() as u64) as usize].clone()))
+}
+
+async fn try_assign_variants(test_names: &[String],
+                             session_id: &str,
+                             conn: &DatabaseConnection,
+) -> Result<HashMap<String, String>, DbErr> {
+    if test_names.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let tests = AbTest::find()
+        .filter(ab_test::Column::Name.is_in(test_names.iter().cloned()))
+        .all(conn)
+        .await?;
+    let mut variants = HashMap::new();
+    let mut assignments = Vec::new();
+    for test in tests {
+        let Some(variant) = pick_variant(&test, session_id)? else {
+            continue;
+        };
+        assignments.push(ab_assignment::ActiveModel {
+            test_id: Set(test.id),
+            session_id: Set(session_id.to_owned()),
+            variant: Set(variant.clone()),
+            assigned_at: Set(chrono::Utc::now()),
+        });
+        variants.insert(test.name, variant);
+    }
+    if assignments.is_empty() {
+        return Ok(variants);
+    }
+    // rewriting only the variant keeps the first assigned_at; `do_nothing()` renders as
+    // invalid MySQL
+    AbAssignment::insert_many(assignments)
+        .on_conflict(
+            OnConflict::columns([ab_assignment::Column::TestId, ab_assignment::Column::SessionId])
+                .update_column(ab_assignment::Column::Variant)
+                .to_owned(),
+        )
+        .exec_without_returning(conn)
+        .await?;
+    Ok(variants)
+}
@@ -0,0 +1,443 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use actix_web::{cookie::{Cookie, SameSite}, delete, post, put, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use chrono::{DateTime, Utc};
+use sea_orm::{entity::*, query::*, sea_query::Expr};
+use serde::{Deserialize, Serialize};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use entity::post::{self, Entity as Post, PostStatus};
+use entity::user;
+use entity::user::Entity as User;
+
+use crate::{api, audit};
+use crate::api_error::ApiError;
+use crate::auth::{self, AdminUser, PendingUser, TOKEN_COOKIE};
+use crate::circuit_breaker::with_circuit_breaker;
+use crate::negotiate::{self, Body};
+use crate::tenants::Tenant;
+use crate::AppState;
+
+/// Most posts one bulk status change may carry.
+const MAX_BULK_IDS: usize = 1000;
+const TOTP_ISSUER: &str = "sea-orm-demo";
+const TOTP_DIGITS: usize = 6;
+const TOTP_STEP: u64 = 30;
+/// Number of steps either side of now a code is still accepted for, to allow for clock drift.
+const TOTP_SKEW: u8 = 1;
+
+#[derive(Debug, Deserialize)]
+pub struct LoginBody {
+    username: String,
+    password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CodeBody {
+    code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatusBody {
+    status: PostStatus,
+}
+
+/// Body of `POST /admin/posts/bulk-status-change`.
+#[derive(Debug, Deserialize)]
+pub struct BulkStatusBody {
+    ids: Vec<u64>,
+    status: PostStatus,
+}
+
+#[derive(Debug, Serialize)]
+struct BulkStatusResponse {
+    /// Posts whose status changed; posts already in the status are not counted.
+    updated: u64,
+}
+
+/// Body of `POST /admin/posts/{id}/feature`; leaving out `until` features the post until
+/// it is unfeatured.
+#[derive(Debug, Deserialize)]
+pub struct FeatureBody {
+    #[serde(default)]
+    until: Option<DateTime<Utc>>,
+}
+
+/// Body of `POST /admin/posts/{id}/expiry`.
+#[derive(Debug, Deserialize)]
+pub struct ExpiryBody {
+    at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenResponse {
+    token: String,
+    pending_2fa: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SetupResponse {
+    provisioning_uri: String,
+}
+
+fn totp(secret: &str, username: &str) -> Result<TOTP, Error> {
+    let secret = Secret::Encoded(secret.to_owned())
+        .to_bytes()
+        .map_err(|_| ApiError::internal("invalid totp secret"))?;
+    TOTP::new(
+        Algorithm::SHA1,
+        TOTP_DIGITS,
+        TOTP_SKEW,
+        TOTP_STEP,
+        secret,
+        Some(TOTP_ISSUER.to_owned()),
+        username.to_owned(),
+    )
+        .map_err(|_| ApiError::internal("could not create totp").into())
+}
+
+/// Returns the time step `code` is valid for, if any.
+fn matching_step(totp: &TOTP, code: &str) -> Option<u64> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() / TOTP_STEP;
+    let skew = TOTP_SKEW as u64;
+    (now.saturating_sub(skew)..=now + skew).find(|step| totp.generate(step * TOTP_STEP) == code)
+}
+
+/// Checks `code` against the user's secret and records its time step, so each code
+/// (and any older one) is accepted at most once.
+async fn check_code(data: &AppState, user: &user::Model, code: &str) -> Result<(), Error> {
+    let secret = user
+        .totp_secret
+        .as_deref()
+        .ok_or_else(|| ApiError::bad_request("2fa has not been set up"))?;
+    let step = matching_step(&totp(secret, &user.username)?, code)
+        .ok_or_else(|| ApiError::unauthorized("invalid code"))?;
+    // recording the step only when it is newer makes concurrent replays lose the race
+    let record = User::update_many()
+        .col_expr(user::Column::TotpLastStep, Expr::value(step))
+        .filter(user::Column::Id.eq(user.id))
+        .filter(
+            Condition::any()
+                .add(user::Column::TotpLastStep.is_null())
+                .add(user::Column::TotpLastStep.lt(step)),
+        )
+        .exec(&data.conn);
+    let result = with_circuit_breaker(&data.circuit_breaker, || record)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not record code"))?;
+    if result.rows_affected == 0 {
+        return Err(ApiError::unauthorized("code already used").into());
+    }
+    Ok(())
+}
+
+async fn find_user(data: &AppState, id: u64) -> Result<user::Model, Error> {
+    with_circuit_breaker(&data.circuit_breaker, || User::find_by_id(id).one(&data.conn))
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not retrieve user"))?
+        .ok_or_else(|| ApiError::unauthorized("user not found").into())
+}
+
+fn token_response(req: &HttpRequest, data: &AppState, token: String, pending_2fa: bool) -> Result<HttpResponse, Error> {
+    let cookie = token_cookie(token.clone(), &data.base_url);
+    negotiate::respond(req, HttpResponse::Ok().cookie(cookie).take(), &TokenResponse { token, pending_2fa })
+}
+
+#[post("/admin/login")]
+async fn login(req: HttpRequest,
+               data: Data<AppState>,
+               body: Body<LoginBody>,
+) -> Result<HttpResponse, Error> {
+    let find = User::find()
+        .filter(user::Column::Username.eq(body.username.as_str()))
+        .one(&data.conn);
+    let user = with_circuit_breaker(&data.circuit_breaker, || find)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not retrieve user"))?
+        .ok_or_else(|| ApiError::unauthorized("invalid credentials"))?;
+    let hash = user
+        .password_hash
+        .as_deref()
+        .ok_or_else(|| ApiError::unauthorized("invalid credentials"))?;
+    let hash = PasswordHash::new(hash)
+        .map_err(|_| ApiError::internal("invalid password hash"))?;
+    Argon2::default()
+        .verify_password(body.password.as_bytes(), &hash)
+        .map_err(|_| ApiError::unauthorized("invalid credentials"))?;
+
+    let (token, pending_2fa) = session_token(&data.jwt_secret, &user)?;
+    token_response(&req, &data, token, pending_2fa)
+}
+
+/// Issues the token for a user who just proved who they are, leaving users with 2FA
+/// enabled pending the TOTP challenge.
+pub fn session_token(secret: &str, user: &user::Model) -> Result<(String, bool), Error> {
+    let (pending_2fa, ttl) = match user.totp_enabled {
+        true => (true, auth::PENDING_TOKEN_TTL_SECS),
+        false => (false, auth::TOKEN_TTL_SECS),
+    };
+    let token = auth::issue_token(secret, user.id, user.is_admin, pending_2fa, ttl)?;
+    Ok((token, pending_2fa))
+}
+
+/// The cookie browsers carry `token` in. Strict, so other sites cannot send requests
+/// with it, and only sent over https when `base_url` is https.
+pub fn token_cookie(token: String, base_url: &str) -> Cookie<'static> {
+    Cookie::build(TOKEN_COOKIE, token)
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .secure(base_url.starts_with("https://"))
+        .finish()
+}
+
+#[post("/admin/2fa/setup")]
+async fn setup(req: HttpRequest,
+               data: Data<AppState>,
+               admin: AdminUser,
+) -> Result<HttpResponse, Error> {
+    let user = find_user(&data, admin.id).await?;
+    if user.totp_enabled {
+        return Err(ApiError::conflict("2fa is already enabled").into());
+    }
+    let secret = Secret::generate_secret().to_encoded().to_string();
+    let provisioning_uri = totp(&secret, &user.username)?.get_url();
+    let save = user::ActiveModel {
+        id: Set(user.id),
+        totp_secret: Set(Some(secret)),
+        totp_last_step: Set(None),
+        ..Default::default()
+    }
+        .update(&data.conn);
+    with_circuit_breaker(&data.circuit_breaker, || save)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not save totp secret"))?;
+    negotiate::respond(&req, HttpResponse::Ok(), &SetupResponse { provisioning_uri })
+}
+
+#[post("/admin/2fa/verify")]
+async fn verify(data: Data<AppState>,
+                admin: AdminUser,
+                body: Body<CodeBody>,
+) -> Result<HttpResponse, Error> {
+    let user = find_user(&data, admin.id).await?;
+    if user.totp_enabled {
+        return Err(ApiError::conflict("2fa is already enabled").into());
+    }
+    check_code(&data, &user, &body.code).await?;
+    let enable = user::ActiveModel {
+        id: Set(user.id),
+        totp_enabled: Set(true),
+        ..Default::default()
+    }
+        .update(&data.conn);
+    with_circuit_breaker(&data.circuit_breaker, || enable)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not enable 2fa"))?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[post("/admin/2fa/challenge")]
+async fn challenge(req: HttpRequest,
+                   data: Data<AppState>,
+                   pending: PendingUser,
+                   body: Body<CodeBody>,
+) -> Result<HttpResponse, Error> {
+    let user = find_user(&data, pending.id).await?;
+    check_code(&data, &user, &body.code).await?;
+    let token = auth::issue_token(&data.jwt_secret, user.id, user.is_admin, false, auth::TOKEN_TTL_SECS)?;
+    token_response(&req, &data, token, false)
+}
+
+#[put("/admin/posts/{id}/status")]
+async fn set_status(data: Data<AppState>,
+                    tenant: Tenant,
+                    admin: AdminUser,
+                    id: web::Path<u64>,
+                    body: Body<StatusBody>,
+) -> Result<HttpResponse, Error> {
+    let status = body.status;
+    let txn = with_circuit_breaker(&data.circuit_breaker, || data.conn.begin())
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not start transaction"))?;
+    let post = Post::find()
+        .filter(post::Column::TenantId.eq(tenant.id))
+        .filter(post::Column::Id.eq(id.into_inner()))
+        .lock_exclusive()
+        .one(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+    Post::update_many()
+        .col_expr(post::Column::Status, Expr::value(status))
+        .filter(post::Column::Id.eq(post.id))
+        .exec(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not update status"))?;
+    audit::post_status_changed(&txn, Some(admin.id), &post, status)
+        .await
+        .map_err(|_| ApiError::database("could not record status change"))?;
+    txn.commit()
+        .await
+        .map_err(|_| ApiError::database("could not commit status change"))?;
+    data.similar_posts.invalidate();
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Sets the status of many posts at once. A revision and an audit event are kept of each
+/// post whose status changes, in the same transaction as the change; ids of other
+/// tenants' or missing posts are skipped.
+#[post("/admin/posts/bulk-status-change")]
+async fn bulk_status_change(req: HttpRequest,
+                            data: Data<AppState>,
+                            tenant: Tenant,
+                            admin: AdminUser,
+                            body: Body<BulkStatusBody>,
+) -> Result<HttpResponse, Error> {
+    let BulkStatusBody { ids, status } = body.into_inner();
+    if ids.is_empty() {
+        return Err(ApiError::bad_request("ids must not be empty").into());
+    }
+    if ids.len() > MAX_BULK_IDS {
+        let message = format!("at most {} posts can be changed at once", MAX_BULK_IDS);
+        return Err(ApiError::validation(message).into());
+    }
+    let txn = with_circuit_breaker(&data.circuit_breaker, || data.conn.begin())
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not start transaction"))?;
+    let changed = Post::find()
+        .filter(post::Column::TenantId.eq(tenant.id))
+        .filter(post::Column::Id.is_in(ids))
+        .filter(post::Column::Status.ne(status))
+        .lock_exclusive()
+        .all(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve posts"))?;
+    for post in &changed {
+        api::save_revision(&txn, admin.id, post).await?;
+        audit::post_status_changed(&txn, Some(admin.id), post, status)
+            .await
+            .map_err(|_| ApiError::database("could not record status change"))?;
+    }
+    let updated = match changed.is_empty() {
+        true => 0,
+        false => {
+            Post::update_many()
+                .col_expr(post::Column::Status, Expr::value(status))
+                .filter(post::Column::Id.is_in(changed.iter().map(|post| post.id)))
+                .exec(&txn)
+                .await
+                .map_err(|_| ApiError::database("could not update status"))?
+                .rows_affected
+        }
+    };
+    txn.commit()
+        .await
+        .map_err(|_| ApiError::database("could not commit status change"))?;
+    if updated > 0 {
+        data.similar_posts.invalidate();
+    }
+    negotiate::respond(&req, HttpResponse::Ok(), &BulkStatusResponse { updated })
+}
+
+#[post("/admin/posts/{id}/feature")]
+async fn feature(data: Data<AppState>,
+                 tenant: Tenant,
+                 _admin: AdminUser,
+                 id: web::Path<u64>,
+                 body: Body<FeatureBody>,
+) -> Result<HttpResponse, Error> {
+    let until = body.into_inner().until;
+    if until.is_some_and(|until| until <= Utc::now()) {
+        return Err(ApiError::validation("until must be in the future").into());
+    }
+    set_featured(&data, &tenant.id, id.into_inner(), true, until).await
+}
+
+#[delete("/admin/posts/{id}/feature")]
+async fn unfeature(data: Data<AppState>,
+                   tenant: Tenant,
+                   _admin: AdminUser,
+                   id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
+    set_featured(&data, &tenant.id, id.into_inner(), false, None).await
+}
+
+async fn set_featured(data: &AppState,
+                      tenant_id: &str,
+                      id: u64,
+                      featured: bool,
+                      until: Option<DateTime<Utc>>,
+) -> Result<HttpResponse, Error> {
+    let update = Post::update_many()
+        .col_expr(post::Column::IsFeatured, Expr::value(featured))
+        .col_expr(post::Column::FeaturedUntil, Expr::value(until))
+        .filter(post::Column::TenantId.eq(tenant_id))
+        .filter(post::Column::Id.eq(id))
+        .exec(&data.conn);
+    let result = with_circuit_breaker(&data.circuit_breaker, || update)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not update featuring"))?;
+    if result.rows_affected == 0 {
+        return Err(ApiError::post_not_found().into());
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Archives the post at `at` if it is still published then.
+#[post("/admin/posts/{id}/expiry")]
+async fn set_expiry(data: Data<AppState>,
+                    tenant: Tenant,
+                    _admin: AdminUser,
+                    id: web::Path<u64>,
+                    body: Body<ExpiryBody>,
+) -> Result<HttpResponse, Error> {
+    let at = body.into_inner().at;
+    if at <= Utc::now() {
+        return Err(ApiError::validation("at must be in the future").into());
+    }
+    update_expiry(&data, &tenant.id, id.into_inner(), Some(at)).await
+}
+
+#[delete("/admin/posts/{id}/expiry")]
+async fn clear_expiry(data: Data<AppState>,
+                      tenant: Tenant,
+                      _admin: AdminUser,
+                      id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
+    update_expiry(&data, &tenant.id, id.into_inner(), None).await
+}
+
+async fn update_expiry(data: &AppState,
+                       tenant_id: &str,
+                       id: u64,
+                       at: Option<DateTime<Utc>>,
+) -> Result<HttpResponse, Error> {
+    let update = Post::update_many()
+        .col_expr(post::Column::ExpiresAt, Expr::value(at))
+        .filter(post::Column::TenantId.eq(tenant_id))
+        .filter(post::Column::Id.eq(id))
+        .exec(&data.conn);
+    let result = with_circuit_breaker(&data.circuit_breaker, || update)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not update expiry"))?;
+    if result.rows_affected == 0 {
+        return Err(ApiError::post_not_found().into());
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(login);
+    cfg.service(setup);
+    cfg.service(verify);
+    cfg.service(challenge);
+    cfg.service(set_status);
+    cfg.service(bulk_status_change);
+    cfg.service(feature);
+    cfg.service(unfeature);
+    cfg.service(set_expiry);
+    cfg.service(clear_expiry);
+}
@@ -0,0 +1,257 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix_web::{get, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use sea_orm::{entity::*, query::*, sea_query::Expr, DatabaseConnection, DbErr, FromQueryResult};
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use entity::bookmark;
+use entity::post;
+use entity::post::{Entity as Post, PostStatus};
+use entity::post_revision;
+use entity::post_revision::Entity as PostRevision;
+
+use crate::api_error::ApiError;
+use crate::auth::AdminUser;
+use crate::tenants::{tenanted_query, Tenant};
+use crate::{encryption, negotiate, AppState};
+
+const DEFAULT_TOP_WORDS: usize = 50;
+const BOOKMARK_WEIGHT: f64 = 2.0;
+const CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+/// Used when `STOP_WORDS` is not set.
+const DEFAULT_STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have", "i",
+    "in", "is", "it", "its", "of", "on", "or", "that", "the", "this", "to", "was", "were",
+    "will", "with", "you",
+];
+
+#[derive(Debug, Deserialize)]
+pub struct WordFrequencyParams {
+    n: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WordCount {
+    word: String,
+    count: u64,
+}
+
+/// Engagement figures for one post on the admin dashboard, scored as
+/// `(reactions * 3 + comments * 5 + bookmarks * 2) / (1 + age in days)`.
+///
+/// Views, reactions and comments aren't recorded by this app yet, so those counts are
+/// always 0 and only bookmarks contribute to the score for now.
+#[derive(Debug, Serialize, FromQueryResult)]
+pub struct PostEngagement {
+    post_id: u64,
+    title: String,
+    view_count: i64,
+    reaction_count: i64,
+    comment_count: i64,
+    bookmark_count: i64,
+    engagement_score: f64,
+}
+
+/// Counts sorted most frequent first, with the time they were computed.
+type CachedCounts = (Instant, Arc<Vec<WordCount>>);
+
+/// Word counts over the published posts of each tenant, recomputed at most every ten
+/// minutes.
+#[derive(Debug, Clone)]
+pub struct WordFrequencyCache {
+    stop_words: Arc<HashSet<String>>,
+    counts: Arc<Mutex<HashMap<String, CachedCounts>>>,
+}
+
+impl WordFrequencyCache {
+    /// Reads the comma-separated stop word list from `STOP_WORDS`.
+    pub fn from_env() -> Self {
+        let stop_words = match env::var("STOP_WORDS") {
+            Ok(words) => words.split(',').map(|word| word.trim().to_lowercase()).collect(),
+            Err(_) => DEFAULT_STOP_WORDS.iter().map(|word| word.to_string()).collect(),
+        };
+        WordFrequencyCache { stop_words: Arc::new(stop_words), counts: Arc::default() }
+    }
+
+    /// Words left out of counts, and of the vectors `similarity` compares.
+    pub fn stop_words(&self) -> &HashSet<String> {
+        &self.stop_words
+    }
+
+    /// Returns all counts of `tenant_id`, most frequent first.
+    async fn counts(&self, conn: &DatabaseConnection, tenant_id: &str) -> Result<Arc<Vec<WordCount>>, DbErr> {
+        if let Some((computed_at, counts)) = self.counts.lock().unwrap().get(tenant_id) {
+            if computed_at.elapsed() < CACHE_TTL {
+                return Ok(counts.clone());
+            }
+        }
+        let texts: Vec<String> = tenanted_query::<Post>(tenant_id)
+            .filter(post::Column::Status.eq(PostStatus::Published))
+            // encrypted text would only contribute noise
+            .filter(post::Column::IsEncrypted.eq(false))
+            .select_only()
+            .column(post::Column::Text)
+            .into_tuple()
+            .all(conn)
+            .await?;
+        let counts = Arc::new(count_words(texts.iter().map(String::as_str), &self.stop_words));
+        self.counts.lock().unwrap().insert(tenant_id.to_owned(), (Instant::now(), counts.clone()));
+        Ok(counts)
+    }
+}
+
+/// Splits `text` on Unicode word boundaries and lowercases the words, dropping stop words.
+pub fn tokenize<'a>(text: &'a str, stop_words: &'a HashSet<String>) -> impl Iterator<Item = String> + 'a {
+    text.unicode_words()
+        .map(str::to_lowercase)
+        .filter(|word| !stop_words.contains(word))
+}
+
+fn count_words<'a>(texts: impl Iterator<Item = &'a str>, stop_words: &HashSet<String>) -> Vec<WordCount> {
+    let mut counts: HashMap<String, u64> = HashMap::new();
+    for text in texts {
+        for word in tokenize(text, stop_words) {
+            *counts.entry(word).or_default() += 1;
+        }
+    }
+    let mut counts: Vec<WordCount> = counts
+        .into_iter()
+        .map(|(word, count)| WordCount { word, count })
+        .collect();
+    // ties are broken alphabetically so the order is stable between recomputations
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.word.cmp(&b.word)));
+    counts
+}
+
+#[get("/api/v1/posts/analytics/word-frequency")]
+async fn word_frequency(req: HttpRequest,
+                        data: Data<AppState>,
+                        tenant: Tenant,
+                        params: web::Query<WordFrequencyParams>,
+) -> Result<HttpResponse, Error> {
+    let counts = data
+        .word_frequency
+        .counts(&data.conn, &tenant.id)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve posts"))?;
+    let n = params.n.unwrap_or(DEFAULT_TOP_WORDS);
+    negotiate::respond(&req, HttpResponse::Ok(), &counts[..n.min(counts.len())])
+}
+
+#[derive(Debug, Serialize)]
+struct LengthPoint {
+    /// `None` for the current content of a post that has no revisions.
+    revision_id: Option<u64>,
+    revision_date: chrono::DateTime<chrono::Utc>,
+    char_count: usize,
+    word_count: usize,
+}
+
+impl LengthPoint {
+    fn new(revision_id: Option<u64>, revision_date: chrono::DateTime<chrono::Utc>, text: &str) -> Self {
+        LengthPoint {
+            revision_id,
+            revision_date,
+            char_count: text.chars().count(),
+            word_count: text.unicode_words().count(),
+        }
+    }
+}
+
+/// Revisions keep the text as it was stored, so encrypted ones are opened when the key
+/// allows; text that does not open was stored in plain.
+fn revision_text(data: &AppState, text: String) -> String {
+    data.encryption_key
+        .as_ref()
+        .and_then(|key| encryption::open(&text, key).ok())
+        .unwrap_or(text)
+}
+
+/// The length of each revision of a post, oldest first, or of the post itself when it
+/// has never been updated.
+#[get("/api/v1/posts/{id}/stats/length-history")]
+async fn length_history(req: HttpRequest,
+                        data: Data<AppState>,
+                        tenant: Tenant,
+                        id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
+    let conn = &data.conn;
+    let mut post = tenanted_query::<Post>(&tenant.id)
+        .filter(post::Column::Id.eq(id.into_inner()))
+        .one(conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+    let revisions = PostRevision::find()
+        .filter(post_revision::Column::PostId.eq(post.id))
+        .order_by_asc(post_revision::Column::CreatedAt)
+        .order_by_asc(post_revision::Column::Id)
+        .all(conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve revisions"))?;
+    let history: Vec<LengthPoint> = match revisions.is_empty() {
+        true => {
+            data.reveal(&mut post)?;
+            vec![LengthPoint::new(None, post.created_at, &post.text)]
+        }
+        false => revisions
+            .into_iter()
+            .map(|revision| {
+                let text = revision_text(&data, revision.text);
+                LengthPoint::new(Some(revision.id), revision.created_at, &text)
+            })
+            .collect(),
+    };
+    negotiate::respond(&req, HttpResponse::Ok(), &history)
+}
+
+/// Scores every post of `tenant_id` in one grouped query, best first.
+async fn post_engagement(conn: &DatabaseConnection, tenant_id: &str) -> Result<Vec<PostEngagement>, DbErr> {
+    // the reaction and comment terms are always 0 until those are recorded; the `e`
+    // literal keeps MySQL in floating point instead of DECIMAL
+    let score = format!(
+        "COUNT(bookmarks.user_id) * {:e} / (1 + TIMESTAMPDIFF(DAY, posts.created_at, NOW()))",
+        BOOKMARK_WEIGHT,
+    );
+    tenanted_query::<Post>(tenant_id)
+        .select_only()
+        .column_as(post::Column::Id, "post_id")
+        .column(post::Column::Title)
+        .column_as(Expr::val(0i64), "view_count")
+        .column_as(Expr::val(0i64), "reaction_count")
+        .column_as(Expr::val(0i64), "comment_count")
+        .column_as(bookmark::Column::UserId.count(), "bookmark_count")
+        .column_as(Expr::cust(&score), "engagement_score")
+        .join(JoinType::LeftJoin, bookmark::Relation::Post.def().rev())
+        .group_by(post::Column::Id)
+        .group_by(post::Column::Title)
+        .group_by(post::Column::CreatedAt)
+        .order_by_desc(Expr::cust("engagement_score"))
+        .order_by_asc(post::Column::Id)
+        .into_model::<PostEngagement>()
+        .all(conn)
+        .await
+}
+
+#[get("/admin/analytics/dashboard")]
+async fn dashboard(req: HttpRequest,
+                   data: Data<AppState>,
+                   tenant: Tenant,
+                   _admin: AdminUser,
+) -> Result<HttpResponse, Error> {
+    let posts = post_engagement(&data.conn, &tenant.id)
+        .await
+        .map_err(|_| ApiError::database("could not compute engagement"))?;
+    negotiate::respond(&req, HttpResponse::Ok(), &posts)
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(word_frequency);
+    cfg.service(dashboard);
+    cfg.service(length_history);
+}
@@ -0,0 +1,112 @@
+use actix_web::{delete, get, post, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use sea_orm::{entity::*, query::*};
+use serde::Deserialize;
+
+use entity::annotation;
+use entity::annotation::Entity as Annotation;
+use entity::post::{self, Entity as Post};
+
+use crate::api_error::ApiError;
+use crate::auth::AuthUser;
+use crate::circuit_breaker::with_circuit_breaker;
+use crate::negotiate::{self, Body};
+use crate::tenants::{tenanted_query, Tenant};
+use crate::AppState;
+
+/// A highlighted range; offsets are UTF-16 code units into the post text, as
+/// reported by the browser's `Selection`/`Range` API.
+#[derive(Debug, Deserialize)]
+pub struct AnnotationBody {
+    start_offset: u64,
+    end_offset: u64,
+    note: Option<String>,
+}
+
+/// Returns the annotations `user_id` made on `post_id`, in reading order.
+pub async fn find_annotations(
+    data: &AppState,
+    user_id: u64,
+    post_id: u64,
+) -> Result<Vec<annotation::Model>, Error> {
+    let find = Annotation::find()
+        .filter(annotation::Column::UserId.eq(user_id))
+        .filter(annotation::Column::PostId.eq(post_id))
+        .order_by_asc(annotation::Column::StartOffset)
+        .all(&data.conn);
+    with_circuit_breaker(&data.circuit_breaker, || find)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not retrieve annotations").into())
+}
+
+#[post("/api/v1/posts/{id}/annotations")]
+async fn create_annotation(req: HttpRequest,
+                           data: Data<AppState>,
+                           tenant: Tenant,
+                           user: AuthUser,
+                           id: web::Path<u64>,
+                           body: Body<AnnotationBody>,
+) -> Result<HttpResponse, Error> {
+    let conn = &data.conn;
+    let breaker = &data.circuit_breaker;
+    let find = tenanted_query::<Post>(&tenant.id).filter(post::Column::Id.eq(id.into_inner())).one(conn);
+    let post = with_circuit_breaker(breaker, || find)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+
+    let body = body.into_inner();
+    let text_len = post.text.encode_utf16().count() as u64;
+    if body.start_offset >= body.end_offset || body.end_offset > text_len {
+        return Err(ApiError::validation("offsets are outside of the post text").into());
+    }
+
+    let save = annotation::ActiveModel {
+        user_id: Set(user.id),
+        post_id: Set(post.id),
+        start_offset: Set(body.start_offset),
+        end_offset: Set(body.end_offset),
+        note: Set(body.note),
+        created_at: Set(chrono::Utc::now()),
+        ..Default::default()
+    }
+        .insert(conn);
+    let annotation = with_circuit_breaker(breaker, || save)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not save annotation"))?;
+    negotiate::respond(&req, HttpResponse::Created(), &annotation)
+}
+
+#[get("/api/v1/posts/{id}/annotations")]
+async fn list_annotations(req: HttpRequest,
+                          data: Data<AppState>,
+                          user: AuthUser,
+                          id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
+    let annotations = find_annotations(&data, user.id, id.into_inner()).await?;
+    negotiate::respond(&req, HttpResponse::Ok(), &annotations)
+}
+
+#[delete("/api/v1/annotations/{id}")]
+async fn delete_annotation(data: Data<AppState>,
+                           user: AuthUser,
+                           id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
+    let remove = Annotation::delete_many()
+        .filter(annotation::Column::Id.eq(id.into_inner()))
+        .filter(annotation::Column::UserId.eq(user.id))
+        .exec(&data.conn);
+    let result = with_circuit_breaker(&data.circuit_breaker, || remove)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not delete annotation"))?;
+    if result.rows_affected == 0 {
+        return Err(ApiError::not_found("annotation not found").into());
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(create_annotation);
+    cfg.service(list_annotations);
+    cfg.service(delete_annotation);
+}
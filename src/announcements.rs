@@ -0,0 +1,187 @@
+use actix_web::{delete, get, patch, post, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use sea_orm::{entity::*, query::*, sea_query::Expr, DatabaseConnection, DatabaseTransaction};
+use serde::Deserialize;
+
+use entity::site_announcement;
+use entity::site_announcement::Entity as SiteAnnouncement;
+
+use crate::api_error::ApiError;
+use crate::auth::AdminUser;
+use crate::negotiate::{self, Body};
+use crate::tenants::Tenant;
+use crate::AppState;
+
+const DEFAULT_BG_COLOR: &str = "#fff3cd";
+
+#[derive(Debug, Deserialize)]
+pub struct CreateInput {
+    message: String,
+    #[serde(default)]
+    active: bool,
+    bg_color: Option<String>,
+}
+
+/// Body of `PATCH /admin/announcements/{id}`; fields left out keep their current value.
+#[derive(Debug, Deserialize)]
+pub struct UpdateInput {
+    message: Option<String>,
+    active: Option<bool>,
+    bg_color: Option<String>,
+}
+
+/// The announcement to show on the tenant's full pages, if one is active.
+pub async fn find_active(conn: &DatabaseConnection,
+                         tenant: &Tenant,
+) -> Result<Option<site_announcement::Model>, Error> {
+    SiteAnnouncement::find()
+        .filter(site_announcement::Column::TenantId.eq(tenant.id.as_str()))
+        .filter(site_announcement::Column::Active.eq(true))
+        .one(conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve announcement").into())
+}
+
+/// Accepts `#rgb` and `#rrggbb` only, since the color goes into a `style` attribute.
+fn validate_color(color: &str) -> Result<(), Error> {
+    let digits = color.strip_prefix('#').unwrap_or_default();
+    if !matches!(digits.len(), 3 | 6) || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ApiError::validation("bg_color must be a hex color such as #fff3cd").into());
+    }
+    Ok(())
+}
+
+/// Clears the active flag of every announcement of the tenant, so another can take it.
+async fn deactivate_all(txn: &DatabaseTransaction, tenant: &Tenant) -> Result<(), Error> {
+    SiteAnnouncement::update_many()
+        .col_expr(site_announcement::Column::Active, Expr::value(false))
+        .filter(site_announcement::Column::TenantId.eq(tenant.id.as_str()))
+        .filter(site_announcement::Column::Active.eq(true))
+        .exec(txn)
+        .await
+        .map_err(|_| ApiError::database("could not deactivate announcements"))?;
+    Ok(())
+}
+
+#[get("/admin/announcements")]
+async fn list_announcements(req: HttpRequest,
+                            data: Data<AppState>,
+                            tenant: Tenant,
+                            _admin: AdminUser,
+) -> Result<HttpResponse, Error> {
+    let announcements = SiteAnnouncement::find()
+        .filter(site_announcement::Column::TenantId.eq(tenant.id))
+        .order_by_desc(site_announcement::Column::Id)
+        .all(&data.conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve announcements"))?;
+    negotiate::respond(&req, HttpResponse::Ok(), &announcements)
+}
+
+/// Creates an announcement; an active one replaces the announcement active before.
+#[post("/admin/announcements")]
+async fn create_announcement(req: HttpRequest,
+                             data: Data<AppState>,
+                             tenant: Tenant,
+                             _admin: AdminUser,
+                             body: Body<CreateInput>,
+) -> Result<HttpResponse, Error> {
+    let input = body.into_inner();
+    let bg_color = input.bg_color.unwrap_or_else(|| DEFAULT_BG_COLOR.to_owned());
+    validate_color(&bg_color)?;
+    let txn = data
+        .conn
+        .begin()
+        .await
+        .map_err(|_| ApiError::database("could not start transaction"))?;
+    if input.active {
+        deactivate_all(&txn, &tenant).await?;
+    }
+    let announcement = site_announcement::ActiveModel {
+        tenant_id: Set(tenant.id),
+        message: Set(input.message),
+        active: Set(input.active),
+        bg_color: Set(bg_color),
+        created_at: Set(chrono::Utc::now()),
+        ..Default::default()
+    }
+        .insert(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not save announcement"))?;
+    txn.commit()
+        .await
+        .map_err(|_| ApiError::database("could not commit announcement"))?;
+    negotiate::respond(&req, HttpResponse::Created(), &announcement)
+}
+
+#[patch("/admin/announcements/{id}")]
+async fn update_announcement(req: HttpRequest,
+                             data: Data<AppState>,
+                             tenant: Tenant,
+                             _admin: AdminUser,
+                             id: web::Path<u64>,
+                             body: Body<UpdateInput>,
+) -> Result<HttpResponse, Error> {
+    let input = body.into_inner();
+    if let Some(bg_color) = &input.bg_color {
+        validate_color(bg_color)?;
+    }
+    let txn = data
+        .conn
+        .begin()
+        .await
+        .map_err(|_| ApiError::database("could not start transaction"))?;
+    let current = SiteAnnouncement::find_by_id(id.into_inner())
+        .filter(site_announcement::Column::TenantId.eq(tenant.id.as_str()))
+        .lock_exclusive()
+        .one(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve announcement"))?
+        .ok_or_else(|| ApiError::not_found("announcement not found"))?;
+    if input.active == Some(true) && !current.active {
+        deactivate_all(&txn, &tenant).await?;
+    }
+    let mut announcement: site_announcement::ActiveModel = current.into();
+    if let Some(message) = input.message {
+        announcement.message = Set(message);
+    }
+    if let Some(active) = input.active {
+        announcement.active = Set(active);
+    }
+    if let Some(bg_color) = input.bg_color {
+        announcement.bg_color = Set(bg_color);
+    }
+    let announcement = announcement
+        .update(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not save announcement"))?;
+    txn.commit()
+        .await
+        .map_err(|_| ApiError::database("could not commit announcement"))?;
+    negotiate::respond(&req, HttpResponse::Ok(), &announcement)
+}
+
+#[delete("/admin/announcements/{id}")]
+async fn delete_announcement(data: Data<AppState>,
+                             tenant: Tenant,
+                             _admin: AdminUser,
+                             id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
+    let result = SiteAnnouncement::delete_many()
+        .filter(site_announcement::Column::TenantId.eq(tenant.id))
+        .filter(site_announcement::Column::Id.eq(id.into_inner()))
+        .exec(&data.conn)
+        .await
+        .map_err(|_| ApiError::database("could not delete announcement"))?;
+    if result.rows_affected == 0 {
+        return Err(ApiError::not_found("announcement not found").into());
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_announcements);
+    cfg.service(create_announcement);
+    cfg.service(update_announcement);
+    cfg.service(delete_announcement);
+}
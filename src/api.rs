@@ -0,0 +1,646 @@
+use std::collections::HashSet;
+
+use actix_web::{get, http::header, patch, post, route, web, Error, HttpRequest, HttpResponse, HttpResponseBuilder};
+use actix_web::web::Data;
+use sea_orm::{entity::*, query::*, DatabaseTransaction, DbBackend};
+use sea_orm::sea_query::{CaseStatement, Expr, OnConflict};
+use serde::{Deserialize, Serialize};
+
+use entity::post::{self, PostStatus};
+use entity::post::Entity as Post;
+use entity::post_permission::Permission;
+use entity::post_revision;
+
+use crate::api_error::{ApiError, ApiErrorCode};
+use crate::auth::{AdminUser, AuthUser};
+use crate::broadcast::{PostEvent, PostEventKind};
+use crate::circuit_breaker::with_circuit_breaker;
+use crate::jobs::Job;
+use crate::negotiate::{self, Body};
+use crate::tenants::{tenanted_query, Tenant};
+use crate::{audit, integrity, payload_errors, permissions, stable_hash, suggestions};
+use crate::{listed_status, AppState, Params, CLAMPED_HEADER};
+
+pub mod proto {
+    include!(concat!(env!("OUT_DIR"), "/posts.rs"));
+}
+
+const PROTOBUF: &str = "application/x-protobuf";
+/// Length of the `posts.title` column, in characters.
+pub const MAX_TITLE_LEN: usize = 255;
+/// Size of the `posts.text` column, in bytes.
+pub const MAX_TEXT_LEN: usize = 65_535;
+/// Most posts one upsert request may carry.
+const MAX_UPSERT_BATCH: usize = 100;
+
+/// Body of `PATCH /api/v1/posts/{id}`; fields left out keep their current value.
+#[derive(Debug, Deserialize)]
+pub struct PatchPostInput {
+    title: Option<String>,
+    text: Option<String>,
+    status: Option<PostStatus>,
+}
+
+/// Body of `PATCH /api/v1/posts/{id}/title`.
+#[derive(Debug, Deserialize)]
+pub struct TitleInput {
+    title: String,
+}
+
+/// Body of `PATCH /api/v1/posts/{id}/text`.
+#[derive(Debug, Deserialize)]
+pub struct TextInput {
+    text: String,
+}
+
+/// One element of the body of `POST /api/v1/posts/upsert`.
+#[derive(Debug, Deserialize)]
+pub struct UpsertPostInput {
+    /// The post's id in the system it is synced from; posts without one are always
+    /// created.
+    pub external_id: Option<String>,
+    pub title: String,
+    pub text: String,
+    #[serde(default)]
+    pub status: PostStatus,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum UpsertAction {
+    Created,
+    Updated,
+}
+
+#[derive(Debug, Serialize)]
+struct UpsertResult {
+    external_id: Option<String>,
+    post_id: u64,
+    action: UpsertAction,
+}
+
+/// Body of `POST /api/v1/posts/reorder`: post ids, first to last.
+#[derive(Debug, Deserialize)]
+pub struct ReorderInput {
+    ordered_ids: Vec<u64>,
+}
+
+/// One page of posts, as returned by the paginated JSON endpoints.
+#[derive(Debug, Serialize)]
+pub struct PostPage {
+    pub posts: Vec<post::Model>,
+    pub page: usize,
+    pub posts_per_page: usize,
+    pub num_pages: u64,
+}
+
+impl From<&post::Model> for proto::Post {
+    fn from(post: &post::Model) -> Self {
+        proto::Post { id: post.id, title: post.title.clone(), text: post.text.clone() }
+    }
+}
+
+impl From<&PostPage> for proto::PostList {
+    fn from(page: &PostPage) -> Self {
+        proto::PostList {
+            posts: page.posts.iter().map(proto::Post::from).collect(),
+            page: page.page as u64,
+            posts_per_page: page.posts_per_page as u64,
+            num_pages: page.num_pages,
+        }
+    }
+}
+
+/// Strong ETag of `post`, shared by reads and the `If-Match` check on writes.
+pub fn etag(post: &post::Model) -> String {
+    let bytes = serde_json::to_vec(post).unwrap_or_default();
+    format!("\"{:016x}\"", stable_hash::fnv1a(bytes))
+}
+
+/// Returns whether the request's `If-Match` header, if any, matches `etag`.
+fn if_match(req: &HttpRequest, etag: &str) -> bool {
+    let header = match req.headers().get(header::IF_MATCH) {
+        Some(header) => header,
+        None => return true,
+    };
+    header.to_str().is_ok_and(|value| {
+        value
+            .split(',')
+            .map(str::trim)
+            .any(|candidate| candidate == "*" || candidate == etag)
+    })
+}
+
+fn accepts_protobuf(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains(PROTOBUF))
+}
+
+/// Serializes `body` as protobuf when the client asks for it, otherwise as
+/// MessagePack or JSON.
+fn negotiate_proto<'a, T, P>(req: &HttpRequest,
+                              mut builder: HttpResponseBuilder,
+                              body: &'a T,
+) -> Result<HttpResponse, Error>
+    where T: Serialize,
+          P: prost::Message + From<&'a T>,
+{
+    if accepts_protobuf(req) {
+        Ok(builder
+            .content_type(PROTOBUF)
+            .body(P::from(body).encode_to_vec()))
+    } else {
+        negotiate::respond(req, builder, body)
+    }
+}
+
+/// Starts a 200 response for `page` with RFC 5988 `Link` headers to the previous and next
+/// pages, keeping the other `params`, plus the `X-Total-Count` and `X-Total-Pages` totals and, when `clamped`, the
+/// clamped page size warning.
+pub fn paginated(req: &HttpRequest,
+                 base_url: &str,
+                 page: &PostPage,
+                 params: &Params,
+                 num_items: u64,
+                 clamped: bool,
+) -> HttpResponseBuilder {
+    let link = |number: usize, rel: &str| {
+        let params = Params {
+            page: Some(number),
+            posts_per_page: Some(page.posts_per_page),
+            status: params.status,
+            use_custom_order: params.use_custom_order,
+        };
+        let query = serde_urlencoded::to_string(&params).unwrap_or_default();
+        format!("<{}{}?{}>; rel=\"{}\"", base_url.trim_end_matches('/'), req.path(), query, rel)
+    };
+    let mut links = Vec::new();
+    if (page.page as u64) < page.num_pages {
+        links.push(link(page.page + 1, "next"));
+    }
+    if page.page > 1 {
+        links.push(link(page.page - 1, "prev"));
+    }
+
+    let mut builder = HttpResponse::Ok();
+    if !links.is_empty() {
+        builder.insert_header((header::LINK, links.join(", ")));
+    }
+    builder
+        .insert_header(("X-Total-Count", num_items))
+        .insert_header(("X-Total-Pages", page.num_pages));
+    if clamped {
+        builder.insert_header((CLAMPED_HEADER, "true"));
+    }
+    builder
+}
+
+#[route("/api/v1/posts", method = "GET", method = "HEAD")]
+async fn list_posts(req: HttpRequest,
+                    data: Data<AppState>,
+                    tenant: Tenant,
+                    user: Option<AuthUser>,
+                    params: web::Query<Params>,
+) -> Result<HttpResponse, Error> {
+    let conn = &data.conn;
+    let status = listed_status(params.status, user.as_ref())?;
+    let page = params.page.unwrap_or(1).max(1);
+    let (posts_per_page, clamped) = data.posts_per_page(params.posts_per_page)?;
+    data.page_size_limits.check_depth(page, posts_per_page)?;
+    let paginator = params
+        .order(tenanted_query::<Post>(&tenant.id).filter(post::Column::Status.eq(status)))
+        .paginate(conn, posts_per_page as u64);
+    let breaker = &data.circuit_breaker;
+    let totals = with_circuit_breaker(breaker, || paginator.num_items_and_pages())
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not count posts"))?;
+    let mut posts = with_circuit_breaker(breaker, || paginator.fetch_page((page - 1) as u64))
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not retrieve posts"))?;
+    for post in posts.iter_mut() {
+        integrity::verify(post)?;
+        data.reveal(post)?;
+    }
+    let page = PostPage { posts, page, posts_per_page, num_pages: totals.number_of_pages };
+    let builder = paginated(&req, &data.base_url, &page, &params, totals.number_of_items, clamped);
+    negotiate_proto::<_, proto::PostList>(&req, builder, &page)
+}
+
+/// The published posts of `tenant_id` in random order; `.one()` picks one of them.
+pub fn random_published(backend: DbBackend, tenant_id: &str) -> Select<Post> {
+    let random = match backend {
+        DbBackend::MySql => "RAND()",
+        DbBackend::Postgres | DbBackend::Sqlite => "RANDOM()",
+    };
+    tenanted_query::<Post>(tenant_id)
+        .filter(post::Column::Status.eq(PostStatus::Published))
+        .order_by(Expr::cust(random), Order::Asc)
+}
+
+/// A published post picked at random, for "feeling lucky" links.
+#[get("/api/v1/posts/random")]
+async fn random_post(req: HttpRequest,
+                     data: Data<AppState>,
+                     tenant: Tenant,
+) -> Result<HttpResponse, Error> {
+    let conn = &data.conn;
+    let query = random_published(conn.get_database_backend(), &tenant.id);
+    let mut post = with_circuit_breaker(&data.circuit_breaker, || query.one(conn))
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+    integrity::verify(&post)?;
+    data.reveal(&mut post)?;
+    negotiate_proto::<_, proto::Post>(&req, HttpResponse::Ok(), &post)
+}
+
+#[route("/api/v1/posts/{id}", method = "GET", method = "HEAD")]
+async fn get_post(req: HttpRequest,
+                  data: Data<AppState>,
+                  tenant: Tenant,
+                  user: Option<AuthUser>,
+                  id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
+    let id = id.into_inner();
+    let find = || tenanted_query::<Post>(&tenant.id).filter(post::Column::Id.eq(id)).one(&data.conn);
+    let mut post = with_circuit_breaker(&data.circuit_breaker, find)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+    permissions::require_read(&data.conn, user.as_ref(), &post).await?;
+    integrity::verify(&post)?;
+    // the tag covers the stored row, which is what PATCH compares it against
+    let mut builder = HttpResponse::Ok();
+    builder.insert_header((header::ETAG, etag(&post)));
+    data.reveal(&mut post)?;
+    negotiate_proto::<_, proto::Post>(&req, builder, &post)
+}
+
+/// The text of a post as it was written, for clients that render it themselves.
+#[get("/api/v1/posts/{id}/raw")]
+async fn raw_post(data: Data<AppState>,
+                  tenant: Tenant,
+                  user: AuthUser,
+                  id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
+    let id = id.into_inner();
+    let find = || tenanted_query::<Post>(&tenant.id).filter(post::Column::Id.eq(id)).one(&data.conn);
+    let mut post = with_circuit_breaker(&data.circuit_breaker, find)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+    permissions::require_read(&data.conn, Some(&user), &post).await?;
+    integrity::verify(&post)?;
+    data.reveal(&mut post)?;
+    // header values are ASCII, so titles whose slug isn't fall back to the id
+    let slug = Some(suggestions::slugify(&post.title))
+        .filter(|slug| !slug.is_empty() && slug.is_ascii())
+        .unwrap_or_else(|| format!("post-{}", post.id));
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .insert_header((header::CONTENT_DISPOSITION, format!("inline; filename=\"{}.md\"", slug)))
+        .body(post.text))
+}
+
+/// Rejects titles and texts that are empty or too long for their columns with 422.
+fn validate(input: &PatchPostInput) -> Result<(), Error> {
+    if let Some(title) = &input.title {
+        if title.trim().is_empty() {
+            return Err(payload_errors::unprocessable(Some("title".to_owned()), "must not be empty"));
+        }
+        if title.chars().count() > MAX_TITLE_LEN {
+            let message = format!("must be at most {} characters", MAX_TITLE_LEN);
+            return Err(payload_errors::unprocessable(Some("title".to_owned()), message));
+        }
+    }
+    if let Some(text) = &input.text {
+        if text.trim().is_empty() {
+            return Err(payload_errors::unprocessable(Some("text".to_owned()), "must not be empty"));
+        }
+        if text.len() > MAX_TEXT_LEN {
+            let message = format!("must be at most {} bytes", MAX_TEXT_LEN);
+            return Err(payload_errors::unprocessable(Some("text".to_owned()), message));
+        }
+    }
+    Ok(())
+}
+
+#[patch("/api/v1/posts/{id}")]
+async fn patch_post(req: HttpRequest,
+                    data: Data<AppState>,
+                    tenant: Tenant,
+                    user: AuthUser,
+                    id: web::Path<u64>,
+                    body: Body<PatchPostInput>,
+) -> Result<HttpResponse, Error> {
+    let input = body.into_inner();
+    if input.title.is_none() && input.text.is_none() && input.status.is_none() {
+        return Err(ApiError::bad_request("patch must set at least one field").into());
+    }
+    apply_patch(&req, &data, &tenant, user, id.into_inner(), input).await
+}
+
+/// Inline edit of just the title.
+#[patch("/api/v1/posts/{id}/title")]
+async fn patch_title(req: HttpRequest,
+                     data: Data<AppState>,
+                     tenant: Tenant,
+                     user: AuthUser,
+                     id: web::Path<u64>,
+                     body: Body<TitleInput>,
+) -> Result<HttpResponse, Error> {
+    let input = PatchPostInput { title: Some(body.into_inner().title), text: None, status: None };
+    apply_patch(&req, &data, &tenant, user, id.into_inner(), input).await
+}
+
+/// Inline edit of just the text.
+#[patch("/api/v1/posts/{id}/text")]
+async fn patch_text(req: HttpRequest,
+                    data: Data<AppState>,
+                    tenant: Tenant,
+                    user: AuthUser,
+                    id: web::Path<u64>,
+                    body: Body<TextInput>,
+) -> Result<HttpResponse, Error> {
+    let input = PatchPostInput { title: None, text: Some(body.into_inner().text), status: None };
+    apply_patch(&req, &data, &tenant, user, id.into_inner(), input).await
+}
+
+/// Sets the fields of post `id` that `input` gives, keeping a revision of the old content
+/// and honouring `If-Match`.
+async fn apply_patch(req: &HttpRequest,
+                     data: &AppState,
+                     tenant: &Tenant,
+                     user: AuthUser,
+                     id: u64,
+                     input: PatchPostInput,
+) -> Result<HttpResponse, Error> {
+    validate(&input)?;
+    let (title, text) = (input.title.as_deref(), input.text.as_deref());
+    data.blocked_words.check(title.unwrap_or_default(), text.unwrap_or_default())?;
+    let txn = data
+        .conn
+        .begin()
+        .await
+        .map_err(|_| ApiError::database("could not start transaction"))?;
+    let current = tenanted_query::<Post>(&tenant.id)
+        .filter(post::Column::Id.eq(id))
+        .lock_exclusive()
+        .one(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+    permissions::require_write(&txn, &user, id).await?;
+    // checked under the row lock, and returning drops the transaction before any write
+    if !if_match(req, &etag(&current)) {
+        let message = "post was modified since it was read";
+        return Err(ApiError::new(ApiErrorCode::PreconditionFailed, message).into());
+    }
+    save_revision(&txn, user.id, &current).await?;
+
+    let mut post: post::ActiveModel = current.clone().into();
+    if let Some(title) = input.title {
+        post.title = Set(title);
+    }
+    if let Some(text) = input.text {
+        // an encrypted post stays encrypted
+        let text = data.store_text(text, current.is_encrypted)?;
+        if text.len() > MAX_TEXT_LEN {
+            let message = format!("must be at most {} bytes once encrypted", MAX_TEXT_LEN);
+            return Err(payload_errors::unprocessable(Some("text".to_owned()), message));
+        }
+        post.text = Set(text);
+    }
+    if let Some(status) = input.status {
+        post.status = Set(status);
+    }
+    let mut post = post
+        .update(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not update post"))?;
+    audit::post_updated(&txn, Some(user.id), &current, &post)
+        .await
+        .map_err(|_| ApiError::database("could not record update"))?;
+    txn.commit()
+        .await
+        .map_err(|_| ApiError::database("could not commit post"))?;
+
+    data.broadcaster.broadcast(PostEvent::new(PostEventKind::Updated, post.id)).await;
+    data.jobs.enqueue(Job::CheckLinks { post_id: post.id });
+    data.similar_posts.invalidate();
+    let mut builder = HttpResponse::Ok();
+    builder.insert_header((header::ETAG, etag(&post)));
+    data.reveal(&mut post)?;
+    negotiate_proto::<_, proto::Post>(req, builder, &post)
+}
+
+/// Keeps the stored title and text of `post` as a revision, before `user_id` changes it.
+pub async fn save_revision(txn: &DatabaseTransaction, user_id: u64, post: &post::Model) -> Result<(), Error> {
+    post_revision::ActiveModel {
+        post_id: Set(post.id),
+        user_id: Set(user_id),
+        title: Set(post.title.clone()),
+        text: Set(post.text.clone()),
+        created_at: Set(chrono::Utc::now()),
+        ..Default::default()
+    }
+        .insert(txn)
+        .await
+        .map_err(|_| ApiError::database("could not save revision"))?;
+    Ok(())
+}
+
+/// Inserts `input`, or updates the post with its `external_id` in the same statement,
+/// returning the post before and after.
+async fn upsert_one(txn: &DatabaseTransaction,
+                    data: &AppState,
+                    tenant: &Tenant,
+                    user: &AuthUser,
+                    input: UpsertPostInput,
+) -> Result<(Option<post::Model>, post::Model), Error> {
+    let by_external_id = input
+        .external_id
+        .as_deref()
+        .map(|external_id| tenanted_query::<Post>(&tenant.id).filter(post::Column::ExternalId.eq(external_id)));
+    let existing = match by_external_id.clone() {
+        // the lock, gap lock for a missing row, keeps concurrent syncs of one id apart
+        Some(query) => query
+            .lock_exclusive()
+            .one(txn)
+            .await
+            .map_err(|_| ApiError::database("could not retrieve post"))?,
+        None => None,
+    };
+    if let Some(existing) = &existing {
+        permissions::require_write(txn, user, existing.id).await?;
+        save_revision(txn, user.id, existing).await?;
+    }
+    // an encrypted post stays encrypted
+    let encrypt = existing.as_ref().is_some_and(|existing| existing.is_encrypted);
+    let text = data.store_text(input.text, encrypt)?;
+    // `Entity::insert` skips `before_save`, so the hash is set here
+    let post = post::ActiveModel {
+        content_hash: Set(post::content_hash(&input.title, &text)),
+        tenant_id: Set(tenant.id.clone()),
+        title: Set(input.title),
+        text: Set(text),
+        status: Set(input.status),
+        is_encrypted: Set(encrypt),
+        external_id: Set(input.external_id.clone()),
+        created_at: Set(chrono::Utc::now()),
+        ..Default::default()
+    };
+    let result = Post::insert(post)
+        .on_conflict(
+            OnConflict::columns([post::Column::TenantId, post::Column::ExternalId])
+                .update_columns([
+                    post::Column::Title,
+                    post::Column::Text,
+                    post::Column::Status,
+                    post::Column::IsEncrypted,
+                    post::Column::ContentHash,
+                ])
+                .to_owned(),
+        )
+        .exec(txn)
+        .await
+        .map_err(|_| ApiError::database("could not save post"))?;
+    // MySQL reports no insert id when the row was updated instead
+    let saved = match (&existing, by_external_id) {
+        (Some(_), Some(query)) => query.one(txn).await,
+        _ => Post::find_by_id(result.last_insert_id).one(txn).await,
+    };
+    let saved = saved
+        .map_err(|_| ApiError::database("could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+    Ok((existing, saved))
+}
+
+/// Creates or updates each post as `user`, matching them to existing posts by
+/// `external_id`, in one transaction; returns each saved post and whether it was updated.
+pub async fn upsert_batch(data: &AppState,
+                          tenant: &Tenant,
+                          user: &AuthUser,
+                          inputs: Vec<UpsertPostInput>,
+) -> Result<Vec<(bool, post::Model)>, Error> {
+    if inputs.len() > MAX_UPSERT_BATCH {
+        let message = format!("at most {} posts can be upserted at once", MAX_UPSERT_BATCH);
+        return Err(ApiError::validation(message).into());
+    }
+    for input in &inputs {
+        let fields = PatchPostInput { title: Some(input.title.clone()), text: Some(input.text.clone()), status: None };
+        validate(&fields)?;
+        data.blocked_words.check(&input.title, &input.text)?;
+    }
+    let txn = data
+        .conn
+        .begin()
+        .await
+        .map_err(|_| ApiError::database("could not start transaction"))?;
+    let mut saved = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let (old, post) = upsert_one(&txn, data, tenant, user, input).await?;
+        let recorded = match &old {
+            Some(old) => audit::post_updated(&txn, Some(user.id), old, &post).await,
+            None => match permissions::grant(&txn, user.id, post.id, Permission::Admin).await {
+                Ok(()) => audit::post_created(&txn, Some(user.id), &post).await,
+                Err(err) => Err(err),
+            },
+        };
+        recorded.map_err(|_| ApiError::database("could not record upsert"))?;
+        saved.push((old.is_some(), post));
+    }
+    txn.commit()
+        .await
+        .map_err(|_| ApiError::database("could not commit posts"))?;
+
+    for (updated, post) in &saved {
+        let kind = if *updated { PostEventKind::Updated } else { PostEventKind::Created };
+        data.broadcaster.broadcast(PostEvent::new(kind, post.id)).await;
+        if !updated {
+            let _ = data.post_events.send(post.id);
+            data.jobs.enqueue(Job::NotifySubscribers { post_id: post.id });
+        }
+        data.jobs.enqueue(Job::CheckLinks { post_id: post.id });
+        data.similar_posts.invalidate();
+    }
+    Ok(saved)
+}
+
+#[post("/api/v1/posts/upsert")]
+async fn upsert_posts(req: HttpRequest,
+                      data: Data<AppState>,
+                      tenant: Tenant,
+                      user: AuthUser,
+                      body: Body<Vec<UpsertPostInput>>,
+) -> Result<HttpResponse, Error> {
+    let saved = upsert_batch(&data, &tenant, &user, body.into_inner()).await?;
+    let results: Vec<UpsertResult> = saved
+        .into_iter()
+        .map(|(updated, post)| {
+            let action = if updated { UpsertAction::Updated } else { UpsertAction::Created };
+            UpsertResult { external_id: post.external_id, post_id: post.id, action }
+        })
+        .collect();
+    negotiate::respond(&req, HttpResponse::Ok(), &results)
+}
+
+/// Gives each listed post its index as `sort_order`, in one `UPDATE`. Posts left out keep
+/// theirs.
+#[post("/api/v1/posts/reorder")]
+async fn reorder_posts(data: Data<AppState>,
+                       tenant: Tenant,
+                       _admin: AdminUser,
+                       body: Body<ReorderInput>,
+) -> Result<HttpResponse, Error> {
+    let ids = body.into_inner().ordered_ids;
+    let mut seen = HashSet::with_capacity(ids.len());
+    if let Some(duplicate) = ids.iter().find(|id| !seen.insert(**id)) {
+        return Err(ApiError::bad_request(format!("post {} is listed more than once", duplicate)).into());
+    }
+    if ids.is_empty() {
+        return Ok(HttpResponse::NoContent().finish());
+    }
+    let txn = data
+        .conn
+        .begin()
+        .await
+        .map_err(|_| ApiError::database("could not start transaction"))?;
+    let found = tenanted_query::<Post>(&tenant.id)
+        .filter(post::Column::Id.is_in(ids.clone()))
+        .lock_exclusive()
+        .all(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve posts"))?;
+    if found.len() < ids.len() {
+        return Err(ApiError::post_not_found().into());
+    }
+    let position = ids
+        .iter()
+        .enumerate()
+        .fold(CaseStatement::new(), |case, (index, id)| case.case(post::Column::Id.eq(*id), index as i32));
+    Post::update_many()
+        .col_expr(post::Column::SortOrder, position.into())
+        .filter(post::Column::Id.is_in(ids))
+        .exec(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not reorder posts"))?;
+    txn.commit()
+        .await
+        .map_err(|_| ApiError::database("could not commit order"))?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(upsert_posts);
+    cfg.service(reorder_posts);
+    cfg.service(random_post);
+    cfg.service(list_posts);
+    cfg.service(get_post);
+    cfg.service(raw_post);
+    cfg.service(patch_post);
+    cfg.service(patch_title);
+    cfg.service(patch_text);
+}
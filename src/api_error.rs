@@ -0,0 +1,162 @@
+use std::fmt;
+
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use sea_orm::DbErr;
+use serde::Serialize;
+
+use crate::circuit_breaker;
+
+/// What went wrong, as a stable number clients can match on instead of the message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApiErrorCode {
+    PostNotFound,
+    ValidationFailed,
+    DatabaseError,
+    RateLimitExceeded,
+    Unauthorized,
+    Forbidden,
+    /// A record other than a post does not exist.
+    NotFound,
+    BadRequest,
+    Conflict,
+    PreconditionFailed,
+    /// A service the request depends on, such as GitHub, failed.
+    UpstreamFailed,
+    IntegrityCheckFailed,
+    Internal,
+    /// The database is unreachable and calls to it are failing fast.
+    ServiceUnavailable,
+    /// A record existed but is no longer available, such as an expired share link.
+    Gone,
+}
+
+impl ApiErrorCode {
+    /// The `error_code` of responses; never renumber a variant once released.
+    pub fn code(self) -> u16 {
+        match self {
+            ApiErrorCode::PostNotFound => 1001,
+            ApiErrorCode::ValidationFailed => 1002,
+            ApiErrorCode::DatabaseError => 1003,
+            ApiErrorCode::RateLimitExceeded => 1004,
+            ApiErrorCode::Unauthorized => 1005,
+            ApiErrorCode::Forbidden => 1006,
+            ApiErrorCode::NotFound => 1007,
+            ApiErrorCode::BadRequest => 1008,
+            ApiErrorCode::Conflict => 1009,
+            ApiErrorCode::PreconditionFailed => 1010,
+            ApiErrorCode::UpstreamFailed => 1011,
+            ApiErrorCode::IntegrityCheckFailed => 1012,
+            ApiErrorCode::Internal => 1013,
+            ApiErrorCode::ServiceUnavailable => 1014,
+            ApiErrorCode::Gone => 1015,
+        }
+    }
+
+    pub fn status(self) -> StatusCode {
+        match self {
+            ApiErrorCode::PostNotFound | ApiErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ApiErrorCode::ValidationFailed => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiErrorCode::RateLimitExceeded => StatusCode::TOO_MANY_REQUESTS,
+            ApiErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ApiErrorCode::BadRequest => StatusCode::BAD_REQUEST,
+            ApiErrorCode::Conflict => StatusCode::CONFLICT,
+            ApiErrorCode::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            ApiErrorCode::UpstreamFailed => StatusCode::BAD_GATEWAY,
+            ApiErrorCode::ServiceUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ApiErrorCode::Gone => StatusCode::GONE,
+            ApiErrorCode::DatabaseError
+            | ApiErrorCode::IntegrityCheckFailed
+            | ApiErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// An error answered with `{"error_code": 1001, "message": "post not found"}`.
+#[derive(Debug)]
+pub struct ApiError {
+    pub code: ApiErrorCode,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorBody<'a> {
+    error_code: u16,
+    message: &'a str,
+}
+
+impl ApiError {
+    pub fn new(code: ApiErrorCode, message: impl Into<String>) -> Self {
+        ApiError { code, message: message.into() }
+    }
+
+    pub fn post_not_found() -> Self {
+        ApiError::new(ApiErrorCode::PostNotFound, "post not found")
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        ApiError::new(ApiErrorCode::ValidationFailed, message)
+    }
+
+    pub fn database(message: impl Into<String>) -> Self {
+        ApiError::new(ApiErrorCode::DatabaseError, message)
+    }
+
+    /// `database(message)`, or 503 when `err` comes from an open circuit breaker.
+    pub fn from_db(err: &DbErr, message: impl Into<String>) -> Self {
+        match circuit_breaker::is_circuit_open(err) {
+            true => ApiError::new(ApiErrorCode::ServiceUnavailable, "database is unavailable"),
+            false => ApiError::database(message),
+        }
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        ApiError::new(ApiErrorCode::Unauthorized, message)
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        ApiError::new(ApiErrorCode::Forbidden, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        ApiError::new(ApiErrorCode::NotFound, message)
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        ApiError::new(ApiErrorCode::BadRequest, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        ApiError::new(ApiErrorCode::Conflict, message)
+    }
+
+    pub fn gone(message: impl Into<String>) -> Self {
+        ApiError::new(ApiErrorCode::Gone, message)
+    }
+
+    pub fn upstream(message: impl Into<String>) -> Self {
+        ApiError::new(ApiErrorCode::UpstreamFailed, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        ApiError::new(ApiErrorCode::Internal, message)
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.code.status()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .json(ErrorBody { error_code: self.code.code(), message: &self.message })
+    }
+}
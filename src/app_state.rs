@@ -0,0 +1,180 @@
+use std::fmt::{self, Display};
+use std::sync::{Arc, RwLock};
+
+use sea_orm::DatabaseConnection;
+use tera::Tera;
+
+use crate::analytics::WordFrequencyCache;
+use crate::blocked_words::BlockedWords;
+use crate::broadcast::BroadcastRegistry;
+use crate::circuit_breaker::CircuitBreaker;
+use crate::features::FeatureFlags;
+use crate::geoip::GeoIp;
+use crate::import_url::ImportPolicy;
+use crate::jobs::JobQueue;
+use crate::pool_monitor::MetricsState;
+use crate::post_counts::PostCountCache;
+use crate::rate_limit::PostRateLimiter;
+use crate::settings::SiteSettings;
+use crate::similarity::SimilarityCache;
+use crate::storage::{LocalObjectStorage, ObjectStorage};
+use crate::tenants::TenantRegistry;
+use crate::{config, encryption, events, images, jobs, preload, response_limit, translate, webhooks};
+use crate::{AppState, PageSizeLimits};
+
+/// Used when `base_url` is not set, as in the example `.env`.
+const DEFAULT_BASE_URL: &str = "http://127.0.0.1:8888";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// A component `AppStateBuilder::build` has no default for was not set; holds the
+    /// name of its setter.
+    Missing(&'static str),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Missing(name) => {
+                write!(f, "app state has no {}, set it with AppStateBuilder::{}", name, name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Assembles an `AppState`. `db`, `templates` and `jwt_secret` must be set; every other
+/// component has a default, and the ones with settings of their own read them from the
+/// environment in `build`.
+///
+/// Jobs are dropped unless `jobs` gets a queue whose worker runs.
+#[derive(Default)]
+pub struct AppStateBuilder {
+    db: Option<DatabaseConnection>,
+    templates: Option<Tera>,
+    jwt_secret: Option<String>,
+    base_url: Option<String>,
+    metrics: MetricsState,
+    feature_flags: FeatureFlags,
+    storage: Option<Arc<dyn ObjectStorage>>,
+    webp_quality: Option<u8>,
+    github: Option<oauth2::basic::BasicClient>,
+    jobs: Option<JobQueue>,
+    encryption_key: Option<encryption::Key>,
+    blocked_words: BlockedWords,
+    site_settings: SiteSettings,
+    similar_posts: SimilarityCache,
+}
+
+impl AppStateBuilder {
+    pub fn db(mut self, conn: DatabaseConnection) -> Self {
+        self.db = Some(conn);
+        self
+    }
+
+    pub fn templates(mut self, templates: Tera) -> Self {
+        self.templates = Some(templates);
+        self
+    }
+
+    pub fn jwt_secret(mut self, jwt_secret: String) -> Self {
+        self.jwt_secret = Some(jwt_secret);
+        self
+    }
+
+    pub fn base_url(mut self, base_url: String) -> Self {
+        self.base_url = Some(base_url);
+        self
+    }
+
+    pub fn metrics(mut self, metrics: MetricsState) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    pub fn feature_flags(mut self, feature_flags: FeatureFlags) -> Self {
+        self.feature_flags = feature_flags;
+        self
+    }
+
+    /// Defaults to local storage in `./uploads`.
+    pub fn storage(mut self, storage: Arc<dyn ObjectStorage>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    pub fn webp_quality(mut self, webp_quality: u8) -> Self {
+        self.webp_quality = Some(webp_quality);
+        self
+    }
+
+    pub fn github(mut self, github: Option<oauth2::basic::BasicClient>) -> Self {
+        self.github = github;
+        self
+    }
+
+    pub fn jobs(mut self, jobs: JobQueue) -> Self {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    pub fn encryption_key(mut self, encryption_key: Option<encryption::Key>) -> Self {
+        self.encryption_key = encryption_key;
+        self
+    }
+
+    pub fn blocked_words(mut self, blocked_words: BlockedWords) -> Self {
+        self.blocked_words = blocked_words;
+        self
+    }
+
+    pub fn site_settings(mut self, site_settings: SiteSettings) -> Self {
+        self.site_settings = site_settings;
+        self
+    }
+
+    pub fn similar_posts(mut self, similar_posts: SimilarityCache) -> Self {
+        self.similar_posts = similar_posts;
+        self
+    }
+
+    pub fn build(self) -> Result<AppState, ConfigError> {
+        let conn = self.db.ok_or(ConfigError::Missing("db"))?;
+        let templates = self.templates.ok_or(ConfigError::Missing("templates"))?;
+        let jwt_secret = self.jwt_secret.ok_or(ConfigError::Missing("jwt_secret"))?;
+        let base_url = self.base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_owned());
+        Ok(AppState {
+            templates: Arc::new(templates),
+            conn,
+            tenants: TenantRegistry::new(&base_url),
+            base_url,
+            jwt_secret,
+            storage: self
+                .storage
+                .unwrap_or_else(|| Arc::new(LocalObjectStorage::new(config::DEFAULT_UPLOAD_DIR))),
+            webp_quality: self.webp_quality.unwrap_or(images::DEFAULT_WEBP_QUALITY),
+            broadcaster: BroadcastRegistry::default(),
+            post_events: events::channel(),
+            github: self.github,
+            jobs: self.jobs.unwrap_or_else(|| jobs::channel().0),
+            feature_flags: self.feature_flags,
+            word_frequency: WordFrequencyCache::from_env(),
+            page_size_limits: PageSizeLimits::from_env(),
+            post_rate_limiter: PostRateLimiter::from_env(),
+            encryption_key: self.encryption_key,
+            blocked_words: self.blocked_words,
+            site_settings: Arc::new(RwLock::new(self.site_settings)),
+            circuit_breaker: CircuitBreaker::from_env(),
+            metrics: self.metrics,
+            preload_links: preload::link_header_from_env(),
+            response_size_limit: response_limit::limit_from_env(),
+            github_webhook: webhooks::config_from_env(),
+            similar_posts: self.similar_posts,
+            geoip: GeoIp::from_env(),
+            translator: translate::config_from_env(),
+            url_import: ImportPolicy::from_env(),
+            post_counts: PostCountCache::default(),
+        })
+    }
+}
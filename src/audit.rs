@@ -0,0 +1,158 @@
+use actix_web::{get, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbErr};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use entity::audit_event::{self, AuditAction, Entity as AuditEvent};
+use entity::post::{self, PostStatus};
+
+use crate::api_error::ApiError;
+use crate::auth::AdminUser;
+use crate::tenants::Tenant;
+use crate::{negotiate, AppState, CLAMPED_HEADER};
+
+/// `entity_type` of the events about posts.
+const POST: &str = "post";
+
+#[derive(Debug, Deserialize)]
+pub struct AuditLogParams {
+    entity_type: Option<String>,
+    entity_id: Option<u64>,
+    page: Option<usize>,
+    per_page: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct AuditLogPage {
+    events: Vec<audit_event::Model>,
+    page: usize,
+    per_page: usize,
+    num_pages: u64,
+}
+
+/// The audited fields of `post`; the id is already the event's `entity_id`.
+fn post_fields(post: &post::Model) -> Map<String, Value> {
+    let mut fields = match serde_json::to_value(post) {
+        Ok(Value::Object(fields)) => fields,
+        _ => Map::new(),
+    };
+    fields.remove("id");
+    fields
+}
+
+async fn record<C: ConnectionTrait>(conn: &C,
+                                    post: &post::Model,
+                                    action: AuditAction,
+                                    changed_fields: Map<String, Value>,
+                                    performed_by: Option<u64>,
+) -> Result<(), DbErr> {
+    audit_event::ActiveModel {
+        tenant_id: Set(post.tenant_id.clone()),
+        entity_type: Set(POST.to_owned()),
+        entity_id: Set(post.id),
+        action: Set(action),
+        changed_fields: Set(Value::Object(changed_fields)),
+        performed_by: Set(performed_by),
+        performed_at: Set(chrono::Utc::now()),
+        ..Default::default()
+    }
+        .insert(conn)
+        .await
+        .map(|_| ())
+}
+
+/// Records that `post` was created, with all of its initial values.
+pub async fn post_created<C: ConnectionTrait>(conn: &C,
+                                              performed_by: Option<u64>,
+                                              post: &post::Model,
+) -> Result<(), DbErr> {
+    record(conn, post, AuditAction::Created, post_fields(post), performed_by).await
+}
+
+/// Records the fields that differ between `old` and `new`, or nothing when none do.
+pub async fn post_updated<C: ConnectionTrait>(conn: &C,
+                                              performed_by: Option<u64>,
+                                              old: &post::Model,
+                                              new: &post::Model,
+) -> Result<(), DbErr> {
+    let old_fields = post_fields(old);
+    let changed: Map<String, Value> = post_fields(new)
+        .into_iter()
+        .filter_map(|(field, new)| {
+            let old = old_fields.get(&field).cloned().unwrap_or(Value::Null);
+            (old != new).then(|| (field, json!({ "old": old, "new": new })))
+        })
+        .collect();
+    if changed.is_empty() {
+        return Ok(());
+    }
+    record(conn, new, AuditAction::Updated, changed, performed_by).await
+}
+
+/// Records that the status of `post` changed to `status`, for changes made without
+/// loading the post as a whole.
+pub async fn post_status_changed<C: ConnectionTrait>(conn: &C,
+                                                     performed_by: Option<u64>,
+                                                     post: &post::Model,
+                                                     status: PostStatus,
+) -> Result<(), DbErr> {
+    if post.status == status {
+        return Ok(());
+    }
+    let mut changed = Map::new();
+    changed.insert("status".to_owned(), json!({ "old": post.status, "new": status }));
+    record(conn, post, AuditAction::Updated, changed, performed_by).await
+}
+
+/// Records that `post` was deleted, with the values it had last.
+pub async fn post_deleted<C: ConnectionTrait>(conn: &C,
+                                              performed_by: Option<u64>,
+                                              post: &post::Model,
+) -> Result<(), DbErr> {
+    record(conn, post, AuditAction::Deleted, post_fields(post), performed_by).await
+}
+
+/// Lists the tenant's audit events newest first, optionally only those about one kind of
+/// record or one record.
+#[get("/admin/audit-log")]
+async fn audit_log(req: HttpRequest,
+                   data: Data<AppState>,
+                   tenant: Tenant,
+                   _admin: AdminUser,
+                   params: web::Query<AuditLogParams>,
+) -> Result<HttpResponse, Error> {
+    let page = params.page.unwrap_or(1).max(1);
+    let (per_page, clamped) = data.posts_per_page(params.per_page)?;
+    data.page_size_limits.check_depth(page, per_page)?;
+    let mut query = AuditEvent::find().filter(audit_event::Column::TenantId.eq(tenant.id.as_str()));
+    if let Some(entity_type) = &params.entity_type {
+        query = query.filter(audit_event::Column::EntityType.eq(entity_type.as_str()));
+    }
+    if let Some(entity_id) = params.entity_id {
+        query = query.filter(audit_event::Column::EntityId.eq(entity_id));
+    }
+    let paginator = query
+        .order_by_desc(audit_event::Column::Id)
+        .paginate(&data.conn, per_page as u64);
+    let totals = paginator
+        .num_items_and_pages()
+        .await
+        .map_err(|_| ApiError::database("could not count audit events"))?;
+    let events = paginator
+        .fetch_page((page - 1) as u64)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve audit events"))?;
+
+    let mut builder = HttpResponse::Ok();
+    builder.insert_header(("X-Total-Count", totals.number_of_items));
+    if clamped {
+        builder.insert_header((CLAMPED_HEADER, "true"));
+    }
+    let page = AuditLogPage { events, page, per_page, num_pages: totals.number_of_pages };
+    negotiate::respond(&req, builder, &page)
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(audit_log);
+}
@@ -0,0 +1,124 @@
+use std::future::{ready, Ready};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use actix_web::{dev::Payload, http::header, web, Error, FromRequest, HttpRequest};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::api_error::ApiError;
+use crate::AppState;
+
+/// Name of the cookie browsers carry the token in.
+pub const TOKEN_COOKIE: &str = "token";
+/// Lifetime of a fully authenticated token.
+pub const TOKEN_TTL_SECS: u64 = 24 * 3600;
+/// Lifetime of a token that still has to pass the 2FA challenge.
+pub const PENDING_TOKEN_TTL_SECS: u64 = 5 * 60;
+
+/// JWT claims carried in the `Authorization: Bearer` header or the `token` cookie.
+///
+/// Tokens are HS256-signed with `JWT_SECRET`; `POST /admin/login` issues them and
+/// `cargo run --bin mint-token -- <user_id>` issues one for local use.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: u64,
+    pub exp: usize,
+    #[serde(default)]
+    pub admin: bool,
+    /// Set after a password login for users with 2FA enabled; such a token is only
+    /// accepted by `POST /admin/2fa/challenge`.
+    #[serde(default)]
+    pub pending_2fa: bool,
+}
+
+/// Signs a token for `user_id` that expires after `ttl_secs`.
+pub fn issue_token(secret: &str,
+                   user_id: u64,
+                   admin: bool,
+                   pending_2fa: bool,
+                   ttl_secs: u64,
+) -> Result<String, Error> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let claims = Claims { sub: user_id, exp: (now + ttl_secs) as usize, admin, pending_2fa };
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes()))
+        .map_err(|_| ApiError::internal("could not issue token").into())
+}
+
+/// The user authenticated by the request's token.
+///
+/// Rejects the request with 401 when the token is missing or invalid; use
+/// `Option<AuthUser>` for routes that also serve anonymous visitors.
+#[derive(Debug, Clone, Copy)]
+pub struct AuthUser {
+    pub id: u64,
+    pub admin: bool,
+}
+
+impl FromRequest for AuthUser {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(claims(req).and_then(|claims| match claims.pending_2fa {
+            true => Err(ApiError::unauthorized("2fa challenge required").into()),
+            false => Ok(AuthUser { id: claims.sub, admin: claims.admin }),
+        }))
+    }
+}
+
+/// A fully authenticated user with the admin flag; 403 for other users.
+#[derive(Debug, Clone, Copy)]
+pub struct AdminUser {
+    pub id: u64,
+}
+
+impl FromRequest for AdminUser {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        ready(AuthUser::from_request(req, payload).into_inner().and_then(|user| match user.admin {
+            true => Ok(AdminUser { id: user.id }),
+            false => Err(ApiError::forbidden("admin access required").into()),
+        }))
+    }
+}
+
+/// A user who passed the password check but not yet the 2FA challenge.
+#[derive(Debug, Clone, Copy)]
+pub struct PendingUser {
+    pub id: u64,
+}
+
+impl FromRequest for PendingUser {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(claims(req).and_then(|claims| match claims.pending_2fa {
+            true => Ok(PendingUser { id: claims.sub }),
+            false => Err(ApiError::unauthorized("no pending 2fa challenge").into()),
+        }))
+    }
+}
+
+fn claims(req: &HttpRequest) -> Result<Claims, Error> {
+    let data = req
+        .app_data::<web::Data<AppState>>()
+        .ok_or_else(|| ApiError::internal("app state missing"))?;
+    let cookie = req.cookie(TOKEN_COOKIE);
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| cookie.as_ref().map(|cookie| cookie.value()))
+        .ok_or_else(|| ApiError::unauthorized("missing token"))?;
+    let token = decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(data.jwt_secret.as_bytes()),
+        &Validation::default(),
+    )
+        .map_err(|_| ApiError::unauthorized("invalid token"))?;
+    Ok(token.claims)
+}
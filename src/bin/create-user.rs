@@ -0,0 +1,48 @@
+//! Creates a user who can log in through `POST /admin/login`.
+//!
+//! The password is read from the first line of stdin so it stays out of the shell
+//! history. Pass `--admin` to grant access to the admin endpoints.
+
+use std::env;
+use std::io;
+
+use argon2::password_hash::{rand_core::OsRng, SaltString};
+use argon2::{Argon2, PasswordHasher};
+use sea_orm::entity::*;
+
+use entity::user;
+
+#[actix_web::main]
+async fn main() {
+    dotenv::dotenv().ok();
+    let db_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+    let (flags, args): (Vec<String>, Vec<String>) =
+        env::args().skip(1).partition(|arg| arg.starts_with("--"));
+    let is_admin = flags.iter().any(|flag| flag == "--admin");
+    let username = args
+        .into_iter()
+        .next()
+        .expect("usage: create-user <username> [--admin] < password");
+
+    let mut password = String::new();
+    io::stdin().read_line(&mut password).expect("could not read password");
+    let password = password.trim_end_matches(['\r', '\n']);
+    let salt = SaltString::generate(&mut OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("could not hash password")
+        .to_string();
+
+    let conn = sea_orm::Database::connect(&db_url).await.unwrap();
+    let user = user::ActiveModel {
+        username: Set(username),
+        password_hash: Set(Some(password_hash)),
+        is_admin: Set(is_admin),
+        totp_enabled: Set(false),
+        ..Default::default()
+    }
+        .insert(&conn)
+        .await
+        .expect("could not insert user");
+    println!("{}", user.id);
+}
@@ -0,0 +1,185 @@
+//! Administrative operations that don't need the HTTP server.
+//!
+//! Run `cargo run --bin manage -- --help` for the list of subcommands.
+
+use std::collections::HashMap;
+use std::env;
+use std::io;
+
+use chrono::{TimeZone, Utc};
+use clap::{Parser, Subcommand};
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{entity::*, query::*, DatabaseConnection, DbErr};
+use serde::Deserialize;
+
+use entity::post;
+use entity::post::Entity as Post;
+
+const DEFAULT_POSTS_PER_PAGE: u64 = 20;
+
+#[derive(Parser)]
+#[command(about = "Manage the sea-orm-demo database")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print a page of posts as a table
+    ListPosts {
+        #[arg(long, default_value_t = 1)]
+        page: u64,
+        #[arg(long, default_value_t = DEFAULT_POSTS_PER_PAGE)]
+        per_page: u64,
+    },
+    /// Create a post and print its id
+    CreatePost {
+        #[arg(long)]
+        title: String,
+        #[arg(long)]
+        text: String,
+    },
+    /// Delete a post; its uploaded images are left in storage
+    DeletePost {
+        #[arg(long)]
+        id: u64,
+    },
+    /// Write every post to stdout as a JSON array
+    ExportJson,
+    /// Create posts from a JSON array on stdin, as written by export-json; ids are reassigned
+    ImportJson,
+    /// Apply pending database migrations
+    RunMigrations,
+    /// Revert the most recently applied migrations, newest first
+    Rollback {
+        #[arg(long, default_value_t = 1)]
+        steps: u32,
+    },
+    /// List every migration as applied, with the time it was, or pending
+    MigrationStatus,
+}
+
+#[derive(Deserialize)]
+struct ImportedPost {
+    title: String,
+    text: String,
+}
+
+async fn setup(db_url: &str) -> Result<DatabaseConnection, DbErr> {
+    sea_orm::Database::connect(db_url).await
+}
+
+async fn list_posts(conn: &DatabaseConnection, page: u64, per_page: u64) -> Result<(), DbErr> {
+    let paginator = Post::find()
+        .order_by_asc(post::Column::Id)
+        .paginate(conn, per_page.max(1));
+    let num_pages = paginator.num_pages().await?;
+    let posts = paginator.fetch_page(page.max(1) - 1).await?;
+    println!("{:>8}  {:<40}  TEXT", "ID", "TITLE");
+    for post in posts {
+        println!("{:>8}  {:<40}  {}", post.id, truncate(&post.title, 40), truncate(&post.text, 60));
+    }
+    println!("page {} of {}", page.max(1), num_pages);
+    Ok(())
+}
+
+fn truncate(value: &str, width: usize) -> String {
+    match value.chars().count() > width {
+        true => value.chars().take(width - 1).chain(Some('…')).collect(),
+        false => value.to_owned(),
+    }
+}
+
+async fn create_post(conn: &DatabaseConnection, title: String, text: String) -> Result<u64, DbErr> {
+    let post = post::ActiveModel {
+        title: Set(title),
+        text: Set(text),
+        ..Default::default()
+    }
+        .insert(conn)
+        .await?;
+    Ok(post.id)
+}
+
+async fn import_posts(conn: &DatabaseConnection, posts: Vec<ImportedPost>) -> Result<usize, DbErr> {
+    let count = posts.len();
+    let txn = conn.begin().await?;
+    for post in posts {
+        create_post_in(&txn, post).await?;
+    }
+    txn.commit().await?;
+    Ok(count)
+}
+
+async fn create_post_in<C: ConnectionTrait>(conn: &C, post: ImportedPost) -> Result<(), DbErr> {
+    post::ActiveModel {
+        title: Set(post.title),
+        text: Set(post.text),
+        ..Default::default()
+    }
+        .insert(conn)
+        .await?;
+    Ok(())
+}
+
+async fn migration_status(conn: &DatabaseConnection) -> Result<(), DbErr> {
+    let applied: HashMap<String, i64> = Migrator::get_migration_models(conn)
+        .await?
+        .into_iter()
+        .map(|model| (model.version, model.applied_at))
+        .collect();
+    println!("{:<8}  {:<25}  MIGRATION", "STATUS", "APPLIED AT");
+    for migration in Migrator::migrations() {
+        let name = migration.name();
+        match applied.get(name) {
+            Some(&applied_at) => {
+                let applied_at = Utc
+                    .timestamp_opt(applied_at, 0)
+                    .single()
+                    .map_or_else(|| applied_at.to_string(), |time| time.to_rfc3339());
+                println!("{:<8}  {:<25}  {}", "applied", applied_at, name);
+            }
+            None => println!("{:<8}  {:<25}  {}", "pending", "", name),
+        }
+    }
+    Ok(())
+}
+
+async fn run(conn: &DatabaseConnection, command: Command) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Command::ListPosts { page, per_page } => list_posts(conn, page, per_page).await?,
+        Command::CreatePost { title, text } => println!("{}", create_post(conn, title, text).await?),
+        Command::DeletePost { id } => {
+            let result = Post::delete_by_id(id).exec(conn).await?;
+            if result.rows_affected == 0 {
+                return Err(format!("post {} not found", id).into());
+            }
+        }
+        Command::ExportJson => {
+            let posts = Post::find().order_by_asc(post::Column::Id).all(conn).await?;
+            serde_json::to_writer_pretty(io::stdout().lock(), &posts)?;
+            println!();
+        }
+        Command::ImportJson => {
+            let posts: Vec<ImportedPost> = serde_json::from_reader(io::stdin().lock())?;
+            println!("imported {} posts", import_posts(conn, posts).await?);
+        }
+        Command::RunMigrations => Migrator::up(conn, None).await?,
+        Command::Rollback { steps } => Migrator::down(conn, Some(steps)).await?,
+        Command::MigrationStatus => migration_status(conn).await?,
+    }
+    Ok(())
+}
+
+#[actix_web::main]
+async fn main() {
+    dotenv::dotenv().ok();
+    let cli = Cli::parse();
+    let db_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
+    let conn = setup(&db_url).await.expect("could not connect to the database");
+    if let Err(err) = run(&conn, cli.command).await {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
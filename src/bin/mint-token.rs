@@ -0,0 +1,42 @@
+//! Issues a JWT accepted by the server's `AuthUser` extractor.
+//!
+//! Send it as `Authorization: Bearer <token>` or store it in a `token` cookie
+//! for browser sessions. Pass `--admin` to mint a token for the admin endpoints.
+
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use jsonwebtoken::{encode, EncodingKey, Header};
+use serde::Serialize;
+
+const DEFAULT_TTL_HOURS: u64 = 24;
+
+#[derive(Serialize)]
+struct Claims {
+    sub: u64,
+    exp: usize,
+    admin: bool,
+}
+
+fn main() {
+    dotenv::dotenv().ok();
+    let secret = env::var("JWT_SECRET").expect("JWT_SECRET is not set in .env file");
+    let (flags, args): (Vec<String>, Vec<String>) =
+        env::args().skip(1).partition(|arg| arg.starts_with("--"));
+    let admin = flags.iter().any(|flag| flag == "--admin");
+    let mut args = args.into_iter();
+    let sub = args
+        .next()
+        .and_then(|id| id.parse().ok())
+        .expect("usage: mint-token <user_id> [ttl_hours] [--admin]");
+    let ttl_hours: u64 = args
+        .next()
+        .map(|hours| hours.parse().expect("ttl_hours must be a number"))
+        .unwrap_or(DEFAULT_TTL_HOURS);
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let claims = Claims { sub, exp: (now + ttl_hours * 3600) as usize, admin };
+    let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes()))
+        .expect("could not encode token");
+    println!("{}", token);
+}
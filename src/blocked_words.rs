@@ -0,0 +1,90 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use actix_web::Error;
+use regex::{Regex, RegexBuilder};
+use tokio::signal::unix::{signal, SignalKind};
+
+use crate::api_error::ApiError;
+
+/// Terms posts may not contain, from the newline-separated `BLOCKED_WORDS_FILE`.
+#[derive(Debug, Clone, Default)]
+pub struct BlockedWords {
+    path: Option<PathBuf>,
+    /// Matches any listed word as a whole word; `None` when the list is empty.
+    pattern: Arc<RwLock<Option<Regex>>>,
+}
+
+/// Builds a case-insensitive pattern matching any of `words` between word boundaries.
+fn compile(words: &str) -> Option<Regex> {
+    let alternatives: Vec<String> = words
+        .lines()
+        .map(str::trim)
+        .filter(|word| !word.is_empty())
+        .map(regex::escape)
+        .collect();
+    if alternatives.is_empty() {
+        return None;
+    }
+    let pattern = format!(r"\b(?:{})\b", alternatives.join("|"));
+    Some(RegexBuilder::new(&pattern)
+        .case_insensitive(true)
+        .build()
+        .expect("escaped words form a valid pattern"))
+}
+
+impl BlockedWords {
+    /// Reads the list from `BLOCKED_WORDS_FILE`, or blocks nothing when it is not set.
+    pub fn from_env() -> io::Result<Self> {
+        let path = env::var("BLOCKED_WORDS_FILE").ok().map(PathBuf::from);
+        let words = BlockedWords { path, ..Default::default() };
+        words.reload()?;
+        Ok(words)
+    }
+
+    /// Reads the file again, keeping the current list when it cannot be read.
+    pub fn reload(&self) -> io::Result<()> {
+        if let Some(path) = &self.path {
+            let pattern = compile(&fs::read_to_string(path)?);
+            *self.pattern.write().unwrap() = pattern;
+        }
+        Ok(())
+    }
+
+    pub fn matches(&self, content: &str) -> bool {
+        self.pattern
+            .read()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|pattern| pattern.is_match(content))
+    }
+
+    /// Rejects a post with 422 when its title or text contains a blocked word, without
+    /// saying which.
+    pub fn check(&self, title: &str, text: &str) -> Result<(), Error> {
+        match self.matches(title) || self.matches(text) {
+            true => Err(ApiError::validation("content policy violation").into()),
+            false => Ok(()),
+        }
+    }
+}
+
+/// Reloads `words` whenever the process receives SIGHUP.
+pub async fn reload_on_sighup(words: BlockedWords) {
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(err) => {
+            tracing::warn!(%err, "could not listen for SIGHUP, blocked words will not be reloaded");
+            return;
+        }
+    };
+    while hangups.recv().await.is_some() {
+        match words.reload() {
+            Ok(()) => tracing::info!("reloaded blocked words"),
+            Err(err) => tracing::warn!(%err, "could not reload blocked words"),
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use std::fmt::{self, Display, Write};
+
+use actix_web::{delete, get, put, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use sea_orm::{entity::*, query::*};
+use serde_json::Value;
+
+use entity::post::{self, Entity as Post};
+
+use crate::api_error::ApiError;
+use crate::auth::AuthUser;
+use crate::negotiate::{self, Body};
+use crate::permissions;
+use crate::tenants::{tenanted_query, Tenant};
+use crate::AppState;
+
+const DEFAULT_HEADING_LEVEL: u64 = 2;
+
+/// Why a block document could not be rendered; `index` is the position of the block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenderError {
+    NotAnArray,
+    NotAnObject { index: usize },
+    UnknownType { index: usize },
+    MissingContent { index: usize },
+    InvalidHeadingLevel { index: usize },
+    /// Image sources must be `http(s)` URLs or paths on this site.
+    UnsafeImageSource { index: usize },
+}
+
+impl Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::NotAnArray => write!(f, "blocks must be an array"),
+            RenderError::NotAnObject { index } => write!(f, "block {} must be an object", index),
+            RenderError::UnknownType { index } => {
+                write!(f, "block {} must have a type of paragraph, heading, code or image", index)
+            }
+            RenderError::MissingContent { index } => write!(f, "block {} must have a string content", index),
+            RenderError::InvalidHeadingLevel { index } => {
+                write!(f, "block {} must have a level between 1 and 6", index)
+            }
+            RenderError::UnsafeImageSource { index } => {
+                write!(f, "block {} must have an http(s) url or a path as content", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+fn is_safe_source(src: &str) -> bool {
+    let lower = src.to_ascii_lowercase();
+    lower.starts_with("https://") || lower.starts_with("http://") || (src.starts_with('/') && !src.starts_with("//"))
+}
+
+/// Renders a block document to HTML. Each block is an object with a `type` and a string
+/// `content`, which is always escaped:
+///
+/// - `paragraph`
+/// - `heading`, with an optional `level` from 1 to 6, 2 by default
+/// - `code`, with an optional `language` for the `language-` class highlighters use
+/// - `image`, whose content is the image URL, with an optional `alt` text
+pub fn render_blocks(blocks: &Value) -> Result<String, RenderError> {
+    let blocks = blocks.as_array().ok_or(RenderError::NotAnArray)?;
+    let mut html = String::new();
+    for (index, block) in blocks.iter().enumerate() {
+        let block = block.as_object().ok_or(RenderError::NotAnObject { index })?;
+        let content = block
+            .get("content")
+            .and_then(Value::as_str)
+            .ok_or(RenderError::MissingContent { index })?;
+        let text = html_escape::encode_text(content);
+        // writing to a `String` cannot fail
+        let _ = match block.get("type").and_then(Value::as_str) {
+            Some("paragraph") => writeln!(html, "<p>{}</p>", text),
+            Some("heading") => {
+                let level = match block.get("level") {
+                    None => DEFAULT_HEADING_LEVEL,
+                    Some(level) => level
+                        .as_u64()
+                        .filter(|level| (1..=6).contains(level))
+                        .ok_or(RenderError::InvalidHeadingLevel { index })?,
+                };
+                writeln!(html, "<h{level}>{}</h{level}>", text, level = level)
+            }
+            Some("code") => match block.get("language").and_then(Value::as_str) {
+                Some(language) => writeln!(
+                    html,
+                    r#"<pre><code class="language-{}">{}</code></pre>"#,
+                    html_escape::encode_double_quoted_attribute(language),
+                    text,
+                ),
+                None => writeln!(html, "<pre><code>{}</code></pre>", text),
+            },
+            Some("image") => {
+                if !is_safe_source(content) {
+                    return Err(RenderError::UnsafeImageSource { index });
+                }
+                let alt = block.get("alt").and_then(Value::as_str).unwrap_or_default();
+                writeln!(
+                    html,
+                    r#"<img src="{}" alt="{}" />"#,
+                    html_escape::encode_double_quoted_attribute(content),
+                    html_escape::encode_double_quoted_attribute(alt),
+                )
+            }
+            _ => return Err(RenderError::UnknownType { index }),
+        };
+    }
+    Ok(html)
+}
+
+/// The HTML of `post`'s blocks, for pages that show a post; `None` when it has none and
+/// its text should be shown instead. Blocks are checked when they are saved, so failing
+/// here means they were written some other way, and the text is shown too.
+pub fn blocks_html(post: &post::Model) -> Option<String> {
+    let blocks = post.blocks.as_ref()?;
+    render_blocks(blocks)
+        .map_err(|err| tracing::warn!(post_id = post.id, %err, "could not render blocks"))
+        .ok()
+}
+
+async fn find_post(data: &AppState, tenant: &Tenant, id: u64) -> Result<post::Model, Error> {
+    tenanted_query::<Post>(&tenant.id)
+        .filter(post::Column::Id.eq(id))
+        .one(&data.conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve post"))?
+        .ok_or_else(|| ApiError::post_not_found().into())
+}
+
+#[get("/api/v1/posts/{id}/blocks")]
+async fn get_blocks(req: HttpRequest,
+                    data: Data<AppState>,
+                    tenant: Tenant,
+                    user: Option<AuthUser>,
+                    id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
+    let post = find_post(&data, &tenant, id.into_inner()).await?;
+    permissions::require_read(&data.conn, user.as_ref(), &post).await?;
+    let blocks = post.blocks.ok_or_else(|| ApiError::not_found("post has no blocks"))?;
+    negotiate::respond(&req, HttpResponse::Ok(), &blocks)
+}
+
+/// Replaces the blocks of a post with the block document in the body, which is rejected
+/// with 422 unless it renders.
+#[put("/api/v1/posts/{id}/blocks")]
+async fn put_blocks(req: HttpRequest,
+                    data: Data<AppState>,
+                    tenant: Tenant,
+                    user: AuthUser,
+                    id: web::Path<u64>,
+                    body: Body<Value>,
+) -> Result<HttpResponse, Error> {
+    let post = find_post(&data, &tenant, id.into_inner()).await?;
+    permissions::require_write(&data.conn, &user, post.id).await?;
+    // blocks are stored as they are, so they would give away what encryption hides
+    if post.is_encrypted {
+        return Err(ApiError::validation("encrypted posts cannot have blocks").into());
+    }
+    let blocks = body.into_inner();
+    render_blocks(&blocks).map_err(|err| ApiError::validation(err.to_string()))?;
+    let mut post: post::ActiveModel = post.into();
+    post.blocks = Set(Some(blocks.clone()));
+    post.update(&data.conn)
+        .await
+        .map_err(|_| ApiError::database("could not update blocks"))?;
+    negotiate::respond(&req, HttpResponse::Ok(), &blocks)
+}
+
+/// Removes the blocks of a post, whose text is shown again.
+#[delete("/api/v1/posts/{id}/blocks")]
+async fn delete_blocks(data: Data<AppState>,
+                       tenant: Tenant,
+                       user: AuthUser,
+                       id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
+    let post = find_post(&data, &tenant, id.into_inner()).await?;
+    permissions::require_write(&data.conn, &user, post.id).await?;
+    let mut post: post::ActiveModel = post.into();
+    post.blocks = Set(None);
+    post.update(&data.conn)
+        .await
+        .map_err(|_| ApiError::database("could not delete blocks"))?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_blocks);
+    cfg.service(put_blocks);
+    cfg.service(delete_blocks);
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn render_blocks_renders_each_type() {
+        let blocks = json!([
+            {"type": "heading", "content": "Title"},
+            {"type": "heading", "level": 3, "content": "Section"},
+            {"type": "paragraph", "content": "Some text"},
+            {"type": "code", "language": "rust", "content": "let a = 1;"},
+            {"type": "code", "content": "ls"},
+            {"type": "image", "content": "/static/a.png", "alt": "An image"},
+        ]);
+        assert_eq!(
+            render_blocks(&blocks).unwrap(),
+            "<h2>Title</h2>\n\
+             <h3>Section</h3>\n\
+             <p>Some text</p>\n\
+             <pre><code class=\"language-rust\">let a = 1;</code></pre>\n\
+             <pre><code>ls</code></pre>\n\
+             <img src=\"/static/a.png\" alt=\"An image\" />\n",
+        );
+    }
+
+    #[test]
+    fn render_blocks_escapes_content() {
+        let blocks = json!([
+            {"type": "paragraph", "content": "<script>alert(1)</script>"},
+            {"type": "code", "language": "\"><script>", "content": "a < b"},
+            {"type": "image", "content": "https://example.com/a.png?\"", "alt": "\" onerror=\""},
+        ]);
+        let html = render_blocks(&blocks).unwrap();
+        assert!(!html.contains("<script>"));
+        assert!(!html.contains("\" onerror"));
+        assert!(html.contains("<p>&lt;script&gt;alert(1)&lt;/script&gt;</p>"));
+    }
+
+    #[test]
+    fn render_blocks_rejects_invalid_blocks() {
+        assert_eq!(render_blocks(&json!({})), Err(RenderError::NotAnArray));
+        assert_eq!(render_blocks(&json!(["text"])), Err(RenderError::NotAnObject { index: 0 }));
+        let blocks = json!([{"type": "paragraph", "content": "ok"}, {"type": "video", "content": "a"}]);
+        assert_eq!(render_blocks(&blocks), Err(RenderError::UnknownType { index: 1 }));
+        let blocks = json!([{"type": "paragraph"}]);
+        assert_eq!(render_blocks(&blocks), Err(RenderError::MissingContent { index: 0 }));
+        let blocks = json!([{"type": "heading", "level": 7, "content": "a"}]);
+        assert_eq!(render_blocks(&blocks), Err(RenderError::InvalidHeadingLevel { index: 0 }));
+    }
+
+    #[test]
+    fn render_blocks_rejects_unsafe_image_sources() {
+        for src in ["javascript:alert(1)", "//evil.example/a.png", "data:image/png;base64,AA"] {
+            let blocks = json!([{"type": "image", "content": src}]);
+            assert_eq!(render_blocks(&blocks), Err(RenderError::UnsafeImageSource { index: 0 }));
+        }
+        let blocks = json!([{"type": "image", "content": "HTTPS://example.com/a.png"}]);
+        assert!(render_blocks(&blocks).is_ok());
+    }
+}
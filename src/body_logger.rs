@@ -0,0 +1,40 @@
+use std::env;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header;
+use actix_web::middleware::Next;
+use actix_web::web::BytesMut;
+use actix_web::{Error, HttpMessage};
+use futures_util::StreamExt;
+
+/// Bytes of each body that are logged; the handler still gets all of it.
+const LOGGED_BYTES: usize = 4096;
+
+/// Whether `LOG_REQUEST_BODIES=1`; read once, when the app is built.
+pub fn enabled() -> bool {
+    env::var("LOG_REQUEST_BODIES").as_deref() == Ok("1")
+}
+
+/// DebugBodyLogger: logs each request body at DEBUG, then hands the buffered bytes on
+/// as the payload so handlers read it as usual.
+pub async fn middleware(mut req: ServiceRequest,
+                        next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let mut payload = req.take_payload();
+    let mut body = BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        body.extend_from_slice(&chunk?);
+    }
+    let header = |name| req.headers().get(name).and_then(|value| value.to_str().ok()).unwrap_or("");
+    tracing::debug!(
+        method = %req.method(),
+        path = req.path(),
+        content_type = header(header::CONTENT_TYPE),
+        content_length = header(header::CONTENT_LENGTH),
+        body = %String::from_utf8_lossy(&body[..body.len().min(LOGGED_BYTES)]),
+        "request body"
+    );
+    req.set_payload(Payload::from(body.freeze()));
+    next.call(req).await
+}
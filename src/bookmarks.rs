@@ -0,0 +1,115 @@
+use actix_web::{delete, get, post, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use sea_orm::{entity::*, query::*, sea_query::OnConflict};
+
+use entity::bookmark;
+use entity::bookmark::Entity as Bookmark;
+use entity::post::{self, Entity as Post};
+
+use crate::api_error::ApiError;
+use crate::api::{self, PostPage};
+use crate::auth::AuthUser;
+use crate::circuit_breaker::with_circuit_breaker;
+use crate::negotiate;
+use crate::tenants::{tenanted_query, Tenant};
+use crate::{AppState, Params};
+
+/// Returns whether `user_id` has bookmarked `post_id`.
+pub async fn is_bookmarked(
+    data: &AppState,
+    user_id: u64,
+    post_id: u64,
+) -> Result<bool, Error> {
+    let find = || Bookmark::find_by_id((user_id, post_id)).one(&data.conn);
+    let bookmark = with_circuit_breaker(&data.circuit_breaker, find)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not retrieve bookmark"))?;
+    Ok(bookmark.is_some())
+}
+
+#[post("/api/v1/posts/{id}/bookmark")]
+async fn add_bookmark(data: Data<AppState>,
+                      tenant: Tenant,
+                      user: AuthUser,
+                      id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
+    let conn = &data.conn;
+    let post_id = id.into_inner();
+    let breaker = &data.circuit_breaker;
+    let find = || tenanted_query::<Post>(&tenant.id).filter(post::Column::Id.eq(post_id)).one(conn);
+    with_circuit_breaker(breaker, find)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+
+    let bookmark = bookmark::ActiveModel {
+        user_id: Set(user.id),
+        post_id: Set(post_id),
+        created_at: Set(chrono::Utc::now()),
+    };
+    // sea-query renders `do_nothing()` as `ON DUPLICATE KEY DO NOTHING`, which MySQL
+    // rejects, so an existing bookmark is kept by a no-op update of its key instead
+    let save = Bookmark::insert(bookmark)
+        .on_conflict(
+            OnConflict::columns([bookmark::Column::UserId, bookmark::Column::PostId])
+                .update_column(bookmark::Column::PostId)
+                .to_owned(),
+        )
+        .exec_without_returning(conn);
+    with_circuit_breaker(breaker, || save)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not save bookmark"))?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[delete("/api/v1/posts/{id}/bookmark")]
+async fn remove_bookmark(data: Data<AppState>,
+                         user: AuthUser,
+                         id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
+    let remove = Bookmark::delete_by_id((user.id, id.into_inner())).exec(&data.conn);
+    let result = with_circuit_breaker(&data.circuit_breaker, || remove)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not delete bookmark"))?;
+    if result.rows_affected == 0 {
+        return Err(ApiError::not_found("bookmark not found").into());
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[get("/api/v1/users/me/bookmarks")]
+async fn list_bookmarks(req: HttpRequest,
+                        data: Data<AppState>,
+                        tenant: Tenant,
+                        user: AuthUser,
+                        params: web::Query<Params>,
+) -> Result<HttpResponse, Error> {
+    let conn = &data.conn;
+    let page = params.page.unwrap_or(1).max(1);
+    let (posts_per_page, clamped) = data.posts_per_page(params.posts_per_page)?;
+    data.page_size_limits.check_depth(page, posts_per_page)?;
+    let paginator = tenanted_query::<Post>(&tenant.id)
+        .join(JoinType::InnerJoin, bookmark::Relation::Post.def().rev())
+        .filter(bookmark::Column::UserId.eq(user.id))
+        .order_by_desc(bookmark::Column::CreatedAt)
+        .paginate(conn, posts_per_page as u64);
+    let breaker = &data.circuit_breaker;
+    let totals = with_circuit_breaker(breaker, || paginator.num_items_and_pages())
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not count bookmarks"))?;
+    let mut posts = with_circuit_breaker(breaker, || paginator.fetch_page((page - 1) as u64))
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not retrieve bookmarks"))?;
+    for post in &mut posts {
+        data.reveal(post)?;
+    }
+    let page = PostPage { posts, page, posts_per_page, num_pages: totals.number_of_pages };
+    let builder = api::paginated(&req, &data.base_url, &page, &params, totals.number_of_items, clamped);
+    negotiate::respond(&req, builder, &page)
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(add_bookmark);
+    cfg.service(remove_bookmark);
+    cfg.service(list_bookmarks);
+}
@@ -0,0 +1,98 @@
+use std::fmt;
+use std::sync::Arc;
+
+use actix_web::{get, rt, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use actix_ws::{Message, Session};
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use crate::AppState;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PostEventKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// Notification pushed to dashboard clients after a post is written.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct PostEvent {
+    event: PostEventKind,
+    post_id: u64,
+}
+
+impl PostEvent {
+    pub fn new(event: PostEventKind, post_id: u64) -> Self {
+        PostEvent { event, post_id }
+    }
+}
+
+/// The WebSocket sessions connected to `/ws/posts`.
+#[derive(Clone, Default)]
+pub struct BroadcastRegistry {
+    sessions: Arc<Mutex<Vec<Session>>>,
+}
+
+impl fmt::Debug for BroadcastRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BroadcastRegistry").finish_non_exhaustive()
+    }
+}
+
+impl BroadcastRegistry {
+    pub async fn register(&self, session: Session) {
+        self.sessions.lock().await.push(session);
+    }
+
+    /// Sends `event` to every connected client, dropping the ones that went away.
+    pub async fn broadcast(&self, event: PostEvent) {
+        let message = match serde_json::to_string(&event) {
+            Ok(message) => message,
+            Err(e) => {
+                tracing::error!("could not serialize post event: {}", e);
+                return;
+            }
+        };
+        let mut sessions = self.sessions.lock().await;
+        let mut alive = Vec::with_capacity(sessions.len());
+        for mut session in sessions.drain(..) {
+            if session.text(message.clone()).await.is_ok() {
+                alive.push(session);
+            }
+        }
+        *sessions = alive;
+    }
+}
+
+#[get("/ws/posts")]
+async fn posts_ws(data: Data<AppState>,
+                  req: HttpRequest,
+                  body: web::Payload,
+) -> Result<HttpResponse, Error> {
+    let (response, mut session, mut stream) = actix_ws::handle(&req, body)?;
+    data.broadcaster.register(session.clone()).await;
+
+    rt::spawn(async move {
+        while let Some(Ok(message)) = stream.recv().await {
+            let closed = match message {
+                Message::Ping(bytes) => session.pong(&bytes).await.is_err(),
+                Message::Close(reason) => {
+                    let _ = session.close(reason).await;
+                    return;
+                }
+                _ => false,
+            };
+            if closed {
+                return;
+            }
+        }
+    });
+    Ok(response)
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(posts_ws);
+}
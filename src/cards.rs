@@ -0,0 +1,152 @@
+use std::fmt::Write;
+
+use actix_web::{get, web, Error, HttpResponse};
+use actix_web::web::Data;
+use sea_orm::{entity::*, query::*};
+use serde::Deserialize;
+
+use entity::post;
+use entity::post::Entity as Post;
+
+use crate::api_error::ApiError;
+use crate::auth::AuthUser;
+use crate::tenants::{tenanted_query, Tenant};
+use crate::{filters, images, permissions, AppState};
+
+const SITE_NAME: &str = "sea-orm-demo";
+/// Size of the card; 1.91:1 is what link previews expect.
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 418;
+const PADDING: u32 = 48;
+/// Width of the featured image along the right edge, when the post has one.
+const IMAGE_WIDTH: u32 = 300;
+const ELLIPSIS: char = '…';
+
+#[derive(Debug, Deserialize)]
+pub struct CardParams {
+    dark: Option<u8>,
+}
+
+struct Theme {
+    background: &'static str,
+    title: &'static str,
+    text: &'static str,
+    accent: &'static str,
+}
+
+const LIGHT: Theme = Theme { background: "#ffffff", title: "#222222", text: "#555555", accent: "#33c3f0" };
+const DARK: Theme = Theme { background: "#1e1e1e", title: "#f5f5f5", text: "#bbbbbb", accent: "#33c3f0" };
+
+/// Breaks `text` into at most `max_lines` lines of at most `width` characters, ending
+/// with "…" when anything was left out. SVG has no text wrapping of its own.
+fn wrap(text: &str, width: usize, max_lines: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    let cut = lines.len() > max_lines;
+    lines.truncate(max_lines);
+    let last = lines.len().saturating_sub(1);
+    for (index, line) in lines.iter_mut().enumerate() {
+        if line.chars().count() > width || (cut && index == last) {
+            *line = line.chars().take(width.saturating_sub(1)).collect();
+            line.push(ELLIPSIS);
+        }
+    }
+    lines
+}
+
+/// Appends a `<text>` element with one `<tspan>` per line.
+fn text_block(svg: &mut String, lines: &[String], y: u32, size: u32, color: &str, weight: &str) {
+    let _ = write!(svg, r#"<text x="{}" y="{}" font-size="{}" font-weight="{}" fill="{}">"#, PADDING, y, size, weight, color);
+    for (index, line) in lines.iter().enumerate() {
+        let dy = if index == 0 { 0 } else { size * 5 / 4 };
+        let _ = write!(svg, r#"<tspan x="{}" dy="{}">{}</tspan>"#, PADDING, dy, html_escape::encode_text(line));
+    }
+    svg.push_str("</text>");
+}
+
+fn render_card(post: &post::Model, image_url: Option<&str>, theme: &Theme) -> String {
+    let text_width = match image_url {
+        Some(_) => WIDTH - IMAGE_WIDTH - 2 * PADDING,
+        None => WIDTH - 2 * PADDING,
+    };
+    // average glyph widths of a sans-serif font at the two sizes used
+    let title = wrap(&post.title, (text_width / 22) as usize, 3);
+    let excerpt = wrap(&filters::html_to_text(&post.text), (text_width / 11) as usize, 4);
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="Raleway, Helvetica, Arial, sans-serif">"#,
+        w = WIDTH,
+        h = HEIGHT,
+    );
+    let _ = write!(svg, r#"<rect width="{}" height="{}" fill="{}"/>"#, WIDTH, HEIGHT, theme.background);
+    if let Some(url) = image_url {
+        let _ = write!(
+            svg,
+            r#"<image href="{}" x="{}" y="0" width="{}" height="{}" preserveAspectRatio="xMidYMid slice"/>"#,
+            html_escape::encode_double_quoted_attribute(url),
+            WIDTH - IMAGE_WIDTH,
+            IMAGE_WIDTH,
+            HEIGHT,
+        );
+    }
+    let _ = write!(svg, r#"<rect x="0" y="0" width="8" height="{}" fill="{}"/>"#, HEIGHT, theme.accent);
+    text_block(&mut svg, &title, PADDING + 40, 40, theme.title, "600");
+    let excerpt_y = PADDING + 40 + title.len() as u32 * 50 + 20;
+    text_block(&mut svg, &excerpt, excerpt_y, 22, theme.text, "400");
+    let _ = write!(
+        svg,
+        r#"<text x="{}" y="{}" font-size="20" fill="{}">{}</text>"#,
+        PADDING,
+        HEIGHT - PADDING,
+        theme.accent,
+        SITE_NAME,
+    );
+    svg.push_str("</svg>");
+    svg
+}
+
+/// A link preview card of the post, as an SVG image; `dark=1` gives a dark background.
+#[get("/posts/{id}/card.svg")]
+async fn card(data: Data<AppState>,
+              tenant: Tenant,
+              user: Option<AuthUser>,
+              id: web::Path<u64>,
+              params: web::Query<CardParams>,
+) -> Result<HttpResponse, Error> {
+    let mut post = tenanted_query::<Post>(&tenant.id)
+        .filter(post::Column::Id.eq(id.into_inner()))
+        .one(&data.conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+    permissions::require_read(&data.conn, user.as_ref(), &post).await?;
+    data.reveal(&mut post)?;
+    // local uploads have relative URLs, which an image viewed on its own cannot resolve
+    let image_url = images::image_urls(data.storage.as_ref(), &post).map(|urls| {
+        match urls.featured.starts_with('/') {
+            true => format!("{}{}", data.base_url.trim_end_matches('/'), urls.featured),
+            false => urls.featured,
+        }
+    });
+    let theme = if params.dark == Some(1) { &DARK } else { &LIGHT };
+    let svg = render_card(&post, image_url.as_deref(), theme);
+    Ok(HttpResponse::Ok().content_type("image/svg+xml").body(svg))
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(card);
+}
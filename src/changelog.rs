@@ -0,0 +1,295 @@
+use actix_web::{get, web, Error, HttpRequest, HttpResponse};
+use serde::Serialize;
+
+use crate::negotiate;
+
+#[derive(Debug, Serialize)]
+pub struct ApiChange {
+    version: &'static str,
+    date: &'static str,
+    /// Whether clients written against the previous behaviour may need changes.
+    breaking: bool,
+    description: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct Version {
+    version: &'static str,
+}
+
+/// Changes to the HTTP API, newest first. Add an entry with every change clients can see.
+const CHANGELOG: &[ApiChange] = &[
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "POST /admin/posts/{id}/share-link and POST /admin/posts/{id}/revoke-share-link manage a link previewing the post without signing in at GET /preview/{token}, which answers 410 with error_code 1015 once it has expired.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "GET /api/v1/posts/count returns the number of posts in each status.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "GET /admin/export/zip returns the published posts as Markdown files in a ZIP archive.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "POST /api/v1/posts/import-url creates a draft from the article at a url, optionally attributed.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "POST /api/v1/posts/{id}/translate?target_lang= machine translates a post, saving it as a draft with save=1.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "POST /admin/posts/batch-duplicate copies posts as drafts, optionally pointing links between them at the copies.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "GET, PUT and DELETE /api/v1/posts/{id}/blocks manage a block document shown instead of the text.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "GET /api/v1/posts/{id}/raw returns the text of a post as written, as text/plain.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "POST /admin/posts/bulk-status-change sets the status of many posts at once.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "POST and DELETE /admin/posts/{id}/expiry set when a published post is archived.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "POST /api/v1/access-log records a page view with the country and city of its reader.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "GET /api/v1/posts/today returns the post of the day, picked at random each midnight.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "GET /api/v1/posts/{id}/similar lists the five posts with the most similar text.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "POST /webhooks/github publishes the Markdown files of GitHub pushes as posts.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "Posts belong to a tenant, picked by the X-Tenant-ID header or the subdomain; unknown tenants get 404.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "GET /api/v1/posts/search?q= finds published posts with the database's full-text index.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "GET /posts/{id}/card.svg renders a link preview card of a post, dark with dark=1.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "/admin/announcements manages the banner shown at the top of every page of the tenant.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "GET /api/v1/posts/random returns a random published post.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "POST and DELETE /admin/posts/{id}/feature feature a post, optionally until a deadline.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "/api/v1/reading-list keeps an ordered queue of posts to read, separate from bookmarks.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "POST /api/v1/posts/reorder sets the order lists use with use_custom_order=1.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "GET, POST and DELETE /admin/posts/{id}/relations manage links between related posts.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "POST /api/v1/posts/upsert creates or updates posts matched by external_id.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "GET /api/v1/posts/{id}/stats/length-history lists the length of each revision of a post.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "Post reads and writes get 503 with error_code 1014 while the database circuit breaker is open.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "The default page size of lists is the posts_per_page site setting, managed at /admin/settings.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "Posts containing a word from BLOCKED_WORDS_FILE get 422 with a content policy violation error.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: true,
+        description: "Error responses are JSON with a numeric error_code and a message.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "Reading a post whose stored content no longer matches its hash gets 500 with the integrity-check-failed error code.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "?encrypt=1 on POST / and POST /{id} stores the post text encrypted; reads return it decrypted.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "PATCH /api/v1/posts/{id}/title and /text edit one field; empty or oversized values get 422.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: true,
+        description: "Pages that would skip more than 100000 posts, or lie past MAX_PAGE_DEPTH, get 400.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "Requests with the right path but an unsupported method get 405 with an Allow header.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "HEAD is accepted on GET /api/v1/posts and GET /api/v1/posts/{id}.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: true,
+        description: "Bodies and query strings that cannot be decoded get 422 with a list of field errors instead of 400.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "GET /api/v1/posts/{id} returns an ETag; PATCH honours If-Match and answers 412 on a mismatch.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "PATCH /api/v1/posts/{id} updates only the fields it is given.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: true,
+        description: "Posts have a status; lists only return published posts unless an admin asks for another status.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: true,
+        description: "posts_per_page is checked against configured limits; values above the maximum are clamped.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "Paginated lists send Link, X-Total-Count and X-Total-Pages headers.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "Responses and request bodies of the v1 API can be MessagePack.",
+    },
+    ApiChange {
+        version: "0.1.0",
+        date: "2026-10-14",
+        breaking: false,
+        description: "Added the post API under /api/v1 and the GraphQL endpoint at /graphql.",
+    },
+];
+
+#[get("/api/changelog")]
+async fn changelog(req: HttpRequest) -> Result<HttpResponse, Error> {
+    negotiate::respond(&req, HttpResponse::Ok(), CHANGELOG)
+}
+
+#[get("/api/version")]
+async fn version(req: HttpRequest) -> Result<HttpResponse, Error> {
+    negotiate::respond(&req, HttpResponse::Ok(), &Version { version: env!("CARGO_PKG_VERSION") })
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(changelog);
+    cfg.service(version);
+}
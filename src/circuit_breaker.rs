@@ -0,0 +1,214 @@
+use std::env;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use sea_orm::DbErr;
+use serde::Serialize;
+
+const DEFAULT_THRESHOLD: u32 = 5;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Message of the `DbErr::Custom` returned while the circuit is open.
+const CIRCUIT_OPEN: &str = "database circuit breaker is open";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Calls go through.
+    Closed,
+    /// Calls fail straight away, until the timeout has passed.
+    Open,
+    /// One trial call goes through; its outcome closes or reopens the circuit.
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Circuit {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Instant,
+    /// When the half-open trial started; a trial that never finished, because its
+    /// request was dropped, is replaced after the timeout.
+    trial_started: Option<Instant>,
+}
+
+/// Stops calling the database after `DB_CIRCUIT_BREAKER_THRESHOLD` consecutive failures
+/// to reach it, so requests fail fast instead of queuing for the connection pool, and
+/// tries again after `DB_CIRCUIT_BREAKER_TIMEOUT_SECS`.
+///
+/// Covers the post pages, the post API reads, reading progress, bookmarks, annotations,
+/// the admin endpoints and GraphQL; a transaction goes through it when it begins, which
+/// is when it takes a connection. Other endpoints and the background jobs call the
+/// database directly.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    timeout: Duration,
+    circuit: Arc<Mutex<Circuit>>,
+}
+
+/// Whether `err` means the database could not be reached, rather than that it refused
+/// one query.
+fn is_unavailable(err: &DbErr) -> bool {
+    matches!(err, DbErr::ConnectionAcquire | DbErr::Conn(_))
+}
+
+/// Whether `err` is the error `with_circuit_breaker` fails fast with.
+pub fn is_circuit_open(err: &DbErr) -> bool {
+    matches!(err, DbErr::Custom(message) if message == CIRCUIT_OPEN)
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, timeout: Duration) -> Self {
+        let circuit = Circuit {
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            opened_at: Instant::now(),
+            trial_started: None,
+        };
+        CircuitBreaker { threshold: threshold.max(1), timeout, circuit: Arc::new(Mutex::new(circuit)) }
+    }
+
+    pub fn from_env() -> Self {
+        let number = |name: &str| {
+            env::var(name)
+                .ok()
+                .map(|value| value.parse::<u64>().unwrap_or_else(|_| panic!("{} must be a number", name)))
+        };
+        let threshold = number("DB_CIRCUIT_BREAKER_THRESHOLD").map_or(DEFAULT_THRESHOLD, |n| n as u32);
+        let timeout = number("DB_CIRCUIT_BREAKER_TIMEOUT_SECS").map_or(DEFAULT_TIMEOUT, Duration::from_secs);
+        CircuitBreaker::new(threshold, timeout)
+    }
+
+    /// The current state; an open circuit whose timeout has passed reads as half open.
+    pub fn state(&self) -> CircuitState {
+        let mut circuit = self.circuit.lock().unwrap();
+        self.expire(&mut circuit);
+        circuit.state
+    }
+
+    fn expire(&self, circuit: &mut Circuit) {
+        if circuit.state == CircuitState::Open && circuit.opened_at.elapsed() >= self.timeout {
+            circuit.state = CircuitState::HalfOpen;
+            circuit.trial_started = None;
+        }
+    }
+
+    /// Returns whether a call may go through now.
+    fn try_acquire(&self) -> bool {
+        let mut circuit = self.circuit.lock().unwrap();
+        self.expire(&mut circuit);
+        match circuit.state {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen => {
+                let free = circuit.trial_started.is_none_or(|started| started.elapsed() >= self.timeout);
+                if free {
+                    circuit.trial_started = Some(Instant::now());
+                }
+                free
+            }
+        }
+    }
+
+    fn record_success(&self) {
+        let mut circuit = self.circuit.lock().unwrap();
+        circuit.state = CircuitState::Closed;
+        circuit.consecutive_failures = 0;
+        circuit.trial_started = None;
+    }
+
+    fn record_failure(&self) {
+        let mut circuit = self.circuit.lock().unwrap();
+        circuit.consecutive_failures = circuit.consecutive_failures.saturating_add(1);
+        if circuit.state == CircuitState::HalfOpen || circuit.consecutive_failures >= self.threshold {
+            if circuit.state != CircuitState::Open {
+                tracing::warn!(failures = circuit.consecutive_failures, "opening database circuit breaker");
+            }
+            circuit.state = CircuitState::Open;
+            circuit.opened_at = Instant::now();
+            circuit.trial_started = None;
+        }
+    }
+}
+
+/// Runs the database call `f` unless the circuit is open, and records whether it could
+/// reach the database. Errors such as a missing record count as reaching it.
+pub async fn with_circuit_breaker<T, F, Fut>(breaker: &CircuitBreaker, f: F) -> Result<T, DbErr>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, DbErr>>,
+{
+    if !breaker.try_acquire() {
+        return Err(DbErr::Custom(CIRCUIT_OPEN.to_owned()));
+    }
+    let result = f().await;
+    match &result {
+        Err(err) if is_unavailable(err) => breaker.record_failure(),
+        _ => breaker.record_success(),
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn fail(breaker: &CircuitBreaker) -> Result<(), DbErr> {
+        with_circuit_breaker(breaker, || async { Err(DbErr::ConnectionAcquire) }).await
+    }
+
+    async fn succeed(breaker: &CircuitBreaker) -> Result<(), DbErr> {
+        with_circuit_breaker(breaker, || async { Ok(()) }).await
+    }
+
+    #[actix_web::test]
+    async fn opens_after_threshold_failures() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        fail(&breaker).await.unwrap_err();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        fail(&breaker).await.unwrap_err();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(is_circuit_open(&succeed(&breaker).await.unwrap_err()));
+    }
+
+    #[actix_web::test]
+    async fn success_resets_the_failure_count() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        fail(&breaker).await.unwrap_err();
+        succeed(&breaker).await.unwrap();
+        fail(&breaker).await.unwrap_err();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[actix_web::test]
+    async fn query_errors_do_not_count() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60));
+        let missing = || async { Err::<(), _>(DbErr::RecordNotFound("post 1".to_owned())) };
+        with_circuit_breaker(&breaker, missing).await.unwrap_err();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[actix_web::test]
+    async fn half_open_trial_closes_or_reopens() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        fail(&breaker).await.unwrap_err();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        fail(&breaker).await.unwrap_err();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        succeed(&breaker).await.unwrap();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn half_open_lets_one_trial_through() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60));
+        breaker.record_failure();
+        breaker.circuit.lock().unwrap().opened_at -= Duration::from_secs(60);
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.try_acquire());
+        assert!(!breaker.try_acquire());
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+    }
+}
@@ -0,0 +1,84 @@
+use std::env;
+
+use crate::encryption::{self, Key};
+use crate::images;
+
+/// Variables the server cannot start without.
+const REQUIRED: &[&str] = &["DATABASE_URL", "HOST", "PORT", "JWT_SECRET"];
+pub const DEFAULT_UPLOAD_DIR: &str = "./uploads";
+
+/// Server settings read from the environment by `validate_env`.
+///
+/// Subsystems with settings of their own (storage, GitHub login, the scheduler, ...)
+/// still read those where they are set up.
+#[derive(Debug, Clone)]
+pub struct ValidatedConfig {
+    pub database_url: String,
+    pub host: String,
+    pub port: u16,
+    /// Defaults to `http://{HOST}:{PORT}`.
+    pub base_url: String,
+    pub jwt_secret: String,
+    /// Defaults to `./uploads`.
+    pub upload_dir: String,
+    /// Defaults to `images::DEFAULT_WEBP_QUALITY`.
+    pub webp_quality: u8,
+    /// Posts can only be encrypted when this is set.
+    pub encryption_key: Option<Key>,
+}
+
+impl ValidatedConfig {
+    pub fn server_url(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+/// Reads the server settings, reporting every missing or malformed variable at once
+/// rather than stopping at the first.
+pub fn validate_env() -> Result<ValidatedConfig, Vec<String>> {
+    let mut errors: Vec<String> = REQUIRED
+        .iter()
+        .filter(|name| env::var(name).is_err())
+        .map(|name| format!("{} is not set", name))
+        .collect();
+    let port = match env::var("PORT").map(|port| port.parse::<u16>()) {
+        Ok(Ok(port)) => port,
+        Ok(Err(_)) => {
+            errors.push("PORT must be a port number".to_owned());
+            0
+        }
+        Err(_) => 0,
+    };
+    let webp_quality = match env::var("WEBP_QUALITY").map(|quality| quality.parse::<u8>()) {
+        Ok(Ok(quality @ 0..=100)) => quality,
+        Ok(_) => {
+            errors.push("WEBP_QUALITY must be between 0 and 100".to_owned());
+            0
+        }
+        Err(_) => images::DEFAULT_WEBP_QUALITY,
+    };
+    let encryption_key = match env::var("ENCRYPTION_KEY").map(|key| encryption::parse_key(&key)) {
+        Ok(Some(key)) => Some(key),
+        Ok(None) => {
+            errors.push("ENCRYPTION_KEY must be 32 bytes, base64 encoded".to_owned());
+            None
+        }
+        Err(_) => None,
+    };
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let var = |name| env::var(name).unwrap_or_default();
+    let host = var("HOST");
+    Ok(ValidatedConfig {
+        database_url: var("DATABASE_URL"),
+        base_url: env::var("BASE_URL").unwrap_or_else(|_| format!("http://{}:{}", host, port)),
+        host,
+        port,
+        jwt_secret: var("JWT_SECRET"),
+        upload_dir: env::var("UPLOAD_DIR").unwrap_or_else(|_| DEFAULT_UPLOAD_DIR.to_owned()),
+        webp_quality,
+        encryption_key,
+    })
+}
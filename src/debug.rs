@@ -0,0 +1,111 @@
+//! Admin endpoints for local development. `main` only registers them in debug builds, so
+//! release builds answer 404 for these paths.
+
+use actix_web::{get, post, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use futures_util::{StreamExt, TryStreamExt};
+use sea_orm::{entity::*, query::*, AccessMode, ConnectionTrait, JsonValue, Statement};
+use serde::{Deserialize, Serialize};
+
+use entity::post;
+use entity::post::{Entity as Post, PostStatus};
+
+use crate::api_error::ApiError;
+use crate::auth::AdminUser;
+use crate::negotiate::Body;
+use crate::{negotiate, seeds, AppState};
+
+/// Most rows `POST /admin/debug/query` returns.
+const MAX_ROWS: usize = 1000;
+
+#[derive(Debug, Serialize)]
+struct QueryPlan {
+    query: String,
+    plan: Vec<JsonValue>,
+}
+
+/// Shows how the database runs the first page of the public post list.
+#[get("/admin/debug/query-plan")]
+async fn query_plan(req: HttpRequest,
+                    data: Data<AppState>,
+                    _admin: AdminUser,
+) -> Result<HttpResponse, Error> {
+    let conn = &data.conn;
+    let backend = conn.get_database_backend();
+    let (posts_per_page, _) = data.posts_per_page(None)?;
+    let query = Post::find()
+        .filter(post::Column::Status.eq(PostStatus::Published))
+        .order_by_asc(post::Column::Id)
+        .limit(posts_per_page as u64)
+        .build(backend)
+        .to_string();
+    let plan = JsonValue::find_by_statement(Statement::from_string(backend, format!("EXPLAIN {}", query)))
+        .all(conn)
+        .await
+        .map_err(|_| ApiError::database("could not explain query"))?;
+    negotiate::respond(&req, HttpResponse::Ok(), &QueryPlan { query, plan })
+}
+
+#[derive(Debug, Deserialize)]
+struct QueryInput {
+    sql: String,
+}
+
+#[derive(Debug, Serialize)]
+struct QueryRows {
+    rows: Vec<JsonValue>,
+    /// Whether the query had more than `MAX_ROWS` rows.
+    truncated: bool,
+}
+
+/// Runs an ad-hoc `SELECT` in a read-only transaction, which the database enforces, so a
+/// statement that passes the `SELECT` check still cannot write.
+#[post("/admin/debug/query")]
+async fn run_query(req: HttpRequest,
+                   data: Data<AppState>,
+                   admin: AdminUser,
+                   body: Body<QueryInput>,
+) -> Result<HttpResponse, Error> {
+    let sql = body.into_inner().sql;
+    let is_select = sql
+        .trim_start()
+        .get(..6)
+        .is_some_and(|keyword| keyword.eq_ignore_ascii_case("select"));
+    if !is_select {
+        return Err(ApiError::bad_request("only SELECT queries can be run").into());
+    }
+    tracing::warn!(user_id = admin.id, sql = %sql, "running debug query");
+    let conn = &data.conn;
+    let txn = conn
+        .begin_with_config(None, Some(AccessMode::ReadOnly))
+        .await
+        .map_err(|_| ApiError::database("could not start transaction"))?;
+    let statement = Statement::from_string(conn.get_database_backend(), sql);
+    let mut rows: Vec<JsonValue> = JsonValue::find_by_statement(statement)
+        .stream(&txn)
+        .await
+        .map_err(|err| ApiError::bad_request(format!("query failed: {}", err)))?
+        .take(MAX_ROWS + 1)
+        .try_collect()
+        .await
+        .map_err(|err| ApiError::bad_request(format!("query failed: {}", err)))?;
+    txn.rollback().await.map_err(|_| ApiError::database("could not end transaction"))?;
+    let truncated = rows.len() > MAX_ROWS;
+    rows.truncate(MAX_ROWS);
+    negotiate::respond(&req, HttpResponse::Ok(), &QueryRows { rows, truncated })
+}
+
+/// Same as `cargo run -- seed`.
+#[post("/admin/seed")]
+async fn seed(data: Data<AppState>, _admin: AdminUser) -> Result<HttpResponse, Error> {
+    seeds::run_seeds(&data.conn)
+        .await
+        .map_err(|_| ApiError::database("could not seed database"))?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn run_debug_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(query_plan);
+    cfg.service(run_query);
+    cfg.service(seed);
+}
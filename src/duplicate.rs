@@ -0,0 +1,164 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+use actix_web::{post, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use regex::{Captures, Regex};
+use sea_orm::{entity::*, query::*};
+use serde::{Deserialize, Serialize};
+
+use entity::post::{self, Entity as Post, PostStatus};
+use entity::post_permission::Permission;
+
+use crate::api::MAX_TEXT_LEN;
+use crate::api_error::ApiError;
+use crate::auth::AdminUser;
+use crate::negotiate::{self, Body};
+use crate::tenants::{tenanted_query, Tenant};
+use crate::{audit, encryption, permissions, AppState};
+
+/// Most posts one request may duplicate.
+const MAX_DUPLICATE_IDS: usize = 100;
+
+/// Body of `POST /admin/posts/batch-duplicate`.
+#[derive(Debug, Deserialize)]
+pub struct BatchDuplicateBody {
+    ids: Vec<u64>,
+    #[serde(default)]
+    remap_links: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Duplicate {
+    original_id: u64,
+    new_id: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct BatchDuplicateResponse {
+    created: Vec<Duplicate>,
+}
+
+fn link_pattern() -> &'static Regex {
+    static LINKS: OnceLock<Regex> = OnceLock::new();
+    LINKS.get_or_init(|| Regex::new(r"/posts/(\d+)\b").expect("the link pattern is valid"))
+}
+
+/// `text` with every `/posts/{id}` link to a post in `clones` pointing at its clone
+/// instead. Links to other posts, and longer ids that start with a cloned one, are left
+/// alone.
+fn remap_links(text: &str, clones: &HashMap<u64, u64>) -> String {
+    link_pattern()
+        .replace_all(text, |caps: &Captures| {
+            match caps[1].parse().ok().and_then(|id: u64| clones.get(&id)) {
+                Some(clone_id) => format!("/posts/{}", clone_id),
+                None => caps[0].to_owned(),
+            }
+        })
+        .into_owned()
+}
+
+/// Copies posts as new drafts of the admin, in one transaction. With `remap_links`,
+/// links between the copied posts are pointed at the copies, so a set of posts that
+/// link to each other can be duplicated as a whole.
+///
+/// Images are not copied, since the copies would share the stored files with the
+/// originals.
+#[post("/admin/posts/batch-duplicate")]
+async fn batch_duplicate(req: HttpRequest,
+                         data: Data<AppState>,
+                         tenant: Tenant,
+                         admin: AdminUser,
+                         body: Body<BatchDuplicateBody>,
+) -> Result<HttpResponse, Error> {
+    let BatchDuplicateBody { ids, remap_links: remap } = body.into_inner();
+    if ids.is_empty() {
+        return Err(ApiError::bad_request("ids must not be empty").into());
+    }
+    let mut seen = HashSet::new();
+    let ids: Vec<u64> = ids.into_iter().filter(|id| seen.insert(*id)).collect();
+    if ids.len() > MAX_DUPLICATE_IDS {
+        let message = format!("at most {} posts can be duplicated at once", MAX_DUPLICATE_IDS);
+        return Err(ApiError::validation(message).into());
+    }
+
+    let txn = data
+        .conn
+        .begin()
+        .await
+        .map_err(|_| ApiError::database("could not start transaction"))?;
+    let mut originals: HashMap<u64, post::Model> = tenanted_query::<Post>(&tenant.id)
+        .filter(post::Column::Id.is_in(ids.clone()))
+        .all(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve posts"))?
+        .into_iter()
+        .map(|post| (post.id, post))
+        .collect();
+    let mut clones = Vec::with_capacity(ids.len());
+    for id in &ids {
+        let original = originals
+            .remove(id)
+            .ok_or_else(|| ApiError::not_found(format!("post {} not found", id)))?;
+        let clone = post::ActiveModel {
+            tenant_id: Set(tenant.id.clone()),
+            title: Set(original.title.clone()),
+            text: Set(original.text.clone()),
+            status: Set(PostStatus::Draft),
+            is_encrypted: Set(original.is_encrypted),
+            blocks: Set(original.blocks.clone()),
+            ..Default::default()
+        }
+            .insert(&txn)
+            .await
+            .map_err(|_| ApiError::database("could not insert post"))?;
+        clones.push((original, clone));
+    }
+
+    if remap {
+        let clone_ids: HashMap<u64, u64> = clones.iter().map(|(original, clone)| (original.id, clone.id)).collect();
+        for (_, clone) in clones.iter_mut() {
+            let mut revealed = clone.clone();
+            encryption::reveal(&mut revealed, data.encryption_key.as_ref())
+                .map_err(|_| ApiError::internal("could not decrypt post"))?;
+            let text = remap_links(&revealed.text, &clone_ids);
+            if text == revealed.text {
+                continue;
+            }
+            let text = data.store_text(text, clone.is_encrypted)?;
+            if text.len() > MAX_TEXT_LEN {
+                let message = format!("the text of post {} must be at most {} bytes once remapped", clone.id, MAX_TEXT_LEN);
+                return Err(ApiError::validation(message).into());
+            }
+            let mut remapped: post::ActiveModel = clone.clone().into();
+            remapped.text = Set(text);
+            *clone = remapped
+                .update(&txn)
+                .await
+                .map_err(|_| ApiError::database("could not remap links"))?;
+        }
+    }
+
+    for (_, clone) in &clones {
+        permissions::grant(&txn, admin.id, clone.id, Permission::Admin)
+            .await
+            .map_err(|_| ApiError::database("could not grant permission"))?;
+        audit::post_created(&txn, Some(admin.id), clone)
+            .await
+            .map_err(|_| ApiError::database("could not record duplication"))?;
+    }
+    txn.commit()
+        .await
+        .map_err(|_| ApiError::database("could not commit duplication"))?;
+
+    data.similar_posts.invalidate();
+    let created = clones
+        .iter()
+        .map(|(original, clone)| Duplicate { original_id: original.id, new_id: clone.id })
+        .collect();
+    negotiate::respond(&req, HttpResponse::Created(), &BatchDuplicateResponse { created })
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(batch_duplicate);
+}
@@ -0,0 +1,127 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+use serde::Deserialize;
+
+use entity::post;
+
+const NONCE_LEN: usize = 12;
+
+/// An AES-256 key, from the base64 `ENCRYPTION_KEY`.
+pub type Key = [u8; 32];
+
+#[derive(Debug)]
+pub enum EncryptionError {
+    /// Encryption is needed but `ENCRYPTION_KEY` is not set.
+    MissingKey,
+    /// The stored text is not something `seal` wrote.
+    Malformed,
+    /// The key is wrong or the ciphertext was changed.
+    Failed,
+}
+
+/// `?encrypt=1` on the post create and update routes.
+#[derive(Debug, Deserialize)]
+pub struct EncryptParams {
+    encrypt: Option<String>,
+}
+
+impl EncryptParams {
+    pub fn requested(&self) -> bool {
+        self.encrypt.as_deref() == Some("1")
+    }
+}
+
+/// Decodes the base64 `ENCRYPTION_KEY` value.
+pub fn parse_key(encoded: &str) -> Option<Key> {
+    BASE64.decode(encoded.trim()).ok()?.try_into().ok()
+}
+
+/// Encrypts `plaintext` under `key` with a fresh random nonce.
+pub fn encrypt(plaintext: &str, key: &Key) -> (Vec<u8>, [u8; NONCE_LEN]) {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256Gcm::new(key.into())
+        .encrypt(&nonce, plaintext.as_bytes())
+        .expect("the plaintext fits in one AES-GCM message");
+    (ciphertext, nonce.into())
+}
+
+pub fn decrypt(ciphertext: &[u8], nonce: &[u8; NONCE_LEN], key: &Key) -> Result<String, EncryptionError> {
+    let plaintext = Aes256Gcm::new(key.into())
+        .decrypt(&Nonce::from(*nonce), ciphertext)
+        .map_err(|_| EncryptionError::Failed)?;
+    String::from_utf8(plaintext).map_err(|_| EncryptionError::Malformed)
+}
+
+/// Encrypts `plaintext` into the form kept in `posts.text`: the nonce followed by the
+/// ciphertext, base64 encoded.
+pub fn seal(plaintext: &str, key: &Key) -> String {
+    let (ciphertext, nonce) = encrypt(plaintext, key);
+    BASE64.encode([&nonce[..], &ciphertext].concat())
+}
+
+/// Reverses `seal`.
+pub fn open(sealed: &str, key: &Key) -> Result<String, EncryptionError> {
+    let bytes = BASE64.decode(sealed).map_err(|_| EncryptionError::Malformed)?;
+    if bytes.len() < NONCE_LEN {
+        return Err(EncryptionError::Malformed);
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    decrypt(ciphertext, nonce.try_into().unwrap(), key)
+}
+
+/// Returns what to keep in `posts.text` for `text`: sealed when `encrypt` is set.
+pub fn store(text: String, encrypt: bool, key: Option<&Key>) -> Result<String, EncryptionError> {
+    match encrypt {
+        true => Ok(seal(&text, key.ok_or(EncryptionError::MissingKey)?)),
+        false => Ok(text),
+    }
+}
+
+/// Replaces the stored text of `post` with its plaintext when it is encrypted.
+pub fn reveal(post: &mut post::Model, key: Option<&Key>) -> Result<(), EncryptionError> {
+    if post.is_encrypted {
+        post.text = open(&post.text, key.ok_or(EncryptionError::MissingKey)?)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: Key = [7; 32];
+
+    #[test]
+    fn open_reverses_seal() {
+        let sealed = seal("secret text", &KEY);
+        assert_ne!(sealed, "secret text");
+        assert_eq!(open(&sealed, &KEY).unwrap(), "secret text");
+    }
+
+    #[test]
+    fn seal_uses_a_fresh_nonce() {
+        assert_ne!(seal("secret text", &KEY), seal("secret text", &KEY));
+    }
+
+    #[test]
+    fn open_fails_with_another_key() {
+        let sealed = seal("secret text", &KEY);
+        assert!(matches!(open(&sealed, &[8; 32]), Err(EncryptionError::Failed)));
+    }
+
+    #[test]
+    fn open_fails_on_changed_ciphertext() {
+        let mut bytes = BASE64.decode(seal("secret text", &KEY)).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        assert!(matches!(open(&BASE64.encode(bytes), &KEY), Err(EncryptionError::Failed)));
+    }
+
+    #[test]
+    fn open_rejects_malformed_text() {
+        assert!(matches!(open("not base64!", &KEY), Err(EncryptionError::Malformed)));
+        assert!(matches!(open(&BASE64.encode([0; NONCE_LEN - 1]), &KEY), Err(EncryptionError::Malformed)));
+    }
+}
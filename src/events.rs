@@ -0,0 +1,41 @@
+use actix_web::{get, web, Error, HttpResponse};
+use actix_web::web::{Bytes, Data};
+use futures_util::stream;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::AppState;
+
+/// How many unsent events a slow SSE client may fall behind before skipping ahead.
+pub const EVENT_BUFFER: usize = 64;
+
+pub fn channel() -> broadcast::Sender<u64> {
+    broadcast::channel(EVENT_BUFFER).0
+}
+
+fn post_created(id: u64) -> Bytes {
+    Bytes::from(format!("event: post-created\ndata: {{\"id\":{}}}\n\n", id))
+}
+
+/// Streams `post-created` events; actix drops the stream (and the receiver
+/// with it) as soon as writing to a disconnected client fails.
+#[get("/events/posts")]
+async fn post_events(data: Data<AppState>) -> HttpResponse {
+    let receiver = data.post_events.subscribe();
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(id) => return Some((Ok::<_, Error>(post_created(id)), receiver)),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("cache-control", "no-cache"))
+        .streaming(events)
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(post_events);
+}
@@ -0,0 +1,89 @@
+use std::collections::HashSet;
+use std::io::{Cursor, Write};
+
+use actix_web::{get, web, Error, HttpResponse};
+use actix_web::http::header;
+use actix_web::web::Data;
+use chrono::{Datelike, Utc};
+use sea_orm::{entity::*, query::*};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use entity::post::{self, Entity as Post, PostStatus};
+
+use crate::api_error::ApiError;
+use crate::auth::AdminUser;
+use crate::suggestions;
+use crate::tenants::{tenanted_query, Tenant};
+use crate::AppState;
+
+/// `post` as a Markdown file with YAML front matter. Posts have no tags, so `tags` is
+/// always empty; it is there for tools that expect it.
+fn to_markdown(post: &post::Model, slug: &str) -> String {
+    // JSON strings are valid YAML scalars, so this quotes any title safely
+    let title = serde_json::to_string(&post.title).unwrap_or_default();
+    format!(
+        "---\ntitle: {}\ndate: {}\ntags: []\nslug: {}\n---\n\n{}\n",
+        title,
+        post.created_at.to_rfc3339(),
+        slug,
+        post.text
+    )
+}
+
+/// The slug of `post`, from its title or its id when that has none, made unique among
+/// `taken` by appending the id.
+fn unique_slug(post: &post::Model, taken: &mut HashSet<String>) -> String {
+    let slug = Some(suggestions::slugify(&post.title))
+        .filter(|slug| !slug.is_empty())
+        .unwrap_or_else(|| format!("post-{}", post.id));
+    if taken.insert(slug.clone()) {
+        return slug;
+    }
+    let slug = format!("{}-{}", slug, post.id);
+    taken.insert(slug.clone());
+    slug
+}
+
+/// A ZIP archive of `posts`, one `YYYY/slug.md` file each by the year they were created.
+fn build_archive(posts: &[post::Model]) -> zip::result::ZipResult<Vec<u8>> {
+    let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+    let mut taken = HashSet::new();
+    for post in posts {
+        let year = post.created_at.year();
+        let slug = unique_slug(post, &mut taken);
+        archive.start_file(format!("{}/{}.md", year, slug), options)?;
+        archive.write_all(to_markdown(post, &slug).as_bytes())?;
+    }
+    Ok(archive.finish()?.into_inner())
+}
+
+/// Every published post of the tenant as Markdown files in a ZIP archive, for backups.
+/// Encrypted posts are exported decrypted.
+#[get("/admin/export/zip")]
+async fn export_zip(data: Data<AppState>,
+                    tenant: Tenant,
+                    _admin: AdminUser,
+) -> Result<HttpResponse, Error> {
+    let mut posts = tenanted_query::<Post>(&tenant.id)
+        .filter(post::Column::Status.eq(PostStatus::Published))
+        .order_by_asc(post::Column::CreatedAt)
+        .order_by_asc(post::Column::Id)
+        .all(&data.conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve posts"))?;
+    for post in posts.iter_mut() {
+        data.reveal(post)?;
+    }
+    let archive = build_archive(&posts).map_err(|_| ApiError::internal("could not build archive"))?;
+    let filename = format!("blog-export-{}.zip", Utc::now().format("%Y-%m-%d"));
+    Ok(HttpResponse::Ok()
+        .content_type("application/zip")
+        .insert_header((header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)))
+        .body(archive))
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(export_zip);
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use actix_web::{get, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use sea_orm::{entity::*, DatabaseConnection, DbErr};
+
+use entity::feature_flag;
+use entity::feature_flag::Entity as FeatureFlag;
+
+use crate::auth::AuthUser;
+use crate::{negotiate, stable_hash, AppState};
+
+/// In-memory copy of the `feature_flags` table, refreshed by the scheduler.
+///
+/// Flags missing from the table are off.
+#[derive(Debug, Clone, Default)]
+pub struct FeatureFlags(Arc<RwLock<HashMap<String, feature_flag::Model>>>);
+
+impl FeatureFlags {
+    /// Replaces the cached flags with the current contents of the table.
+    pub async fn refresh(&self, conn: &DatabaseConnection) -> Result<(), DbErr> {
+        let flags = FeatureFlag::find().all(conn).await?;
+        let flags = flags.into_iter().map(|flag| (flag.name.clone(), flag)).collect();
+        *self.0.write().unwrap() = flags;
+        Ok(())
+    }
+
+    /// Returns whether `flag` is on for everyone.
+    pub fn is_enabled(&self, flag: &str) -> bool {
+        self.0
+            .read()
+            .unwrap()
+            .get(flag)
+            .is_some_and(|flag| flag.enabled && flag.rollout_percent >= 100)
+    }
+
+    /// Returns whether `flag` is on for `user_id`. Each user lands in a fixed bucket per
+    /// flag, so raising `rollout_percent` only ever adds users.
+    pub fn is_enabled_for_user(&self, flag: &str, user_id: u64) -> bool {
+        self.0
+            .read()
+            .unwrap()
+            .get(flag)
+            .is_some_and(|entry| entry.enabled && bucket(flag, user_id) < entry.rollout_percent as u64)
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.0.read().unwrap().keys().cloned().collect()
+    }
+}
+
+/// Maps `user_id` to a fixed bucket in 0..100 for `flag`.
+fn bucket(flag: &str, user_id: u64) -> u64 {
+    stable_hash::fnv1a(flag.bytes().chain(Some(b':')).chain(user_id.to_le_bytes())) % 100
+}
+
+/// Lists the flags that are on for the caller, for clients that gate features themselves.
+#[get("/api/v1/features")]
+async fn enabled_features(req: HttpRequest,
+                          data: Data<AppState>,
+                          user: Option<AuthUser>,
+) -> Result<HttpResponse, Error> {
+    let flags = &data.feature_flags;
+    let mut enabled: Vec<String> = flags
+        .names()
+        .into_iter()
+        .filter(|name| match user {
+            Some(user) => flags.is_enabled_for_user(name, user.id),
+            None => flags.is_enabled(name),
+        })
+        .collect();
+    enabled.sort();
+    negotiate::respond(&req, HttpResponse::Ok(), &enabled)
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(enabled_features);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_is_stable() {
+        assert_eq!(bucket("new-editor", 42), bucket("new-editor", 42));
+        assert_eq!(bucket("new-editor", 42), stable_hash::fnv1a(*b"new-editor:\x2a\0\0\0\0\0\0\0") % 100);
+    }
+
+    #[test]
+    fn bucket_is_below_100() {
+        assert!((0..1000).all(|user_id| bucket("new-editor", user_id) < 100));
+    }
+
+    #[test]
+    fn bucket_spreads_users() {
+        let in_first_half = (0..1000).filter(|&user_id| bucket("new-editor", user_id) < 50).count();
+        assert!((400..600).contains(&in_first_half), "{} of 1000 users in the first half", in_first_half);
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use regex::Regex;
+use tera::{Tera, Value};
+
+const ELLIPSIS: char = '…';
+
+fn tag_pattern() -> &'static Regex {
+    static TAGS: OnceLock<Regex> = OnceLock::new();
+    TAGS.get_or_init(|| Regex::new(r"<[^>]*>").expect("the tag pattern is valid"))
+}
+
+/// The text of `html`, without tags and with entities decoded.
+pub fn html_to_text(html: &str) -> String {
+    let stripped = tag_pattern().replace_all(html, "");
+    html_escape::decode_html_entities(&stripped).into_owned()
+}
+
+/// Reduces `html` to its text and cuts it to `length` characters, ending with "…" when
+/// anything was cut. Entities count as the one character they stand for.
+///
+/// The result is escaped again, since templates are not autoescaped.
+pub fn truncate_html(html: &str, length: usize) -> String {
+    let text = html_to_text(html);
+    let mut chars = text.chars();
+    let mut truncated: String = chars.by_ref().take(length).collect();
+    if chars.next().is_some() {
+        truncated.push(ELLIPSIS);
+    }
+    html_escape::encode_text(&truncated).into_owned()
+}
+
+/// Registers `{{ post.text | truncate_html(length=100) }}` with `tera`.
+pub fn register(tera: &mut Tera) {
+    tera.register_filter("truncate_html", |value: &Value, args: &HashMap<String, Value>| {
+        let html = value
+            .as_str()
+            .ok_or_else(|| tera::Error::msg("truncate_html needs a string"))?;
+        let length = args
+            .get("length")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| tera::Error::msg("truncate_html needs a length argument"))?;
+        Ok(Value::String(truncate_html(html, length as usize)))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_html_keeps_short_text() {
+        assert_eq!(truncate_html("<p>Hello <b>world</b></p>", 20), "Hello world");
+    }
+
+    #[test]
+    fn truncate_html_cuts_long_text() {
+        assert_eq!(truncate_html("<p>Hello <b>world</b></p>", 5), "Hello…");
+        assert_eq!(truncate_html("héllo wörld", 4), "héll…");
+    }
+
+    #[test]
+    fn truncate_html_counts_entities_as_one_character() {
+        assert_eq!(truncate_html("a&amp;b", 3), "a&amp;b");
+        assert_eq!(truncate_html("a&amp;bc", 2), "a&amp;…");
+    }
+
+    #[test]
+    fn truncate_html_escapes_decoded_text() {
+        assert_eq!(truncate_html("&lt;script&gt;", 100), "&lt;script&gt;");
+    }
+}
@@ -0,0 +1,171 @@
+use std::env;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use actix_web::{post, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use maxminddb::{geoip2, Reader};
+use sea_orm::entity::*;
+use serde::{Deserialize, Serialize};
+
+use entity::access_log;
+
+use crate::api_error::ApiError;
+use crate::negotiate::Body;
+use crate::tenants::Tenant;
+use crate::AppState;
+
+const MAX_PATH_LEN: usize = 255;
+
+/// Where a reader is, as far as the database knows.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Location {
+    /// ISO 3166-1 alpha-2 code, e.g. `DE`.
+    pub country_code: Option<String>,
+    /// English name of the city.
+    pub city_name: Option<String>,
+}
+
+/// The GeoLite2-City database from `GEOIP_DB_PATH`, loaded into memory at startup.
+/// Without it every lookup finds nothing.
+#[derive(Debug, Clone, Default)]
+pub struct GeoIp {
+    reader: Option<Arc<Reader<Vec<u8>>>>,
+}
+
+impl GeoIp {
+    /// A missing or unreadable database is logged and leaves lookups disabled rather than
+    /// stopping the server, since locations only feed analytics.
+    pub fn from_env() -> Self {
+        let Ok(path) = env::var("GEOIP_DB_PATH") else {
+            return GeoIp::default();
+        };
+        match Reader::open_readfile(&path) {
+            Ok(reader) => GeoIp { reader: Some(Arc::new(reader)) },
+            Err(err) => {
+                tracing::warn!(%err, path, "could not load the GeoIP database");
+                GeoIp::default()
+            }
+        }
+    }
+
+    /// The location of `ip`; `None` for addresses not on the public internet, which the
+    /// database has no entries for, and for public ones it doesn't know either.
+    pub fn lookup(&self, ip: IpAddr) -> Option<Location> {
+        let reader = self.reader.as_ref()?;
+        if !is_public(ip) {
+            return None;
+        }
+        let city: geoip2::City = reader.lookup(ip).ok()?;
+        let location = Location {
+            country_code: city.country.and_then(|country| country.iso_code).map(str::to_owned),
+            city_name: city
+                .city
+                .and_then(|city| city.names)
+                .and_then(|names| names.get("en").map(|name| name.to_string())),
+        };
+        Some(location)
+    }
+
+    /// The location of the client of `req`, from `trusted_client_ip`.
+    pub fn locate(&self, req: &HttpRequest) -> Option<Location> {
+        self.lookup(trusted_client_ip(req)?)
+    }
+
+    /// Logs the location of the client of `req` as the `country_code` and `city_name`
+    /// fields.
+    pub fn log_location(&self, req: &HttpRequest) {
+        let Some(location) = self.locate(req) else {
+            return;
+        };
+        tracing::info!(
+            path = req.path(),
+            country_code = location.country_code.as_deref(),
+            city_name = location.city_name.as_deref(),
+            "client location"
+        );
+    }
+}
+
+/// Whether `ip` is routed on the public internet, i.e. none of the private, loopback,
+/// link-local, shared, documentation and other special ranges.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            // 100.64.0.0/10 is shared by carrier-grade NATs
+            let shared = a == 100 && (64..128).contains(&b);
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || shared)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                // fc00::/7 is unique local, fe80::/10 link-local
+                let unique_local = first & 0xfe00 == 0xfc00;
+                let link_local = first & 0xffc0 == 0xfe80;
+                !(ip.is_loopback() || ip.is_unspecified() || unique_local || link_local)
+            }
+        },
+    }
+}
+
+/// The address of the client of `req`. `X-Forwarded-For` is only believed when the peer
+/// is a proxy on this host, and then only its last entry, the one that proxy added;
+/// anyone else could send the header with any address in it.
+pub fn trusted_client_ip(req: &HttpRequest) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+    if !peer.is_loopback() {
+        return Some(peer);
+    }
+    let forwarded = req
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.rsplit(',').next())
+        .and_then(|ip| ip.trim().parse().ok());
+    Some(forwarded.unwrap_or(peer))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccessEvent {
+    path: String,
+}
+
+/// Records a view of `path` with the location of its reader, for the beacons of pages
+/// that are served from a cache. The address itself is not stored.
+#[post("/api/v1/access-log")]
+async fn record_access(req: HttpRequest,
+                       data: Data<AppState>,
+                       tenant: Tenant,
+                       body: Body<AccessEvent>,
+) -> Result<HttpResponse, Error> {
+    let event = body.into_inner();
+    if !event.path.starts_with('/') || event.path.chars().count() > MAX_PATH_LEN {
+        let message = format!("path must start with / and be at most {} characters", MAX_PATH_LEN);
+        return Err(ApiError::validation(message).into());
+    }
+    let location = data.geoip.locate(&req);
+    access_log::ActiveModel {
+        tenant_id: Set(tenant.id.clone()),
+        path: Set(event.path),
+        country_code: Set(location.as_ref().and_then(|location| location.country_code.clone())),
+        city_name: Set(location.and_then(|location| location.city_name)),
+        created_at: Set(chrono::Utc::now()),
+        ..Default::default()
+    }
+        .insert(&data.conn)
+        .await
+        .map_err(|_| ApiError::database("could not record access"))?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(record_access);
+}
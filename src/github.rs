@@ -0,0 +1,153 @@
+use std::env;
+
+use actix_web::{get, http::header, web, Error, HttpRequest, HttpResponse};
+use actix_web::cookie::{time::Duration, Cookie, SameSite};
+use actix_web::web::Data;
+use oauth2::basic::BasicClient;
+use oauth2::reqwest::async_http_client;
+use oauth2::{
+    AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, RedirectUrl, Scope,
+    TokenResponse, TokenUrl,
+};
+use sea_orm::{entity::*, query::*};
+use serde::Deserialize;
+
+use entity::user;
+use entity::user::Entity as User;
+
+use crate::api_error::ApiError;
+use crate::admin;
+use crate::AppState;
+
+const AUTHORIZE_URL: &str = "https://github.com/login/oauth/authorize";
+const TOKEN_URL: &str = "https://github.com/login/oauth/access_token";
+const PROFILE_URL: &str = "https://api.github.com/user";
+const STATE_COOKIE: &str = "oauth_state";
+const STATE_TTL_MINUTES: i64 = 10;
+
+#[derive(Debug, Deserialize)]
+pub struct CallbackParams {
+    code: String,
+    state: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Profile {
+    id: u64,
+    login: String,
+}
+
+/// Builds the GitHub OAuth2 client from `GITHUB_CLIENT_ID` and `GITHUB_CLIENT_SECRET`.
+///
+/// Returns `None` when they are not set, which leaves GitHub login disabled.
+pub fn client_from_env(base_url: &str) -> Option<BasicClient> {
+    let client_id = env::var("GITHUB_CLIENT_ID").ok()?;
+    let client_secret = env::var("GITHUB_CLIENT_SECRET")
+        .expect("GITHUB_CLIENT_SECRET is not set in .env file");
+    let redirect_url = format!("{}/auth/github/callback", base_url.trim_end_matches('/'));
+    let client = BasicClient::new(
+        ClientId::new(client_id),
+        Some(ClientSecret::new(client_secret)),
+        AuthUrl::new(AUTHORIZE_URL.to_owned()).unwrap(),
+        Some(TokenUrl::new(TOKEN_URL.to_owned()).unwrap()),
+    )
+        .set_redirect_uri(RedirectUrl::new(redirect_url).expect("BASE_URL is not a valid url"));
+    Some(client)
+}
+
+fn client(data: &AppState) -> Result<&BasicClient, Error> {
+    data.github
+        .as_ref()
+        .ok_or_else(|| ApiError::not_found("github login is not configured").into())
+}
+
+fn state_cookie(state: String) -> Cookie<'static> {
+    // Lax so the cookie still comes along on GitHub's redirect back to the callback
+    Cookie::build(STATE_COOKIE, state)
+        .path("/auth/github")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .max_age(Duration::minutes(STATE_TTL_MINUTES))
+        .finish()
+}
+
+#[get("/auth/github")]
+async fn authorize(data: Data<AppState>) -> Result<HttpResponse, Error> {
+    let (url, state) = client(&data)?
+        .authorize_url(CsrfToken::new_random)
+        .add_scope(Scope::new("read:user".to_owned()))
+        .url();
+    Ok(HttpResponse::Found()
+        .cookie(state_cookie(state.secret().to_owned()))
+        .append_header((header::LOCATION, url.to_string()))
+        .finish())
+}
+
+#[get("/auth/github/callback")]
+async fn callback(req: HttpRequest,
+                  data: Data<AppState>,
+                  params: web::Query<CallbackParams>,
+) -> Result<HttpResponse, Error> {
+    let client = client(&data)?;
+    let expected = req
+        .cookie(STATE_COOKIE)
+        .ok_or_else(|| ApiError::bad_request("missing oauth state"))?;
+    if expected.value() != params.state {
+        return Err(ApiError::bad_request("oauth state mismatch").into());
+    }
+
+    let token = client
+        .exchange_code(AuthorizationCode::new(params.code.clone()))
+        .request_async(async_http_client)
+        .await
+        .map_err(|_| ApiError::upstream("could not exchange oauth code"))?;
+    let profile: Profile = reqwest::Client::new()
+        .get(PROFILE_URL)
+        .bearer_auth(token.access_token().secret())
+        .header(header::USER_AGENT.as_str(), "sea-orm-demo")
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|_| ApiError::upstream("could not fetch github profile"))?
+        .json()
+        .await
+        .map_err(|_| ApiError::upstream("invalid github profile"))?;
+
+    let user = find_or_create_user(&data, profile).await?;
+    let (token, _) = admin::session_token(&data.jwt_secret, &user)?;
+    let mut removal = state_cookie(String::new());
+    removal.make_removal();
+    Ok(HttpResponse::Found()
+        .cookie(admin::token_cookie(token, &data.base_url))
+        .cookie(removal)
+        .append_header((header::LOCATION, "/"))
+        .finish())
+}
+
+async fn find_or_create_user(data: &AppState, profile: Profile) -> Result<user::Model, Error> {
+    let conn = &data.conn;
+    let github_id = profile.id.to_string();
+    let user = User::find()
+        .filter(user::Column::GithubId.eq(github_id.as_str()))
+        .one(conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve user"))?;
+    if let Some(user) = user {
+        return Ok(user);
+    }
+    user::ActiveModel {
+        username: Set(profile.login),
+        github_id: Set(Some(github_id)),
+        is_admin: Set(false),
+        totp_enabled: Set(false),
+        ..Default::default()
+    }
+        .insert(conn)
+        .await
+        .map_err(|_| ApiError::conflict("could not create user, the username may be taken").into())
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(authorize);
+    cfg.service(callback);
+}
@@ -0,0 +1,265 @@
+use actix_web::{get, web, Either, HttpResponse};
+use actix_web::http::header;
+use actix_web::web::Data;
+use async_graphql::http::{playground_source, GraphQLPlaygroundConfig};
+use async_graphql::parser::types::OperationType;
+use async_graphql::{Context, EmptySubscription, Object, Result, Schema, SimpleObject};
+use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
+use sea_orm::{entity::*, query::*};
+
+use entity::post;
+use entity::post::{Entity as PostEntity, PostStatus};
+use entity::post_permission::Permission;
+
+use crate::auth::AuthUser;
+use crate::broadcast::{PostEvent, PostEventKind};
+use crate::circuit_breaker::with_circuit_breaker;
+use crate::jobs::Job;
+use crate::tenants::{tenanted_query, Tenant};
+use crate::{audit, images, permissions, AppState};
+
+pub type PostSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+#[derive(SimpleObject)]
+struct Post {
+    id: u64,
+    title: String,
+    text: String,
+}
+
+impl From<post::Model> for Post {
+    fn from(post: post::Model) -> Self {
+        Post { id: post.id, title: post.title, text: post.text }
+    }
+}
+
+#[derive(SimpleObject)]
+struct PostConnection {
+    posts: Vec<Post>,
+    page: u64,
+    per_page: u64,
+    num_pages: u64,
+}
+
+fn state<'a>(ctx: &Context<'a>) -> Result<&'a Data<AppState>> {
+    ctx.data::<Data<AppState>>()
+}
+
+fn tenant<'a>(ctx: &Context<'a>) -> Result<&'a Tenant> {
+    ctx.data::<Tenant>()
+}
+
+fn require_user(ctx: &Context<'_>) -> Result<AuthUser> {
+    ctx.data_opt::<AuthUser>().copied().ok_or_else(|| "unauthorized".into())
+}
+
+async fn require_write(data: &AppState, user: &AuthUser, post_id: u64) -> Result<()> {
+    let check = || permissions::can_write(&data.conn, user, post_id);
+    match with_circuit_breaker(&data.circuit_breaker, check).await? {
+        true => Ok(()),
+        false => Err("forbidden".into()),
+    }
+}
+
+/// Converts `post` for a response, decrypting its text if needed.
+fn reveal(data: &AppState, mut post: post::Model) -> Result<Post> {
+    data.reveal(&mut post).map_err(|err| err.to_string())?;
+    Ok(post.into())
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn posts(&self,
+                   ctx: &Context<'_>,
+                   page: Option<u64>,
+                   per_page: Option<u64>,
+    ) -> Result<PostConnection> {
+        let data = state(ctx)?;
+        let conn = &data.conn;
+        let page = page.unwrap_or(1).max(1);
+        // the response carries the page size actually used, so clamping needs no warning
+        let (per_page, _) = data
+            .page_size_limits
+            .apply(per_page.map(|size| size as usize), data.default_posts_per_page())
+            .ok_or_else(|| format!("perPage must be at least {}", data.page_size_limits.min))?;
+        if let Some(message) = data.page_size_limits.depth_error(page as usize, per_page) {
+            return Err(message.into());
+        }
+        let per_page = per_page as u64;
+        let paginator = tenanted_query::<PostEntity>(&tenant(ctx)?.id)
+            .filter(post::Column::Status.eq(PostStatus::Published))
+            .order_by_asc(post::Column::Id)
+            .paginate(conn, per_page);
+        let breaker = &data.circuit_breaker;
+        let num_pages = with_circuit_breaker(breaker, || paginator.num_pages()).await?;
+        let posts = with_circuit_breaker(breaker, || paginator.fetch_page(page - 1)).await?;
+        Ok(PostConnection {
+            posts: posts.into_iter().map(|post| reveal(data, post)).collect::<Result<_>>()?,
+            page,
+            per_page,
+            num_pages,
+        })
+    }
+
+    /// `None` for unpublished posts unless the user may change them.
+    async fn post(&self, ctx: &Context<'_>, id: u64) -> Result<Option<Post>> {
+        let data = state(ctx)?;
+        let breaker = &data.circuit_breaker;
+        let find = tenanted_query::<PostEntity>(&tenant(ctx)?.id).filter(post::Column::Id.eq(id));
+        let Some(post) = with_circuit_breaker(breaker, || find.one(&data.conn)).await? else {
+            return Ok(None);
+        };
+        let check = || permissions::can_read(&data.conn, ctx.data_opt::<AuthUser>(), &post);
+        if !with_circuit_breaker(breaker, check).await? {
+            return Ok(None);
+        }
+        reveal(data, post).map(Some)
+    }
+}
+
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    async fn create_post(&self, ctx: &Context<'_>, title: String, text: String) -> Result<Post> {
+        let user = require_user(ctx)?;
+        let data = state(ctx)?;
+        data.blocked_words.check(&title, &text).map_err(|err| err.to_string())?;
+        data.post_rate_limiter.check(user.id).map_err(|err| err.to_string())?;
+        let txn = with_circuit_breaker(&data.circuit_breaker, || data.conn.begin()).await?;
+        let post = post::ActiveModel {
+            tenant_id: Set(tenant(ctx)?.id.clone()),
+            title: Set(title),
+            text: Set(text),
+            ..Default::default()
+        }
+            .insert(&txn)
+            .await?;
+        permissions::grant(&txn, user.id, post.id, Permission::Admin).await?;
+        audit::post_created(&txn, Some(user.id), &post).await?;
+        txn.commit().await?;
+        data.broadcaster.broadcast(PostEvent::new(PostEventKind::Created, post.id)).await;
+        let _ = data.post_events.send(post.id);
+        data.jobs.enqueue(Job::NotifySubscribers { post_id: post.id });
+        data.jobs.enqueue(Job::CheckLinks { post_id: post.id });
+        data.similar_posts.invalidate();
+        Ok(post.into())
+    }
+
+    async fn update_post(&self,
+                         ctx: &Context<'_>,
+                         id: u64,
+                         title: String,
+                         text: String,
+    ) -> Result<Post> {
+        let user = require_user(ctx)?;
+        let data = state(ctx)?;
+        require_write(data, &user, id).await?;
+        data.blocked_words.check(&title, &text).map_err(|err| err.to_string())?;
+        let txn = with_circuit_breaker(&data.circuit_breaker, || data.conn.begin()).await?;
+        let old = tenanted_query::<PostEntity>(&tenant(ctx)?.id)
+            .filter(post::Column::Id.eq(id))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or("post not found")?;
+        let mut post: post::ActiveModel = old.clone().into();
+        post.title = Set(title);
+        // an encrypted post stays encrypted
+        post.text = Set(data.store_text(text, old.is_encrypted).map_err(|err| err.to_string())?);
+        let post = post.update(&txn).await?;
+        audit::post_updated(&txn, Some(user.id), &old, &post).await?;
+        txn.commit().await?;
+        data.broadcaster.broadcast(PostEvent::new(PostEventKind::Updated, post.id)).await;
+        data.jobs.enqueue(Job::CheckLinks { post_id: post.id });
+        data.similar_posts.invalidate();
+        reveal(data, post)
+    }
+
+    async fn delete_post(&self, ctx: &Context<'_>, id: u64) -> Result<bool> {
+        let user = require_user(ctx)?;
+        let data = state(ctx)?;
+        require_write(data, &user, id).await?;
+        let find = tenanted_query::<PostEntity>(&tenant(ctx)?.id).filter(post::Column::Id.eq(id));
+        let breaker = &data.circuit_breaker;
+        let post = match with_circuit_breaker(breaker, || find.one(&data.conn)).await? {
+            Some(post) => post,
+            None => return Ok(false),
+        };
+        let txn = with_circuit_breaker(breaker, || data.conn.begin()).await?;
+        audit::post_deleted(&txn, Some(user.id), &post).await?;
+        post::ActiveModel::from(post.clone()).delete(&txn).await?;
+        txn.commit().await?;
+        images::delete_images(data.storage.as_ref(), &post).await;
+        data.similar_posts.invalidate();
+        data.broadcaster.broadcast(PostEvent::new(PostEventKind::Deleted, id)).await;
+        Ok(true)
+    }
+}
+
+pub fn schema() -> PostSchema {
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription).finish()
+}
+
+async fn graphql(data: Data<AppState>,
+                 schema: Data<PostSchema>,
+                 tenant: Tenant,
+                 user: Option<AuthUser>,
+                 request: GraphQLRequest,
+) -> GraphQLResponse {
+    execute(data, schema, tenant, user, request.into_inner()).await
+}
+
+/// Queries only: a GET can be triggered by any page the user visits, such as through an
+/// `<img>`, and would carry the token cookie, so mutations must come by POST.
+async fn graphql_get(data: Data<AppState>,
+                     schema: Data<PostSchema>,
+                     tenant: Tenant,
+                     user: Option<AuthUser>,
+                     request: GraphQLRequest,
+) -> Either<GraphQLResponse, HttpResponse> {
+    let mut request = request.into_inner();
+    // unparsable queries are left for `execute` to report
+    let is_mutation = request.parsed_query().is_ok_and(|query| {
+        query.operations.iter().any(|(_, operation)| operation.node.ty == OperationType::Mutation)
+    });
+    if is_mutation {
+        return Either::Right(
+            HttpResponse::MethodNotAllowed()
+                .insert_header((header::ALLOW, "POST"))
+                .body("mutations must be sent with POST"),
+        );
+    }
+    Either::Left(execute(data, schema, tenant, user, request).await)
+}
+
+async fn execute(data: Data<AppState>,
+                 schema: Data<PostSchema>,
+                 tenant: Tenant,
+                 user: Option<AuthUser>,
+                 request: async_graphql::Request,
+) -> GraphQLResponse {
+    let mut request = request.data(data).data(tenant);
+    if let Some(user) = user {
+        request = request.data(user);
+    }
+    schema.execute(request).await.into()
+}
+
+#[get("/graphql-ui")]
+async fn graphql_ui() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(playground_source(GraphQLPlaygroundConfig::new("/graphql")))
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/graphql")
+            .route(web::get().to(graphql_get))
+            .route(web::post().to(graphql)),
+    );
+    cfg.service(graphql_ui);
+}
@@ -0,0 +1,79 @@
+use std::env;
+
+use actix_web::{get, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{ConnectionTrait, Statement};
+use serde::Serialize;
+
+use crate::circuit_breaker::CircuitState;
+use crate::{negotiate, AppState};
+
+#[derive(Debug, Serialize)]
+struct Health {
+    database: bool,
+    /// `None` when the database could not be asked.
+    pending_migrations: Option<usize>,
+    /// Probes bypass the breaker, so this can be open while `database` is already true.
+    circuit_breaker: CircuitState,
+    /// Pool checks that found every connection in use, since the server started.
+    pool_exhaustions: u64,
+}
+
+impl Health {
+    async fn check(data: &AppState) -> Self {
+        let backend = data.conn.get_database_backend();
+        let database = data
+            .conn
+            .execute(Statement::from_string(backend, "SELECT 1".to_owned()))
+            .await
+            .is_ok();
+        let pending_migrations = match database {
+            true => Migrator::get_pending_migrations(&data.conn)
+                .await
+                .map(|migrations| migrations.len())
+                .ok(),
+            false => None,
+        };
+        Health {
+            database,
+            pending_migrations,
+            circuit_breaker: data.circuit_breaker.state(),
+            pool_exhaustions: data.metrics.pool_exhaustions(),
+        }
+    }
+
+    fn migrated(&self) -> bool {
+        self.pending_migrations == Some(0)
+    }
+}
+
+/// Liveness: fails only when the database is down, or when migrations are pending and
+/// `REQUIRE_MIGRATIONS_APPLIED=1`.
+#[get("/health")]
+async fn health(req: HttpRequest, data: Data<AppState>) -> Result<HttpResponse, Error> {
+    let status = Health::check(&data).await;
+    let require_migrations = env::var("REQUIRE_MIGRATIONS_APPLIED").as_deref() == Ok("1");
+    let builder = match status.database && (status.migrated() || !require_migrations) {
+        true => HttpResponse::Ok(),
+        false => HttpResponse::ServiceUnavailable(),
+    };
+    negotiate::respond(&req, builder, &status)
+}
+
+/// Readiness: fails while the database is down or migrations are pending, so no traffic
+/// reaches an instance running against an older schema.
+#[get("/readiness")]
+async fn readiness(req: HttpRequest, data: Data<AppState>) -> Result<HttpResponse, Error> {
+    let status = Health::check(&data).await;
+    let builder = match status.database && status.migrated() {
+        true => HttpResponse::Ok(),
+        false => HttpResponse::ServiceUnavailable(),
+    };
+    negotiate::respond(&req, builder, &status)
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(health);
+    cfg.service(readiness);
+}
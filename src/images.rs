@@ -0,0 +1,203 @@
+use std::io::Cursor;
+
+use actix_multipart::Multipart;
+use actix_web::{post, web, Error, HttpResponse};
+use actix_web::web::{Bytes, Data};
+use futures_util::TryStreamExt;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageError, ImageFormat};
+use sea_orm::{entity::*, query::*};
+use serde::Serialize;
+
+use entity::post;
+use entity::post::Entity as Post;
+
+use crate::api_error::ApiError;
+use crate::auth::AuthUser;
+use crate::storage::ObjectStorage;
+use crate::tenants::{tenanted_query, Tenant};
+use crate::{permissions, AppState};
+
+const MAX_IMAGE_SIZE: usize = 5 * 1024 * 1024;
+const FEATURED_SIZE: (u32, u32) = (1200, 630);
+const THUMBNAIL_SIZE: (u32, u32) = (300, 200);
+const FEATURED_FILE: &str = "featured.jpg";
+const THUMBNAIL_FILE: &str = "thumbnail.jpg";
+const WEBP_FILE: &str = "featured.webp";
+pub const DEFAULT_WEBP_QUALITY: u8 = 80;
+
+/// Public URLs of the images generated for a post's featured image.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageUrls {
+    pub featured: String,
+    /// Missing for images uploaded before WebP variants were generated.
+    webp: Option<String>,
+    thumbnail: String,
+}
+
+/// Storage keys of the files generated from one upload.
+struct ImageKeys {
+    featured: String,
+    thumbnail: String,
+}
+
+/// The encoded files generated from one upload.
+struct ProcessedImages {
+    featured: Bytes,
+    webp: Bytes,
+    thumbnail: Bytes,
+}
+
+/// A post as rendered in listings, together with its image URLs.
+#[derive(Debug, Serialize)]
+pub struct PostWithImages {
+    #[serde(flatten)]
+    post: post::Model,
+    images: Option<ImageUrls>,
+}
+
+/// Storage keys of the images generated from a featured image key.
+fn image_keys(featured: &str) -> Option<ImageKeys> {
+    let (dir, _) = featured.rsplit_once('/')?;
+    Some(ImageKeys {
+        featured: featured.to_owned(),
+        thumbnail: format!("{}/{}", dir, THUMBNAIL_FILE),
+    })
+}
+
+pub fn image_urls(storage: &dyn ObjectStorage, post: &post::Model) -> Option<ImageUrls> {
+    let keys = image_keys(post.featured_image.as_deref()?)?;
+    Some(ImageUrls {
+        featured: storage.url(&keys.featured),
+        webp: post.featured_image_webp.as_deref().map(|key| storage.url(key)),
+        thumbnail: storage.url(&keys.thumbnail),
+    })
+}
+
+pub fn with_images(storage: &dyn ObjectStorage, posts: Vec<post::Model>) -> Vec<PostWithImages> {
+    posts
+        .into_iter()
+        .map(|post| PostWithImages { images: image_urls(storage, &post), post })
+        .collect()
+}
+
+/// Removes the stored images of a post that is being deleted.
+pub async fn delete_images(storage: &dyn ObjectStorage, post: &post::Model) {
+    let keys = match post.featured_image.as_deref().and_then(image_keys) {
+        Some(keys) => keys,
+        None => return,
+    };
+    let webp = post.featured_image_webp.clone();
+    for key in [Some(keys.featured), webp, Some(keys.thumbnail)].into_iter().flatten() {
+        if let Err(e) = storage.delete_object(&key).await {
+            tracing::warn!(post_id = post.id, key = %key, "could not delete image: {}", e);
+        }
+    }
+}
+
+fn encode_jpeg(image: DynamicImage) -> Result<Bytes, ImageError> {
+    let mut jpeg = Cursor::new(Vec::new());
+    DynamicImage::ImageRgb8(image.to_rgb8()).write_to(&mut jpeg, ImageFormat::Jpeg)?;
+    Ok(Bytes::from(jpeg.into_inner()))
+}
+
+fn encode_webp(image: &DynamicImage, quality: u8) -> Bytes {
+    let rgb = image.to_rgb8();
+    let webp = webp::Encoder::from_rgb(&rgb, rgb.width(), rgb.height()).encode(quality as f32);
+    Bytes::copy_from_slice(&webp)
+}
+
+/// Decodes the upload and returns the featured image as JPEG and WebP, and its thumbnail.
+fn process_image(bytes: &[u8], webp_quality: u8) -> Result<ProcessedImages, ImageError> {
+    let image = image::load_from_memory(bytes)?;
+    let featured = image.resize_to_fill(FEATURED_SIZE.0, FEATURED_SIZE.1, FilterType::Lanczos3);
+    let thumbnail = image.resize_to_fill(THUMBNAIL_SIZE.0, THUMBNAIL_SIZE.1, FilterType::Triangle);
+    Ok(ProcessedImages {
+        webp: encode_webp(&featured, webp_quality),
+        featured: encode_jpeg(featured)?,
+        thumbnail: encode_jpeg(thumbnail)?,
+    })
+}
+
+/// Reads the `image` field of the form, enforcing content type and size.
+async fn read_image(payload: &mut Multipart) -> Result<Vec<u8>, Error> {
+    while let Some(mut field) = payload
+        .try_next()
+        .await
+        .map_err(|_| ApiError::bad_request("invalid multipart body"))?
+    {
+        if field.name() != "image" {
+            continue;
+        }
+        match field.content_type().map(|mime| mime.essence_str()) {
+            Some("image/jpeg") | Some("image/png") => {}
+            _ => return Err(ApiError::bad_request("image must be image/jpeg or image/png").into()),
+        }
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field
+            .try_next()
+            .await
+            .map_err(|_| ApiError::bad_request("invalid multipart body"))?
+        {
+            if bytes.len() + chunk.len() > MAX_IMAGE_SIZE {
+                return Err(ApiError::bad_request("image must not exceed 5 MiB").into());
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        return Ok(bytes);
+    }
+    Err(ApiError::bad_request("missing image field").into())
+}
+
+#[post("/posts/{id}/image")]
+async fn upload_image(data: Data<AppState>,
+                      tenant: Tenant,
+                      user: AuthUser,
+                      id: web::Path<u64>,
+                      mut payload: Multipart,
+) -> Result<HttpResponse, Error> {
+    let conn = &data.conn;
+    let post = tenanted_query::<Post>(&tenant.id)
+        .filter(post::Column::Id.eq(id.into_inner()))
+        .one(conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+    permissions::require_write(conn, &user, post.id).await?;
+
+    let bytes = read_image(&mut payload).await?;
+    let webp_quality = data.webp_quality;
+    let images = web::block(move || process_image(&bytes, webp_quality))
+        .await
+        .map_err(|_| ApiError::internal("could not process image"))?
+        .map_err(|_| ApiError::bad_request("could not decode image"))?;
+
+    let featured_image = format!("posts/{}/{}", post.id, FEATURED_FILE);
+    let keys = image_keys(&featured_image).expect("featured key has a directory");
+    let webp_key = format!("posts/{}/{}", post.id, WEBP_FILE);
+    data.storage
+        .put_object(&keys.featured, images.featured, "image/jpeg")
+        .await
+        .map_err(|_| ApiError::internal("could not save image"))?;
+    data.storage
+        .put_object(&webp_key, images.webp, "image/webp")
+        .await
+        .map_err(|_| ApiError::internal("could not save webp image"))?;
+    data.storage
+        .put_object(&keys.thumbnail, images.thumbnail, "image/jpeg")
+        .await
+        .map_err(|_| ApiError::internal("could not save thumbnail"))?;
+
+    let mut post: post::ActiveModel = post.into();
+    post.featured_image = Set(Some(featured_image));
+    post.featured_image_webp = Set(Some(webp_key));
+    let post = post
+        .update(conn)
+        .await
+        .map_err(|_| ApiError::database("could not update post"))?;
+    Ok(HttpResponse::Found().append_header(("location", format!("/{}", post.id))).finish())
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(upload_image);
+}
@@ -0,0 +1,236 @@
+use std::env;
+use std::net::IpAddr;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use actix_web::{post, web, Error, HttpRequest, HttpResponse};
+use actix_web::http::header;
+use actix_web::web::Data;
+use regex::Regex;
+use reqwest::{redirect, StatusCode, Url};
+use sea_orm::{entity::*, query::*};
+use serde::Deserialize;
+
+use entity::post::{self, PostStatus};
+use entity::post_permission::Permission;
+
+use crate::api::{MAX_TEXT_LEN, MAX_TITLE_LEN};
+use crate::api_error::ApiError;
+use crate::auth::AuthUser;
+use crate::negotiate::{self, Body};
+use crate::tenants::Tenant;
+use crate::{audit, filters, geoip, permissions, AppState};
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+/// Most bytes of a page read; articles are far smaller.
+const MAX_PAGE_LEN: usize = 2 * 1024 * 1024;
+/// Redirects are followed by hand, so each target is checked like the first URL.
+const MAX_REDIRECTS: usize = 5;
+
+/// Hosts pages may be imported from, from `IMPORT_URL_ALLOWLIST` and
+/// `IMPORT_URL_BLOCKLIST` (comma-separated). A host listed covers its subdomains too.
+#[derive(Debug, Clone, Default)]
+pub struct ImportPolicy {
+    /// When not empty, only these hosts are allowed.
+    allow: Vec<String>,
+    block: Vec<String>,
+}
+
+impl ImportPolicy {
+    pub fn from_env() -> Self {
+        let hosts = |name| {
+            env::var(name)
+                .unwrap_or_default()
+                .split(',')
+                .map(|host| host.trim().trim_end_matches('.').to_ascii_lowercase())
+                .filter(|host| !host.is_empty())
+                .collect()
+        };
+        ImportPolicy { allow: hosts("IMPORT_URL_ALLOWLIST"), block: hosts("IMPORT_URL_BLOCKLIST") }
+    }
+
+    fn permits(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        let covers = |listed: &String| host == *listed || host.ends_with(&format!(".{}", listed));
+        (self.allow.is_empty() || self.allow.iter().any(covers)) && !self.block.iter().any(covers)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportUrlBody {
+    url: String,
+    #[serde(default)]
+    attribute_source: bool,
+}
+
+fn pattern(cell: &'static OnceLock<Regex>, source: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(source).expect("the import patterns are valid"))
+}
+
+/// Refuses URLs the policy doesn't permit and hosts on private networks, so the server
+/// cannot be made to fetch its own internal services.
+async fn check_url(policy: &ImportPolicy, url: &Url) -> Result<(), Error> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(ApiError::validation("url must be an http or https url").into());
+    }
+    let host = url.host_str().ok_or_else(|| ApiError::validation("url must have a host"))?;
+    if !policy.permits(host) {
+        return Err(ApiError::forbidden(format!("importing from {} is not allowed", host)).into());
+    }
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addresses: Vec<IpAddr> = tokio::net::lookup_host((host.trim_matches(['[', ']']), port))
+        .await
+        .map_err(|_| ApiError::upstream(format!("could not resolve {}", host)))?
+        .map(|address| address.ip())
+        .collect();
+    if addresses.is_empty() || !addresses.into_iter().all(geoip::is_public) {
+        return Err(ApiError::forbidden(format!("{} is not on the public internet", host)).into());
+    }
+    Ok(())
+}
+
+/// Fetches the HTML page at `url`, following redirects to permitted hosts only.
+async fn fetch_page(policy: &ImportPolicy, mut url: Url) -> Result<(Url, String), Error> {
+    let client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .redirect(redirect::Policy::none())
+        .build()
+        .map_err(|_| ApiError::internal("could not create http client"))?;
+    for _ in 0..=MAX_REDIRECTS {
+        check_url(policy, &url).await?;
+        let mut response = client
+            .get(url.clone())
+            .header(header::USER_AGENT.as_str(), "sea-orm-demo")
+            .send()
+            .await
+            .map_err(|_| ApiError::upstream("could not fetch url"))?;
+        if response.status().is_redirection() {
+            let location = response
+                .headers()
+                .get(header::LOCATION.as_str())
+                .and_then(|value| value.to_str().ok())
+                .and_then(|location| url.join(location).ok())
+                .ok_or_else(|| ApiError::upstream("url redirects nowhere"))?;
+            url = location;
+            continue;
+        }
+        if response.status() != StatusCode::OK {
+            return Err(ApiError::upstream(format!("url answered {}", response.status())).into());
+        }
+        let is_html = response
+            .headers()
+            .get(header::CONTENT_TYPE.as_str())
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|content_type| content_type.starts_with("text/html"));
+        if !is_html {
+            return Err(ApiError::validation("url is not an html page").into());
+        }
+        let mut page = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(|_| ApiError::upstream("could not read url"))? {
+            if page.len() + chunk.len() > MAX_PAGE_LEN {
+                return Err(ApiError::validation("page is too large to import").into());
+            }
+            page.extend_from_slice(&chunk);
+        }
+        return Ok((url, String::from_utf8_lossy(&page).into_owned()));
+    }
+    Err(ApiError::upstream("url redirects too often").into())
+}
+
+/// The title and text of the article in `html`: the `og:title`, `<title>` or first
+/// `<h1>`, and the paragraphs of the `<article>`, or of `<main>` or the whole page when it
+/// has none, each on its own, separated by blank lines.
+fn extract_article(html: &str) -> (Option<String>, String) {
+    static SCRIPTS: OnceLock<Regex> = OnceLock::new();
+    static OG_TITLE: OnceLock<Regex> = OnceLock::new();
+    static TITLE: OnceLock<Regex> = OnceLock::new();
+    static H1: OnceLock<Regex> = OnceLock::new();
+    static ARTICLE: OnceLock<Regex> = OnceLock::new();
+    static MAIN: OnceLock<Regex> = OnceLock::new();
+    static PARAGRAPH: OnceLock<Regex> = OnceLock::new();
+
+    let html = pattern(&SCRIPTS, r"(?is)<(script|style|noscript)\b.*?</(script|style|noscript)>").replace_all(html, "");
+    let capture = |cell: &'static OnceLock<Regex>, source: &str, html: &str| {
+        pattern(cell, source)
+            .captures(html)
+            .map(|caps| filters::html_to_text(&caps[1]).split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|text| !text.is_empty())
+    };
+    let title = capture(&OG_TITLE, r#"(?is)<meta[^>]+property=["']og:title["'][^>]+content=["']([^"']*)["']"#, &html)
+        .or_else(|| capture(&TITLE, r"(?is)<title[^>]*>(.*?)</title>", &html))
+        .or_else(|| capture(&H1, r"(?is)<h1[^>]*>(.*?)</h1>", &html));
+    let region = pattern(&ARTICLE, r"(?is)<article\b[^>]*>(.*?)</article>")
+        .captures(&html)
+        .or_else(|| pattern(&MAIN, r"(?is)<main\b[^>]*>(.*?)</main>").captures(&html))
+        .map_or(html.as_ref(), |caps| caps.get(1).map_or("", |region| region.as_str()));
+    let text = pattern(&PARAGRAPH, r"(?is)<p\b[^>]*>(.*?)</p>")
+        .captures_iter(region)
+        .map(|caps| filters::html_to_text(&caps[1]).split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|paragraph| !paragraph.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    (title, text)
+}
+
+/// Creates a draft from the article at `url`, for editors re-publishing it. With
+/// `attribute_source`, the text ends with a line naming the page it came from.
+#[post("/api/v1/posts/import-url")]
+async fn import_url(req: HttpRequest,
+                    data: Data<AppState>,
+                    tenant: Tenant,
+                    user: AuthUser,
+                    body: Body<ImportUrlBody>,
+) -> Result<HttpResponse, Error> {
+    let body = body.into_inner();
+    let url = Url::parse(&body.url).map_err(|_| ApiError::validation("url must be a valid url"))?;
+    let (url, html) = fetch_page(&data.url_import, url).await?;
+    let (title, mut text) = extract_article(&html);
+    if text.is_empty() {
+        return Err(ApiError::validation("no article text was found at url").into());
+    }
+    if body.attribute_source {
+        text.push_str(&format!("\n\nOriginally published at {}", url));
+    }
+    if text.len() > MAX_TEXT_LEN {
+        let message = format!("the article must be at most {} bytes", MAX_TEXT_LEN);
+        return Err(ApiError::validation(message).into());
+    }
+    let title: String = title
+        .unwrap_or_else(|| url.host_str().unwrap_or_default().to_owned())
+        .chars()
+        .take(MAX_TITLE_LEN)
+        .collect();
+    data.blocked_words.check(&title, &text)?;
+    data.post_rate_limiter.check(user.id)?;
+
+    let txn = data
+        .conn
+        .begin()
+        .await
+        .map_err(|_| ApiError::database("could not start transaction"))?;
+    let post = post::ActiveModel {
+        tenant_id: Set(tenant.id.clone()),
+        title: Set(title),
+        text: Set(text),
+        status: Set(PostStatus::Draft),
+        ..Default::default()
+    }
+        .insert(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not insert post"))?;
+    permissions::grant(&txn, user.id, post.id, Permission::Admin)
+        .await
+        .map_err(|_| ApiError::database("could not grant permission"))?;
+    audit::post_created(&txn, Some(user.id), &post)
+        .await
+        .map_err(|_| ApiError::database("could not record import"))?;
+    txn.commit()
+        .await
+        .map_err(|_| ApiError::database("could not commit import"))?;
+    data.similar_posts.invalidate();
+    negotiate::respond(&req, HttpResponse::Created(), &post)
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(import_url);
+}
@@ -0,0 +1,54 @@
+use actix_web::web::Data;
+use actix_web::{get, web, Error, HttpRequest, HttpResponse};
+use sea_orm::{entity::*, query::*};
+use serde::Serialize;
+
+use entity::post;
+use entity::post::Entity as Post;
+
+use crate::api_error::{ApiError, ApiErrorCode};
+use crate::auth::AdminUser;
+use crate::{negotiate, AppState};
+
+/// Posts hashed per query by the integrity check.
+const SCAN_BATCH: u64 = 500;
+
+#[derive(Debug, Serialize)]
+struct IntegrityReport {
+    checked: u64,
+    mismatched: Vec<u64>,
+}
+
+/// Fails with 500 when the stored title or text of `post` no longer matches its hash.
+pub fn verify(post: &post::Model) -> Result<(), Error> {
+    if post.has_valid_content_hash() {
+        return Ok(());
+    }
+    tracing::error!(post_id = post.id, "post content does not match its hash");
+    Err(ApiError::new(ApiErrorCode::IntegrityCheckFailed, "integrity check failed").into())
+}
+
+/// Hashes every post and lists those whose content no longer matches.
+#[get("/admin/integrity-check")]
+async fn integrity_check(req: HttpRequest,
+                         data: Data<AppState>,
+                         _admin: AdminUser,
+) -> Result<HttpResponse, Error> {
+    let mut pages = Post::find()
+        .order_by_asc(post::Column::Id)
+        .paginate(&data.conn, SCAN_BATCH);
+    let mut report = IntegrityReport { checked: 0, mismatched: Vec::new() };
+    while let Some(posts) = pages
+        .fetch_and_next()
+        .await
+        .map_err(|_| ApiError::database("could not retrieve posts"))?
+    {
+        report.checked += posts.len() as u64;
+        report.mismatched.extend(posts.iter().filter(|post| !post.has_valid_content_hash()).map(|post| post.id));
+    }
+    negotiate::respond(&req, HttpResponse::Ok(), &report)
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(integrity_check);
+}
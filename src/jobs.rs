@@ -0,0 +1,168 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sea_orm::{entity::*, query::*, sea_query::Expr, DatabaseConnection, DbErr};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use entity::post;
+use entity::post::{Entity as Post, PostStatus};
+
+use crate::similarity::SimilarityCache;
+
+const LINK_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Work that runs after a post is written, outside the request that wrote it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Job {
+    /// Tells subscribers about a new post.
+    NotifySubscribers { post_id: u64 },
+    /// Reports links in the post's text that do not resolve.
+    CheckLinks { post_id: u64 },
+    /// Tells the author that the post expired and was archived.
+    NotifyExpired { post_id: u64 },
+}
+
+/// Hands jobs to the worker started by `run_worker`.
+#[derive(Debug, Clone)]
+pub struct JobQueue(UnboundedSender<Job>);
+
+impl JobQueue {
+    pub fn enqueue(&self, job: Job) {
+        if self.0.send(job).is_err() {
+            tracing::warn!(?job, "job worker has stopped, dropping job");
+        }
+    }
+}
+
+pub fn channel() -> (JobQueue, UnboundedReceiver<Job>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    (JobQueue(sender), receiver)
+}
+
+/// Runs jobs one at a time until every `JobQueue` is dropped.
+pub async fn run_worker(conn: DatabaseConnection, mut receiver: UnboundedReceiver<Job>) {
+    while let Some(job) = receiver.recv().await {
+        tracing::debug!(?job, "running job");
+        match job {
+            Job::NotifySubscribers { post_id } => notify_subscribers(post_id).await,
+            Job::CheckLinks { post_id } => check_links(&conn, post_id).await,
+            Job::NotifyExpired { post_id } => notify_expired(post_id).await,
+        }
+    }
+}
+
+/// Queues a link check for every post.
+pub async fn enqueue_link_checks(conn: &DatabaseConnection, jobs: &JobQueue) {
+    let ids: Vec<u64> = match Post::find()
+        .select_only()
+        .column(post::Column::Id)
+        .into_tuple()
+        .all(conn)
+        .await
+    {
+        Ok(ids) => ids,
+        Err(err) => {
+            tracing::warn!(%err, "could not retrieve posts for link check");
+            return;
+        }
+    };
+    for post_id in ids {
+        jobs.enqueue(Job::CheckLinks { post_id });
+    }
+}
+
+/// Archives the published posts whose `expires_at` has passed and clears it, so a post
+/// published again afterwards stays published. Returns the ids of the archived posts;
+/// running it again right after archives nothing more.
+async fn archive_expired(conn: &DatabaseConnection) -> Result<Vec<u64>, DbErr> {
+    let now = Utc::now();
+    let expired = || {
+        Condition::all()
+            .add(post::Column::Status.eq(PostStatus::Published))
+            .add(post::Column::ExpiresAt.lte(now))
+    };
+    let ids: Vec<u64> = Post::find()
+        .filter(expired())
+        .select_only()
+        .column(post::Column::Id)
+        .into_tuple()
+        .all(conn)
+        .await?;
+    if ids.is_empty() {
+        return Ok(ids);
+    }
+    // the conditions are repeated so a post republished meanwhile is left alone
+    Post::update_many()
+        .col_expr(post::Column::Status, Expr::value(PostStatus::Archived))
+        .col_expr(post::Column::ExpiresAt, Expr::value(None::<DateTime<Utc>>))
+        .filter(post::Column::Id.is_in(ids.clone()))
+        .filter(expired())
+        .exec(conn)
+        .await?;
+    Ok(ids)
+}
+
+/// Archives the expired posts and queues a notification for each; run every minute.
+pub async fn expire_posts(conn: &DatabaseConnection, jobs: &JobQueue, similar_posts: &SimilarityCache) {
+    let ids = match archive_expired(conn).await {
+        Ok(ids) => ids,
+        Err(err) => {
+            tracing::warn!(%err, "could not archive expired posts");
+            return;
+        }
+    };
+    if !ids.is_empty() {
+        similar_posts.invalidate();
+    }
+    for post_id in ids {
+        jobs.enqueue(Job::NotifyExpired { post_id });
+    }
+}
+
+async fn notify_subscribers(post_id: u64) {
+    // there is no mailer yet, so the notification only goes to the log
+    tracing::info!(post_id, "new post published");
+}
+
+async fn notify_expired(post_id: u64) {
+    // like `notify_subscribers`, this only goes to the log until there is a mailer
+    tracing::info!(post_id, "post expired and was archived");
+}
+
+async fn check_links(conn: &DatabaseConnection, post_id: u64) {
+    let post = match Post::find_by_id(post_id).one(conn).await {
+        Ok(Some(post)) => post,
+        Ok(None) => return,
+        Err(err) => {
+            tracing::warn!(post_id, %err, "could not retrieve post for link check");
+            return;
+        }
+    };
+    let client = match reqwest::Client::builder().timeout(LINK_CHECK_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(err) => {
+            tracing::warn!(%err, "could not build link check client");
+            return;
+        }
+    };
+    for link in links(&post.text) {
+        let broken = match client.head(link).send().await {
+            Ok(response) => {
+                response.status().is_client_error() || response.status().is_server_error()
+            }
+            Err(_) => true,
+        };
+        if broken {
+            tracing::warn!(post_id, link, "broken link");
+        }
+    }
+}
+
+/// Returns the http(s) URLs in `text`, without surrounding punctuation.
+fn links(text: &str) -> impl Iterator<Item = &str> {
+    text.split_whitespace()
+        .filter_map(|word| {
+            let start = word.find("http://").or_else(|| word.find("https://"))?;
+            Some(word[start..].trim_end_matches(|c: char| ".,;:!?)]\"'>".contains(c)))
+        })
+}
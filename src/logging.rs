@@ -0,0 +1,61 @@
+use std::env;
+use std::io::IsTerminal;
+
+use tracing_subscriber::EnvFilter;
+
+/// Used when `LOG_LEVEL` is not set.
+const DEFAULT_LEVEL: &str = "debug";
+const LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error"];
+
+/// When log lines are colored, from `LOG_COLOR`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColorMode {
+    /// Only when stdout is a terminal.
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "auto" => Some(ColorMode::Auto),
+            "always" => Some(ColorMode::Always),
+            "never" => Some(ColorMode::Never),
+            _ => None,
+        }
+    }
+
+    fn use_color(self) -> bool {
+        match self {
+            ColorMode::Auto => std::io::stdout().is_terminal(),
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+        }
+    }
+}
+
+/// Installs the global subscriber, logging at `LOG_LEVEL` (`trace`, `debug`, `info`, `warn`
+/// or `error`, default `debug`) with colors as `LOG_COLOR` says (`auto`, `always` or
+/// `never`, default `auto`). Unknown values are logged and the defaults used instead.
+pub fn init() {
+    let level = env::var("LOG_LEVEL").ok();
+    let color = env::var("LOG_COLOR").ok();
+    let valid_level = level
+        .as_deref()
+        .map(str::to_ascii_lowercase)
+        .filter(|level| LEVELS.contains(&level.as_str()));
+    let mode = color.as_deref().and_then(ColorMode::parse);
+
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::new(valid_level.as_deref().unwrap_or(DEFAULT_LEVEL)))
+        .with_ansi(mode.unwrap_or(ColorMode::Auto).use_color())
+        .init();
+
+    if let (Some(level), None) = (&level, &valid_level) {
+        tracing::warn!(level, "LOG_LEVEL must be one of trace, debug, info, warn or error");
+    }
+    if let (Some(color), None) = (&color, mode) {
+        tracing::warn!(color, "LOG_COLOR must be auto, always or never");
+    }
+}
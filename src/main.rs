@@ -1,178 +1,895 @@
+use std::collections::HashSet;
 use std::env;
+use std::error::Error as _;
+use std::io::Cursor;
+use std::process;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 use actix_files::Files as Fs;
 use actix_web::{
-    App, error, Error, get, HttpRequest, HttpResponse, HttpServer, middleware, post, Result, web,
+    App, Error, FromRequest, get, HttpRequest, HttpResponse, HttpServer, middleware, post, Result, route, web,
 };
+use actix_web::dev::{HttpServiceFactory, Service, ServiceResponse};
+use actix_web::http::header;
 use actix_web::web::{Data, Form};
+use image::imageops::{self, FilterType};
+use image::{ImageFormat, Luma};
 use listenfd::ListenFd;
+use qrcode::QrCode;
 use sea_orm::{entity::*, query::*};
-use sea_orm::DatabaseConnection;
+use sea_orm::{DatabaseConnection, DbErr};
 use serde::{Deserialize, Serialize};
 use tera::Tera;
 
 use entity::post;
-use entity::post::Entity as Post;
+use entity::post::{Entity as Post, PostStatus};
+use entity::post_permission::Permission;
 
+use crate::analytics::WordFrequencyCache;
+use crate::api_error::ApiError;
+use crate::app_state::AppStateBuilder;
+use crate::auth::AuthUser;
+use crate::blocked_words::BlockedWords;
+use crate::broadcast::{BroadcastRegistry, PostEvent, PostEventKind};
+use crate::circuit_breaker::{with_circuit_breaker, CircuitBreaker};
+use crate::encryption::EncryptParams;
+use crate::features::FeatureFlags;
+use crate::geoip::GeoIp;
+use crate::jobs::{Job, JobQueue};
+use crate::pool_monitor::{MetricsState, PoolMonitor};
+use crate::rate_limit::PostRateLimiter;
+use crate::routes::RouteMap;
+use crate::scheduler::Scheduler;
+use crate::settings::SiteSettings;
+use crate::similarity::SimilarityCache;
+use crate::storage::ObjectStorage;
+use crate::tenants::{tenanted_query, Tenant, TenantRegistry};
+use crate::view_history::ViewHistory;
+
+mod ab_tests;
+mod admin;
+mod announcements;
+mod analytics;
+mod annotations;
+mod api;
+mod api_error;
+mod app_state;
+mod audit;
+mod auth;
+mod blocked_words;
+mod blocks;
+mod body_logger;
+mod bookmarks;
+mod broadcast;
+mod cards;
+mod changelog;
+mod circuit_breaker;
+mod config;
+#[cfg(debug_assertions)]
+mod debug;
+mod duplicate;
+mod encryption;
+mod events;
+mod export;
+mod features;
+mod filters;
+mod geoip;
+mod github;
+mod graphql;
+mod health;
+mod images;
+mod import_url;
+mod integrity;
+mod jobs;
+mod logging;
+mod merge;
+mod negotiate;
+mod payload_errors;
+mod permissions;
+mod pool_monitor;
+mod post_counts;
+mod post_of_the_day;
+mod preload;
+mod progress;
+mod query_count;
+mod rate_limit;
+mod reading_list;
+mod relations;
+mod response_limit;
+mod retry;
+mod robots;
+mod routes;
+mod scheduler;
+mod search;
+mod seeds;
+mod settings;
+mod share_links;
+mod similarity;
+mod stable_hash;
+mod storage;
+mod suggestions;
+mod tenants;
+mod translate;
+mod view_history;
+mod webhooks;
+
+/// Used when the `posts_per_page` site setting is missing or not a number.
 const DEFAULT_POSTS_PER_PAGE: usize = 5;
+const DEFAULT_MIN_POSTS_PER_PAGE: usize = 1;
+const DEFAULT_MAX_POSTS_PER_PAGE: usize = 100;
+/// Most rows a list may skip to reach a page; deeper offsets are slow to scan.
+const MAX_OFFSET: usize = 100_000;
+/// Set to `true` on responses whose `posts_per_page` was lowered to the max.
+const CLAMPED_HEADER: &str = "X-Posts-Per-Page-Clamped";
+const DEFAULT_QR_SIZE: u32 = 200;
+const MIN_QR_SIZE: u32 = 64;
+const MAX_QR_SIZE: u32 = 1000;
+const DEFAULT_LINK_CHECK_INTERVAL: Duration = Duration::from_secs(24 * 3600);
+const FEATURE_FLAG_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+const POST_EXPIRY_INTERVAL: Duration = Duration::from_secs(60);
 
 #[derive(Debug, Clone)]
 struct AppState {
-    templates: Tera,
+    templates: Arc<Tera>,
     conn: DatabaseConnection,
+    base_url: String,
+    jwt_secret: String,
+    storage: Arc<dyn ObjectStorage>,
+    webp_quality: u8,
+    broadcaster: BroadcastRegistry,
+    post_events: tokio::sync::broadcast::Sender<u64>,
+    github: Option<oauth2::basic::BasicClient>,
+    jobs: JobQueue,
+    feature_flags: FeatureFlags,
+    word_frequency: WordFrequencyCache,
+    page_size_limits: PageSizeLimits,
+    post_rate_limiter: PostRateLimiter,
+    encryption_key: Option<encryption::Key>,
+    blocked_words: BlockedWords,
+    site_settings: Arc<RwLock<SiteSettings>>,
+    circuit_breaker: CircuitBreaker,
+    metrics: MetricsState,
+    tenants: TenantRegistry,
+    preload_links: Option<header::HeaderValue>,
+    response_size_limit: usize,
+    github_webhook: Option<webhooks::WebhookConfig>,
+    similar_posts: SimilarityCache,
+    geoip: GeoIp,
+    translator: Option<translate::TranslatorConfig>,
+    url_import: import_url::ImportPolicy,
+    post_counts: post_counts::PostCountCache,
 }
 
-#[derive(Debug, Deserialize)]
+impl AppState {
+    /// Renders template `name` on the blocking thread pool, so the CPU work of rendering
+    /// doesn't hold up the async workers. A panicking render is a 500 too.
+    async fn render(&self, name: &'static str, ctx: tera::Context) -> Result<String, Error> {
+        let templates = self.templates.clone();
+        tokio::task::spawn_blocking(move || templates.render(name, &ctx))
+            .await
+            .map_err(|_| ApiError::internal("template rendering panicked"))?
+            .map_err(|_| ApiError::internal("template error").into())
+    }
+
+    /// `encryption::store` for handlers; asking for encryption without a key is a 400.
+    fn store_text(&self, text: String, encrypt: bool) -> Result<String, Error> {
+        encryption::store(text, encrypt, self.encryption_key.as_ref())
+            .map_err(|_| ApiError::bad_request("encryption is not configured").into())
+    }
+
+    /// Page size of lists whose request doesn't give one, from the site settings.
+    fn default_posts_per_page(&self) -> usize {
+        self.site_settings
+            .read()
+            .unwrap()
+            .get_setting(settings::POSTS_PER_PAGE)
+            .and_then(|size| size.parse().ok())
+            .unwrap_or(DEFAULT_POSTS_PER_PAGE)
+    }
+
+    /// `PageSizeLimits::posts_per_page` with the configured default.
+    fn posts_per_page(&self, requested: Option<usize>) -> Result<(usize, bool), Error> {
+        self.page_size_limits.posts_per_page(requested, self.default_posts_per_page())
+    }
+
+    /// `encryption::reveal` for handlers.
+    fn reveal(&self, post: &mut post::Model) -> Result<(), Error> {
+        encryption::reveal(post, self.encryption_key.as_ref())
+            .map_err(|_| ApiError::internal("could not decrypt post").into())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Params {
     page: Option<usize>,
     posts_per_page: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<PostStatus>,
+    /// `1` orders the list by the `sort_order` editors set instead of by id.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    use_custom_order: Option<u8>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
-struct FlashData {
-    kind: String,
-    message: String,
+impl Params {
+    /// Orders `query` as these params ask, with id breaking ties of `sort_order`.
+    fn order(&self, query: Select<Post>) -> Select<Post> {
+        match self.use_custom_order {
+            Some(1) => query.order_by_asc(post::Column::SortOrder).order_by_asc(post::Column::Id),
+            _ => query.order_by_asc(post::Column::Id),
+        }
+    }
 }
 
-#[get("/")]
-async fn list(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse, Error> {
-    let template = &data.templates;
-    let conn = &data.conn;
+/// Where a rendered list page sits among its pages, for the pagination links.
+#[derive(Debug, Serialize)]
+struct PaginationContext {
+    current_page: usize,
+    total_pages: u64,
+    per_page: usize,
+    has_prev: bool,
+    has_next: bool,
+    prev_page: Option<usize>,
+    next_page: Option<usize>,
+}
 
-    let params = web::Query::<Params>::from_query(req.query_string()).unwrap();
-    let page = params.page.unwrap_or(1);
-    let posts_per_page = params.posts_per_page.unwrap_or(DEFAULT_POSTS_PER_PAGE);
-    let paginator = Post::find()
+fn paginate_context(page: usize, total_pages: u64, per_page: usize) -> PaginationContext {
+    let prev_page = (page > 1).then(|| page - 1);
+    let next_page = ((page as u64) < total_pages).then(|| page + 1);
+    PaginationContext {
+        current_page: page,
+        total_pages,
+        per_page,
+        has_prev: prev_page.is_some(),
+        has_next: next_page.is_some(),
+        prev_page,
+        next_page,
+    }
+}
+
+/// Returns the status a post list shows: published posts, unless an admin asks for
+/// another status.
+fn listed_status(requested: Option<PostStatus>,
+                 user: Option<&AuthUser>,
+) -> Result<PostStatus, Error> {
+    match requested {
+        None | Some(PostStatus::Published) => Ok(PostStatus::Published),
+        Some(status) if user.is_some_and(|user| user.admin) => Ok(status),
+        Some(_) => Err(ApiError::forbidden("only admins may list unpublished posts").into()),
+    }
+}
+
+/// Bounds on `posts_per_page` for every paginated list, from `MIN_POSTS_PER_PAGE` and
+/// `MAX_POSTS_PER_PAGE`, and on how deep those lists may be paged, from `MAX_PAGE_DEPTH`.
+#[derive(Debug, Clone, Copy)]
+struct PageSizeLimits {
+    min: usize,
+    max: usize,
+    /// Highest page number, when `MAX_PAGE_DEPTH` is set; `MAX_OFFSET` applies regardless.
+    max_page: Option<usize>,
+}
+
+impl PageSizeLimits {
+    fn from_env() -> Self {
+        let limit = |name: &str, default: usize| {
+            env::var(name)
+                .map(|value| value.parse().unwrap_or_else(|_| panic!("{} must be a number", name)))
+                .unwrap_or(default)
+        };
+        let min = limit("MIN_POSTS_PER_PAGE", DEFAULT_MIN_POSTS_PER_PAGE).max(1);
+        let max = limit("MAX_POSTS_PER_PAGE", DEFAULT_MAX_POSTS_PER_PAGE);
+        assert!(min <= max, "MIN_POSTS_PER_PAGE must not exceed MAX_POSTS_PER_PAGE");
+        let max_page = env::var("MAX_PAGE_DEPTH")
+            .ok()
+            .map(|depth| depth.parse().expect("MAX_PAGE_DEPTH must be a number"));
+        PageSizeLimits { min, max, max_page }
+    }
+
+    /// Returns the page size to use for `requested`, or `default` when not given, and
+    /// whether it was clamped to the max, or `None` when it is below the min.
+    fn apply(&self, requested: Option<usize>, default: usize) -> Option<(usize, bool)> {
+        match requested {
+            Some(size) if size < self.min => None,
+            Some(size) if size > self.max => Some((self.max, true)),
+            Some(size) => Some((size, false)),
+            None => Some((default.clamp(self.min, self.max), false)),
+        }
+    }
+
+    /// Explains why `page` of `posts_per_page` posts is too deep to fetch, if it is.
+    fn depth_error(&self, page: usize, posts_per_page: usize) -> Option<String> {
+        if let Some(max_page) = self.max_page.filter(|&max_page| page > max_page) {
+            return Some(format!("page must be at most {}", max_page));
+        }
+        if page.saturating_sub(1).saturating_mul(posts_per_page) > MAX_OFFSET {
+            return Some(format!("pages past the first {} posts cannot be fetched", MAX_OFFSET));
+        }
+        None
+    }
+
+    /// `depth_error` for HTTP handlers, rejecting too deep pages with 400.
+    fn check_depth(&self, page: usize, posts_per_page: usize) -> Result<(), Error> {
+        match self.depth_error(page, posts_per_page) {
+            Some(message) => Err(ApiError::bad_request(message).into()),
+            None => Ok(()),
+        }
+    }
+
+    /// `apply` for HTTP handlers, rejecting too small sizes with 400.
+    fn posts_per_page(&self, requested: Option<usize>, default: usize) -> Result<(usize, bool), Error> {
+        self.apply(requested, default).ok_or_else(|| {
+            ApiError::bad_request(format!("posts_per_page must be at least {}", self.min)).into()
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QrParams {
+    size: Option<u32>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FontSize {
+    Small,
+    #[default]
+    Medium,
+    Large,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReaderParams {
+    font_size: Option<FontSize>,
+}
+
+/// Published posts featured now, for the highlighted section of the list page.
+async fn featured_posts(conn: &DatabaseConnection, tenant_id: &str) -> Result<Vec<post::Model>, Error> {
+    let now = chrono::Utc::now();
+    let posts = tenanted_query::<Post>(tenant_id)
+        .filter(post::Column::IsFeatured.eq(true))
+        .filter(post::Column::Status.eq(PostStatus::Published))
         .order_by_asc(post::Column::Id)
-        .paginate(conn, posts_per_page.try_into().unwrap());
-    let num_pages = paginator.num_pages().await.ok().unwrap();
+        .all(conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve featured posts"))?;
+    Ok(posts.into_iter().filter(|post| post.is_currently_featured(now)).collect())
+}
+
+#[route("/", method = "GET", method = "HEAD")]
+async fn list(req: HttpRequest,
+              data: web::Data<AppState>,
+              tenant: Tenant,
+              user: Option<AuthUser>,
+) -> Result<HttpResponse, Error> {
+    let conn = &data.conn;
+    data.geoip.log_location(&req);
+
+    let params = web::Query::<Params>::from_query(req.query_string())
+        .map_err(payload_errors::query_error)?;
+    let status = listed_status(params.status, user.as_ref())?;
+    let page = params.page.unwrap_or(1).max(1);
+    let (posts_per_page, clamped) = data.posts_per_page(params.posts_per_page)?;
+    data.page_size_limits.check_depth(page, posts_per_page)?;
+    let paginator = params
+        .order(tenanted_query::<Post>(&tenant.id).filter(post::Column::Status.eq(status)))
+        .paginate(conn, posts_per_page as u64);
+    let breaker = &data.circuit_breaker;
+    let num_pages = with_circuit_breaker(breaker, || paginator.num_pages())
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not count posts"))?;
+    if page as u64 > num_pages && num_pages > 0 {
+        let last = Params {
+            page: Some(num_pages as usize),
+            posts_per_page: Some(posts_per_page),
+            status: params.status,
+            use_custom_order: params.use_custom_order,
+        };
+        let query = serde_urlencoded::to_string(&last).unwrap_or_default();
+        return Ok(HttpResponse::Found().append_header(("location", format!("/?{}", query))).finish());
+    }
 
-    let posts = paginator
-        .fetch_page((page - 1).try_into().unwrap())
+    let mut posts = with_circuit_breaker(breaker, || paginator.fetch_page((page - 1) as u64))
         .await
-        .expect("could not retrieve posts");
+        .map_err(|err| ApiError::from_db(&err, "could not retrieve posts"))?;
+    let (session_id, session_cookie) = ab_tests::session(&req);
+    let tests: Vec<String> = posts.iter().map(|post| ab_tests::title_test(post.id)).collect();
+    let mut titles = ab_tests::assign_variants(&tests, &session_id, conn).await;
+    for (post, test) in posts.iter_mut().zip(&tests) {
+        data.reveal(post)?;
+        if let Some(title) = titles.remove(test) {
+            post.title = title;
+        }
+    }
     let mut ctx = tera::Context::new();
-    ctx.insert("posts", &posts);
-    ctx.insert("page", &page);
-    ctx.insert("posts_per_page", &posts_per_page);
-    ctx.insert("num_pages", &num_pages);
-
-    let body = template
-        .render("index.html.tera", &ctx)
-        .map_err(|_| error::ErrorInternalServerError("Template error"))?;
-    Ok(HttpResponse::Ok().content_type("text/html").body(body))
+    ctx.insert("posts", &images::with_images(data.storage.as_ref(), posts));
+    ctx.insert("pagination", &paginate_context(page, num_pages, posts_per_page));
+    ctx.insert("status", &status);
+    ctx.insert("use_custom_order", &params.use_custom_order.unwrap_or(0));
+    ctx.insert("featured_posts", &featured_posts(conn, &tenant.id).await?);
+    ctx.insert("post_of_the_day", &post_of_the_day::for_list(conn, &tenant.id).await?);
+    ctx.insert("announcement", &announcements::find_active(conn, &tenant).await?);
+    let history = ViewHistory::from_request(&req);
+    ctx.insert("recent_posts", &view_history::recent_posts(conn, &tenant.id, &history).await?);
+
+    let body = data.render("index.html.tera", ctx).await?;
+    let mut response = HttpResponse::Ok();
+    if let Some(cookie) = session_cookie {
+        response.cookie(cookie);
+    }
+    if clamped {
+        response.insert_header((CLAMPED_HEADER, "true"));
+    }
+    if let Some(links) = &data.preload_links {
+        response.insert_header((header::LINK, links.clone()));
+    }
+    response.insert_header((robots::ROBOTS_TAG_HEADER, robots::NOARCHIVE));
+    Ok(response.content_type("text/html").body(body))
 }
 
 #[get("/new")]
-async fn new(data: web::Data<AppState>) -> Result<HttpResponse, Error> {
-    let template = &data.templates;
-    let ctx = tera::Context::new();
-    let body = template.render("new.html.tera", &ctx)
-        .map_err(|_| error::ErrorInternalServerError("templdate error"))?;
+async fn new(data: web::Data<AppState>, tenant: Tenant) -> Result<HttpResponse, Error> {
+    let mut ctx = tera::Context::new();
+    ctx.insert("announcement", &announcements::find_active(&data.conn, &tenant).await?);
+    let body = data.render("new.html.tera", ctx).await?;
     Ok(HttpResponse::Ok().content_type("text/html").body(body))
 }
 
 #[post("/")]
-async fn create(data: Data<AppState>, post_form: Form<post::Model>) -> Result<HttpResponse, Error> {
-    let conn = &data.conn;
-    let form = post_form.into_inner();
-    post::ActiveModel {
-        title: Set(form.title.to_owned()),
-        text: Set(form.text.to_owned()),
-        ..Default::default()
-    }
-        .save(conn)
+async fn create(data: Data<AppState>,
+                tenant: Tenant,
+                user: AuthUser,
+                post_form: Form<post::Model>,
+                params: web::Query<EncryptParams>,
+) -> Result<HttpResponse, Error> {
+    let mut form = post_form.into_inner();
+    data.blocked_words.check(&form.title, &form.text)?;
+    data.post_rate_limiter.check(user.id)?;
+    let encrypt = params.requested();
+    form.text = data.store_text(form.text, encrypt)?;
+    let insert = || retry::with_retry(&data.conn, retry::MAX_RETRIES, |txn| {
+        let form = form.clone();
+        let tenant_id = tenant.id.clone();
+        Box::pin(async move {
+            let post = post::ActiveModel {
+                tenant_id: Set(tenant_id),
+                title: Set(form.title),
+                text: Set(form.text),
+                status: Set(form.status),
+                is_encrypted: Set(encrypt),
+                ..Default::default()
+            }
+                .insert(txn)
+                .await?;
+            permissions::grant(txn, user.id, post.id, Permission::Admin).await?;
+            audit::post_created(txn, Some(user.id), &post).await?;
+            Ok(post)
+        })
+    });
+    let post = with_circuit_breaker(&data.circuit_breaker, insert)
         .await
-        .expect("could not insert post");
+        .map_err(|err| ApiError::from_db(&err, "could not insert post"))?;
+    data.broadcaster.broadcast(PostEvent::new(PostEventKind::Created, post.id)).await;
+    // sending only fails when no SSE client is listening
+    let _ = data.post_events.send(post.id);
+    data.jobs.enqueue(Job::NotifySubscribers { post_id: post.id });
+    data.jobs.enqueue(Job::CheckLinks { post_id: post.id });
+    data.similar_posts.invalidate();
     Ok(HttpResponse::Found().append_header(("location", "/")).finish())
 }
 
-#[get("/{id}")]
-async fn edit(data: Data<AppState>, id: web::Path<u64>) -> Result<HttpResponse, Error> {
+#[route("/{id:\\d+}", method = "GET", method = "HEAD")]
+async fn edit(req: HttpRequest,
+              data: Data<AppState>,
+              tenant: Tenant,
+              id: web::Path<u64>,
+              user: Option<AuthUser>,
+) -> Result<HttpResponse, Error> {
     let conn = &data.conn;
-    let template = &data.templates;
-    let post: post::Model = Post::find_by_id(id.into_inner())
-        .one(conn)
+    data.geoip.log_location(&req);
+    let id = id.into_inner();
+    let find = || tenanted_query::<Post>(&tenant.id).filter(post::Column::Id.eq(id)).one(conn);
+    let mut post: post::Model = with_circuit_breaker(&data.circuit_breaker, find)
         .await
-        .expect("cound not found post")
-        .unwrap();
+        .map_err(|err| ApiError::from_db(&err, "could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+    permissions::require_read(conn, user.as_ref(), &post).await?;
+    integrity::verify(&post)?;
+    data.reveal(&mut post)?;
     let mut ctx = tera::Context::new();
     ctx.insert("post", &post);
+    ctx.insert("images", &images::image_urls(data.storage.as_ref(), &post));
+    ctx.insert("related_posts", &relations::find_related_posts(conn, post.id).await?);
+    ctx.insert("announcement", &announcements::find_active(conn, &tenant).await?);
+    if let Some(user) = user {
+        let scroll_percent = progress::find_progress(&data, user.id, post.id).await?;
+        ctx.insert("scroll_percent", &scroll_percent);
+        let is_bookmarked = bookmarks::is_bookmarked(&data, user.id, post.id).await?;
+        ctx.insert("is_bookmarked", &is_bookmarked);
+        let annotations = annotations::find_annotations(&data, user.id, post.id).await?;
+        ctx.insert("annotations", &annotations);
+    }
 
-    let body = template
-        .render("edit.html.tera", &ctx)
-        .map_err(|_| error::ErrorInternalServerError("Template error")).unwrap();
-    Ok(HttpResponse::Ok().content_type("text/html").body(body))
+    let body = data.render("edit.html.tera", ctx).await?;
+    let mut history = ViewHistory::from_request(&req);
+    history.record(post.id);
+    Ok(HttpResponse::Ok()
+        .cookie(history.cookie())
+        .insert_header((robots::ROBOTS_TAG_HEADER, robots::post_directives(post.status)))
+        .content_type("text/html")
+        .body(body))
 }
 
-#[post("/{id}")]
+#[post("/{id:\\d+}")]
 async fn update(data: Data<AppState>,
+                tenant: Tenant,
+                user: AuthUser,
                 id: web::Path<u64>,
                 post_form: web::Form<post::Model>,
+                params: web::Query<EncryptParams>,
 ) -> Result<HttpResponse, Error> {
     let conn = &data.conn;
-    let form = post_form.into_inner();
-    post::ActiveModel {
-        id: Set(id.into_inner()),
-        title: Set(form.title.to_owned()),
-        text: Set(form.text.to_owned()),
-    }
-        .save(conn)
+    let mut form = post_form.into_inner();
+    let id = id.into_inner();
+    permissions::require_write(conn, &user, id).await?;
+    data.blocked_words.check(&form.title, &form.text)?;
+    let encrypt = params.requested();
+    form.text = data.store_text(form.text, encrypt)?;
+    let update = || retry::with_retry(conn, retry::MAX_RETRIES, |txn| {
+        let form = form.clone();
+        let find = tenanted_query::<Post>(&tenant.id).filter(post::Column::Id.eq(id));
+        Box::pin(async move {
+            let old = find
+                .lock_exclusive()
+                .one(txn)
+                .await?
+                .ok_or_else(|| DbErr::RecordNotFound(format!("post {}", id)))?;
+            let mut post: post::ActiveModel = old.clone().into();
+            post.title = Set(form.title);
+            post.text = Set(form.text);
+            post.status = Set(form.status);
+            post.is_encrypted = Set(encrypt);
+            let post = post.update(txn).await?;
+            audit::post_updated(txn, Some(user.id), &old, &post).await
+        })
+    });
+    with_circuit_breaker(&data.circuit_breaker, update)
         .await
-        .expect("could not edit post");
+        .map_err(|err| match err {
+            DbErr::RecordNotFound(_) => ApiError::post_not_found(),
+            err => ApiError::from_db(&err, "could not edit post"),
+        })?;
+    data.broadcaster.broadcast(PostEvent::new(PostEventKind::Updated, id)).await;
+    data.jobs.enqueue(Job::CheckLinks { post_id: id });
+    data.similar_posts.invalidate();
     Ok(HttpResponse::Found().append_header(("location", "/")).finish())
 }
 
 #[post("/delete/{id}")]
-async fn delete(data: web::Data<AppState>, id: web::Path<u64>) -> Result<HttpResponse, Error> {
+async fn delete(data: web::Data<AppState>,
+                tenant: Tenant,
+                user: AuthUser,
+                id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
     let conn = &data.conn;
-    let post: post::ActiveModel = Post::find_by_id(id.into_inner())
-        .one(conn)
+    let id = id.into_inner();
+    permissions::require_write(conn, &user, id).await?;
+    let breaker = &data.circuit_breaker;
+    let find = tenanted_query::<Post>(&tenant.id).filter(post::Column::Id.eq(id)).one(conn);
+    let post: post::Model = with_circuit_breaker(breaker, || find)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+    let txn = with_circuit_breaker(breaker, || conn.begin())
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not start transaction"))?;
+    audit::post_deleted(&txn, Some(user.id), &post)
         .await
-        .unwrap()
-        .unwrap()
-        .into();
-    post.delete(conn).await.unwrap();
+        .map_err(|_| ApiError::database("could not record deletion"))?;
+    post::ActiveModel::from(post.clone())
+        .delete(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not delete post"))?;
+    txn.commit()
+        .await
+        .map_err(|_| ApiError::database("could not commit deletion"))?;
+    // only once the post is gone, so a failed deletion keeps its images
+    images::delete_images(data.storage.as_ref(), &post).await;
+    data.similar_posts.invalidate();
+    data.broadcaster.broadcast(PostEvent::new(PostEventKind::Deleted, id)).await;
     Ok(HttpResponse::Found().append_header(("location", "/")).finish())
 }
 
+#[get("/posts/{id}/qr.png")]
+async fn qr_code(data: Data<AppState>,
+                 tenant: Tenant,
+                 id: web::Path<u64>,
+                 params: web::Query<QrParams>,
+) -> Result<HttpResponse, Error> {
+    let conn = &data.conn;
+    let id = id.into_inner();
+    tenanted_query::<Post>(&tenant.id)
+        .filter(post::Column::Id.eq(id))
+        .one(conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+
+    let size = params.size.unwrap_or(DEFAULT_QR_SIZE).clamp(MIN_QR_SIZE, MAX_QR_SIZE);
+    let url = format!("{}/{}", data.base_url.trim_end_matches('/'), id);
+    let code = QrCode::new(url.as_bytes())
+        .map_err(|_| ApiError::internal("could not encode qr code"))?;
+    // the renderer works in whole modules, so scale the result to exactly `size`
+    let image = code
+        .render::<Luma<u8>>()
+        .min_dimensions(size, size)
+        .build();
+    let image = imageops::resize(&image, size, size, FilterType::Nearest);
+
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, ImageFormat::Png)
+        .map_err(|_| ApiError::internal("could not render qr code"))?;
+    Ok(HttpResponse::Ok().content_type("image/png").body(png.into_inner()))
+}
+
+#[get("/posts/{id}/print")]
+async fn print(data: Data<AppState>,
+               tenant: Tenant,
+               user: Option<AuthUser>,
+               id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
+    let id = id.into_inner();
+    let find = || tenanted_query::<Post>(&tenant.id).filter(post::Column::Id.eq(id)).one(&data.conn);
+    let mut post = with_circuit_breaker(&data.circuit_breaker, find)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+    permissions::require_read(&data.conn, user.as_ref(), &post).await?;
+    data.reveal(&mut post)?;
+    let mut ctx = tera::Context::new();
+    ctx.insert("url", &format!("{}/{}", data.base_url.trim_end_matches('/'), post.id));
+    ctx.insert("post", &post);
+    ctx.insert("blocks_html", &blocks::blocks_html(&post));
+    let body = data.render("print.html.tera", ctx).await?;
+    Ok(HttpResponse::Ok()
+        .insert_header((robots::ROBOTS_TAG_HEADER, robots::post_directives(post.status)))
+        .content_type("text/html")
+        .body(body))
+}
+
+/// The post alone, at a comfortable width and `font_size`, without the site's layout.
+#[get("/posts/{id}/reader")]
+async fn reader(data: Data<AppState>,
+                tenant: Tenant,
+                user: Option<AuthUser>,
+                id: web::Path<u64>,
+                params: web::Query<ReaderParams>,
+) -> Result<HttpResponse, Error> {
+    let id = id.into_inner();
+    let find = || tenanted_query::<Post>(&tenant.id).filter(post::Column::Id.eq(id)).one(&data.conn);
+    let mut post = with_circuit_breaker(&data.circuit_breaker, find)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+    permissions::require_read(&data.conn, user.as_ref(), &post).await?;
+    data.reveal(&mut post)?;
+    let mut ctx = tera::Context::new();
+    ctx.insert("post", &post);
+    ctx.insert("blocks_html", &blocks::blocks_html(&post));
+    ctx.insert("font_size", &params.font_size.unwrap_or_default());
+    let body = data.render("reader.html.tera", ctx).await?;
+    Ok(HttpResponse::Ok()
+        .insert_header((robots::ROBOTS_TAG_HEADER, "noindex"))
+        .content_type("text/html")
+        .body(body))
+}
 
 async fn not_found(data: Data<AppState>, request: HttpRequest) -> Result<HttpResponse, Error> {
     println!("not found");
-    let template = &data.templates;
+    let tenant = Tenant::extract(&request).await?;
     let mut ctx = tera::Context::new();
     ctx.insert("uri", request.uri().path());
-    let body = template.render("error/404.html.tera", &ctx)
-        .map_err(|_| error::ErrorInternalServerError("template error")).unwrap();
+    // the suggestion is a nicety, so the 404 page is shown without it when it fails
+    let suggestion = suggestions::suggest_post(&data.conn, &tenant.id, request.path())
+        .await
+        .unwrap_or_else(|err| {
+            tracing::warn!(%err, "could not look for a suggestion");
+            None
+        });
+    ctx.insert("suggestion", &suggestion);
+    let body = data.render("error/404.html.tera", ctx).await?;
 
-    Ok(HttpResponse::Ok().content_type("text/html").body(body))
+    Ok(HttpResponse::NotFound().content_type("text/html").body(body))
+}
+
+/// Registered after every other service, so it only sees requests that no route took.
+///
+/// When another resource matched the path but not the method, actix has noted that
+/// resource's methods on the request and the built-in resource default answers 405 with
+/// them in `Allow`. Without that note the path is unknown and `not_found` answers instead.
+fn not_allowed() -> impl HttpServiceFactory {
+    web::resource("/{tail:.*}").wrap_fn(|req, srv| {
+        let response = srv.call(req);
+        async move {
+            let response = response.await?;
+            if response.headers().contains_key(header::ALLOW) {
+                return Ok(response);
+            }
+            let request = response.request().clone();
+            let data = request
+                .app_data::<Data<AppState>>()
+                .cloned()
+                .expect("app state is registered");
+            let not_found = not_found(data, request.clone()).await?;
+            Ok(ServiceResponse::new(request, not_found))
+        }
+    })
 }
 
-fn get_env_var(str: &str) -> String {
-    let string = format!("{} is not set in .env file", str);
-    env::var(str).expect(&*string)
+/// Templates the handlers render, relative to the template directory.
+const REQUIRED_TEMPLATES: &[&str] = &[
+    "layout.html.tera",
+    "index.html.tera",
+    "new.html.tera",
+    "edit.html.tera",
+    "print.html.tera",
+    "reader.html.tera",
+    "error/404.html.tera",
+];
+
+/// Loads the templates in `dir` and checks them, so that a broken or missing template
+/// stops the server at startup instead of failing its first request.
+fn load_templates(dir: &str) -> Result<Tera, tera::Error> {
+    let mut templates = Tera::new(&format!("{}/**/*", dir))?;
+    routes::register(&mut templates, RouteMap::default());
+    filters::register(&mut templates);
+    let names: HashSet<&str> = templates.get_template_names().collect();
+    if let Some(missing) = REQUIRED_TEMPLATES.iter().find(|name| !names.contains(*name)) {
+        return Err(tera::Error::msg(format!("template {} is missing", missing)));
+    }
+    // parsing alone misses errors such as unknown filters and functions, so render the
+    // list page with no posts as well
+    let mut ctx = tera::Context::new();
+    ctx.insert("posts", &Vec::<post::Model>::new());
+    ctx.insert("pagination", &paginate_context(1, 0, 1));
+    ctx.insert("status", &PostStatus::Published);
+    ctx.insert("use_custom_order", &0);
+    templates.render("index.html.tera", &ctx)?;
+    Ok(templates)
 }
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    std::env::set_var("RUST_LOG", "DEBUG");
-    tracing_subscriber::fmt::init();
-
+    // before logging starts, so the .env can set its level and colors
     dotenv::dotenv().ok();
-    let db_url = get_env_var("DATABASE_URL");
-    let host = get_env_var("HOST");
-    let port = get_env_var("PORT");
-    let server_url = format!("{}:{}", host, port);
-    let conn = sea_orm::Database::connect(&db_url).await.unwrap();
+    logging::init();
 
-    let templates = Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/**/*")).unwrap();
-    let state = AppState { templates, conn };
+    let config = config::validate_env().unwrap_or_else(|errors| {
+        for error in errors {
+            eprintln!("{}", error);
+        }
+        process::exit(1);
+    });
+    let upload_dir = config.upload_dir.clone();
+    // actix-files falls back to the working directory when the root does not exist
+    std::fs::create_dir_all(&upload_dir)?;
+    let server_url = config.server_url();
+    let templates = load_templates(concat!(env!("CARGO_MANIFEST_DIR"), "/templates"))
+        .unwrap_or_else(|err| {
+            let mut message = err.to_string();
+            let mut source = err.source();
+            while let Some(cause) = source {
+                message = format!("{}: {}", message, cause);
+                source = cause.source();
+            }
+            eprintln!("could not load templates: {}", message);
+            process::exit(1);
+        });
+    let blocked_words = BlockedWords::from_env().unwrap_or_else(|err| {
+        eprintln!("could not read BLOCKED_WORDS_FILE: {}", err);
+        process::exit(1);
+    });
+    actix_web::rt::spawn(blocked_words::reload_on_sighup(blocked_words.clone()));
+    let mut conn = sea_orm::Database::connect(&config.database_url).await.unwrap();
+    if cfg!(debug_assertions) {
+        query_count::count_queries(&mut conn);
+    }
+    let site_settings = SiteSettings::load(&conn).await.expect("could not load site settings");
+    // `cargo run -- seed` fills a development database instead of serving it
+    if env::args().nth(1).as_deref() == Some("seed") {
+        seeds::run_seeds(&conn).await.expect("could not seed the database");
+        println!("seeded the database");
+        return Ok(());
+    }
+    let storage = storage::storage_from_env(&upload_dir).await;
+
+    let github = github::client_from_env(&config.base_url);
+    let (jobs, job_receiver) = jobs::channel();
+    actix_web::rt::spawn(jobs::run_worker(conn.clone(), job_receiver));
+
+    let mut scheduler = Scheduler::default();
+    let link_check_interval =
+        scheduler::period_from_env("LINK_CHECK_INTERVAL_SECS", DEFAULT_LINK_CHECK_INTERVAL);
+    let (task_conn, task_jobs) = (conn.clone(), jobs.clone());
+    scheduler.every("link-check", link_check_interval, move || {
+        let (conn, jobs) = (task_conn.clone(), task_jobs.clone());
+        Box::pin(async move { jobs::enqueue_link_checks(&conn, &jobs).await })
+    });
+    let metrics = MetricsState::default();
+    let pool_monitor = PoolMonitor::from_env(metrics.clone());
+    let pool_monitor_interval =
+        scheduler::period_from_env("POOL_MONITOR_INTERVAL_SECS", pool_monitor::DEFAULT_INTERVAL);
+    let task_conn = conn.clone();
+    scheduler.every("pool-monitor", pool_monitor_interval, move || {
+        pool_monitor.check(&task_conn);
+        Box::pin(async {})
+    });
+    let feature_flags = FeatureFlags::default();
+    feature_flags.refresh(&conn).await.expect("could not load feature flags");
+    let (task_conn, task_flags) = (conn.clone(), feature_flags.clone());
+    scheduler.every("feature-flags", FEATURE_FLAG_REFRESH_INTERVAL, move || {
+        let (conn, flags) = (task_conn.clone(), task_flags.clone());
+        Box::pin(async move {
+            if let Err(err) = flags.refresh(&conn).await {
+                tracing::warn!(%err, "could not refresh feature flags");
+            }
+        })
+    });
+    let similar_posts = SimilarityCache::default();
+    let (task_conn, task_jobs, task_similar) = (conn.clone(), jobs.clone(), similar_posts.clone());
+    scheduler.every("post-expiry", POST_EXPIRY_INTERVAL, move || {
+        let (conn, jobs, similar) = (task_conn.clone(), task_jobs.clone(), task_similar.clone());
+        Box::pin(async move { jobs::expire_posts(&conn, &jobs, &similar).await })
+    });
+    let task_conn = conn.clone();
+    scheduler.daily("post-of-the-day", move || {
+        let conn = task_conn.clone();
+        Box::pin(async move { post_of_the_day::pick_for_all_tenants(&conn).await })
+    });
+    scheduler.start();
+
+    let state = AppStateBuilder::default()
+        .db(conn)
+        .templates(templates)
+        .base_url(config.base_url)
+        .jwt_secret(config.jwt_secret)
+        .storage(storage)
+        .webp_quality(config.webp_quality)
+        .github(github)
+        .jobs(jobs)
+        .feature_flags(feature_flags)
+        .encryption_key(config.encryption_key)
+        .blocked_words(blocked_words)
+        .site_settings(site_settings)
+        .metrics(metrics)
+        .similar_posts(similar_posts)
+        .build()
+        .unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1);
+        });
+
+    let schema = graphql::schema();
+
+    let log_request_bodies = body_logger::enabled();
 
     let mut listenfd = ListenFd::from_env();
     let mut server = HttpServer::new(move || {
         App::new()
             .service(Fs::new("/static", "./static"))
+            .service(Fs::new("/uploads", &upload_dir))
             .app_data(web::Data::new(state.clone()))
+            .app_data(web::Data::new(schema.clone()))
+            .app_data(payload_errors::json_config())
+            .app_data(payload_errors::form_config())
+            .app_data(payload_errors::query_config())
+            .wrap(middleware::from_fn(robots::middleware))
+            .wrap(middleware::from_fn(response_limit::middleware))
+            .wrap(middleware::from_fn(tenants::middleware))
+            .wrap(middleware::Condition::new(cfg!(debug_assertions),
+                                             middleware::from_fn(query_count::middleware)))
+            .wrap(middleware::Condition::new(log_request_bodies,
+                                             middleware::from_fn(body_logger::middleware)))
             .wrap(middleware::Logger::default())
             .configure(init)
     });
@@ -192,7 +909,114 @@ pub fn init(cfg: &mut web::ServiceConfig) {
     cfg.service(edit);
     cfg.service(update);
     cfg.service(delete);
-    cfg.default_service(web::route().to(not_found));
+    cfg.service(qr_code);
+    cfg.service(print);
+    cfg.service(reader);
+    progress::init(cfg);
+    bookmarks::init(cfg);
+    reading_list::init(cfg);
+    annotations::init(cfg);
+    images::init(cfg);
+    cards::init(cfg);
+    broadcast::init(cfg);
+    events::init(cfg);
+    graphql::init(cfg);
+    search::init(cfg);
+    post_of_the_day::init(cfg);
+    import_url::init(cfg);
+    post_counts::init(cfg);
+    api::init(cfg);
+    admin::init(cfg);
+    announcements::init(cfg);
+    github::init(cfg);
+    webhooks::init(cfg);
+    permissions::init(cfg);
+    relations::init(cfg);
+    similarity::init(cfg);
+    blocks::init(cfg);
+    translate::init(cfg);
+    merge::init(cfg);
+    duplicate::init(cfg);
+    export::init(cfg);
+    share_links::init(cfg);
+    features::init(cfg);
+    analytics::init(cfg);
+    geoip::init(cfg);
+    audit::init(cfg);
+    changelog::init(cfg);
+    health::init(cfg);
+    integrity::init(cfg);
+    settings::init(cfg);
+    robots::init(cfg);
+    #[cfg(debug_assertions)]
+    debug::run_debug_routes(cfg);
+    cfg.service(not_allowed());
 }
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paginate_context_links_neighbouring_pages() {
+        let ctx = paginate_context(2, 3, 10);
+        assert_eq!((ctx.current_page, ctx.total_pages, ctx.per_page), (2, 3, 10));
+        assert_eq!((ctx.prev_page, ctx.next_page), (Some(1), Some(3)));
+        assert!(ctx.has_prev && ctx.has_next);
+    }
+
+    #[test]
+    fn paginate_context_has_no_links_past_the_ends() {
+        let first = paginate_context(1, 3, 10);
+        assert_eq!((first.prev_page, first.has_prev), (None, false));
+        let last = paginate_context(3, 3, 10);
+        assert_eq!((last.next_page, last.has_next), (None, false));
+        let only = paginate_context(1, 0, 10);
+        assert!(!only.has_prev && !only.has_next);
+    }
+
+    fn limits(max_page: Option<usize>) -> PageSizeLimits {
+        PageSizeLimits { min: 5, max: 50, max_page }
+    }
+
+    #[test]
+    fn apply_keeps_sizes_within_the_limits() {
+        assert_eq!(limits(None).apply(Some(20), 10), Some((20, false)));
+        assert_eq!(limits(None).apply(Some(5), 10), Some((5, false)));
+        assert_eq!(limits(None).apply(Some(50), 10), Some((50, false)));
+    }
+
+    #[test]
+    fn apply_clamps_large_sizes_and_rejects_small_ones() {
+        assert_eq!(limits(None).apply(Some(51), 10), Some((50, true)));
+        assert_eq!(limits(None).apply(Some(4), 10), None);
+    }
+
+    #[test]
+    fn apply_clamps_the_default_silently() {
+        assert_eq!(limits(None).apply(None, 10), Some((10, false)));
+        assert_eq!(limits(None).apply(None, 1), Some((5, false)));
+        assert_eq!(limits(None).apply(None, 500), Some((50, false)));
+    }
+
+    #[test]
+    fn depth_error_allows_pages_up_to_the_limits() {
+        assert_eq!(limits(Some(10)).depth_error(10, 50), None);
+        assert_eq!(limits(None).depth_error(MAX_OFFSET / 50 + 1, 50), None);
+    }
+
+    #[test]
+    fn depth_error_rejects_pages_past_max_page() {
+        let message = limits(Some(10)).depth_error(11, 5).unwrap();
+        assert_eq!(message, "page must be at most 10");
+    }
+
+    #[test]
+    fn depth_error_rejects_offsets_past_max_offset() {
+        let message = limits(None).depth_error(MAX_OFFSET / 50 + 2, 50).unwrap();
+        assert_eq!(message, format!("pages past the first {} posts cannot be fetched", MAX_OFFSET));
+        assert!(limits(None).depth_error(usize::MAX, usize::MAX).is_some());
+    }
+}
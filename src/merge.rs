@@ -0,0 +1,149 @@
+use actix_web::{post, web, Error, HttpResponse};
+use actix_web::web::Data;
+use sea_orm::{entity::*, query::*, sea_query::{Expr, OnConflict}, DatabaseTransaction};
+
+use entity::annotation;
+use entity::annotation::Entity as Annotation;
+use entity::bookmark;
+use entity::bookmark::Entity as Bookmark;
+use entity::post::{self, Entity as Post, PostStatus};
+
+use crate::api::{self, MAX_TEXT_LEN};
+use crate::api_error::ApiError;
+use crate::auth::AuthUser;
+use crate::broadcast::{PostEvent, PostEventKind};
+use crate::jobs::Job;
+use crate::tenants::{tenanted_query, Tenant};
+use crate::{audit, encryption, permissions, AppState};
+
+/// Put between the target's text and the source's appended to it.
+const SEPARATOR: &str = "\n\n";
+
+async fn find_post(txn: &DatabaseTransaction, tenant_id: &str, id: u64) -> Result<post::Model, Error> {
+    tenanted_query::<Post>(tenant_id)
+        .filter(post::Column::Id.eq(id))
+        .lock_exclusive()
+        .one(txn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve post"))?
+        .ok_or_else(|| ApiError::post_not_found().into())
+}
+
+/// Moves the bookmarks of `source_id` to `target_id`; users who bookmarked both keep one.
+async fn move_bookmarks(txn: &DatabaseTransaction, source_id: u64, target_id: u64) -> Result<(), Error> {
+    let bookmarks = Bookmark::find()
+        .filter(bookmark::Column::PostId.eq(source_id))
+        .all(txn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve bookmarks"))?;
+    if bookmarks.is_empty() {
+        return Ok(());
+    }
+    let moved = bookmarks.into_iter().map(|bookmark| bookmark::ActiveModel {
+        user_id: Set(bookmark.user_id),
+        post_id: Set(target_id),
+        created_at: Set(bookmark.created_at),
+    });
+    // a no-op update of the key keeps existing bookmarks, as in `bookmarks::add_bookmark`
+    Bookmark::insert_many(moved)
+        .on_conflict(
+            OnConflict::columns([bookmark::Column::UserId, bookmark::Column::PostId])
+                .update_column(bookmark::Column::PostId)
+                .to_owned(),
+        )
+        .exec_without_returning(txn)
+        .await
+        .map_err(|_| ApiError::database("could not move bookmarks"))?;
+    Bookmark::delete_many()
+        .filter(bookmark::Column::PostId.eq(source_id))
+        .exec(txn)
+        .await
+        .map_err(|_| ApiError::database("could not move bookmarks"))?;
+    Ok(())
+}
+
+/// Combines duplicate post `id` into `target_id`: the source's text is appended to the
+/// target's, its bookmarks and annotations move to the target, and it is archived. Both
+/// posts get a revision of what they held before.
+///
+/// Posts have no slugs, so no redirect is left behind; the archived source stays readable
+/// at its own URL.
+#[post("/posts/merge/{id}/into/{target_id}")]
+async fn merge(data: Data<AppState>,
+               tenant: Tenant,
+               user: AuthUser,
+               path: web::Path<(u64, u64)>,
+) -> Result<HttpResponse, Error> {
+    let (source_id, target_id) = path.into_inner();
+    if source_id == target_id {
+        return Err(ApiError::bad_request("a post cannot be merged into itself").into());
+    }
+    let txn = data
+        .conn
+        .begin()
+        .await
+        .map_err(|_| ApiError::database("could not start transaction"))?;
+    let source = find_post(&txn, &tenant.id, source_id).await?;
+    let target = find_post(&txn, &tenant.id, target_id).await?;
+    permissions::require_write(&txn, &user, source_id).await?;
+    permissions::require_write(&txn, &user, target_id).await?;
+
+    let key = data.encryption_key.as_ref();
+    let reveal = |post: &post::Model| {
+        let mut post = post.clone();
+        encryption::reveal(&mut post, key).map_err(|_| ApiError::internal("could not decrypt post"))?;
+        Ok::<_, ApiError>(post.text)
+    };
+    let target_text = format!("{}{}", reveal(&target)?, SEPARATOR);
+    // annotation offsets count UTF-16 code units, see `annotations::AnnotationBody`
+    let shift = target_text.encode_utf16().count() as u64;
+    let text = data.store_text(target_text + &reveal(&source)?, target.is_encrypted)?;
+    if text.len() > MAX_TEXT_LEN {
+        let message = format!("the merged text must be at most {} bytes", MAX_TEXT_LEN);
+        return Err(ApiError::validation(message).into());
+    }
+
+    api::save_revision(&txn, user.id, &source).await?;
+    api::save_revision(&txn, user.id, &target).await?;
+    let mut merged: post::ActiveModel = target.clone().into();
+    merged.text = Set(text);
+    let merged = merged
+        .update(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not save post"))?;
+    let mut archived: post::ActiveModel = source.clone().into();
+    archived.status = Set(PostStatus::Archived);
+    let archived = archived
+        .update(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not archive post"))?;
+
+    move_bookmarks(&txn, source_id, target_id).await?;
+    Annotation::update_many()
+        .col_expr(annotation::Column::PostId, Expr::value(target_id))
+        .col_expr(annotation::Column::StartOffset, Expr::col(annotation::Column::StartOffset).add(shift))
+        .col_expr(annotation::Column::EndOffset, Expr::col(annotation::Column::EndOffset).add(shift))
+        .filter(annotation::Column::PostId.eq(source_id))
+        .exec(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not move annotations"))?;
+    audit::post_updated(&txn, Some(user.id), &target, &merged)
+        .await
+        .map_err(|_| ApiError::database("could not record merge"))?;
+    audit::post_updated(&txn, Some(user.id), &source, &archived)
+        .await
+        .map_err(|_| ApiError::database("could not record merge"))?;
+    txn.commit()
+        .await
+        .map_err(|_| ApiError::database("could not commit merge"))?;
+
+    data.broadcaster.broadcast(PostEvent::new(PostEventKind::Updated, source_id)).await;
+    data.broadcaster.broadcast(PostEvent::new(PostEventKind::Updated, target_id)).await;
+    data.jobs.enqueue(Job::CheckLinks { post_id: target_id });
+    data.similar_posts.invalidate();
+    Ok(HttpResponse::Found().append_header(("location", format!("/{}", target_id))).finish())
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(merge);
+}
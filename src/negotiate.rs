@@ -0,0 +1,73 @@
+use actix_web::{dev::Payload, http::header, Error, FromRequest, HttpRequest, HttpResponse, HttpResponseBuilder};
+use actix_web::web::Bytes;
+use futures_util::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::api_error::ApiError;
+use crate::payload_errors;
+
+const MSGPACK: &str = "application/msgpack";
+const X_MSGPACK: &str = "application/x-msgpack";
+
+fn is_msgpack(value: Option<&header::HeaderValue>) -> bool {
+    value
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains(MSGPACK) || value.contains(X_MSGPACK))
+}
+
+/// Writes `body` as MessagePack when the client's `Accept` asks for it, JSON otherwise.
+pub fn respond<T: Serialize + ?Sized>(req: &HttpRequest,
+                                      mut builder: HttpResponseBuilder,
+                                      body: &T,
+) -> Result<HttpResponse, Error> {
+    if is_msgpack(req.headers().get(header::ACCEPT)) {
+        let bytes = rmp_serde::to_vec_named(body)
+            .map_err(|_| ApiError::internal("could not serialize response"))?;
+        Ok(builder.content_type(MSGPACK).body(bytes))
+    } else {
+        Ok(builder.json(body))
+    }
+}
+
+/// A request body decoded from MessagePack or JSON depending on its `Content-Type`.
+#[derive(Debug)]
+pub struct Body<T>(pub T);
+
+impl<T> Body<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for Body<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for Body<T> {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let msgpack = is_msgpack(req.headers().get(header::CONTENT_TYPE));
+        let bytes = Bytes::from_request(req, payload);
+        Box::pin(async move {
+            let bytes = bytes.await?;
+            let value = if msgpack {
+                let mut de = rmp_serde::Deserializer::new(&bytes[..]);
+                serde_path_to_error::deserialize(&mut de).map_err(payload_errors::from_path_error)?
+            } else {
+                let mut de = serde_json::Deserializer::from_slice(&bytes);
+                let value = serde_path_to_error::deserialize(&mut de)
+                    .map_err(payload_errors::from_path_error)?;
+                de.end().map_err(|e| payload_errors::unprocessable(None, e))?;
+                value
+            };
+            Ok(Body(value))
+        })
+    }
+}
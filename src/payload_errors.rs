@@ -0,0 +1,93 @@
+use std::fmt::Display;
+
+use actix_web::error::{InternalError, JsonPayloadError, QueryPayloadError, UrlencodedError};
+use actix_web::{web, Error, HttpResponse};
+use serde::Serialize;
+
+use crate::api_error::ApiErrorCode;
+
+/// Serde messages that name a field in backticks; everything after the prefix is dropped.
+const FIELD_MESSAGES: &[&str] = &["missing field", "unknown field", "duplicate field"];
+
+#[derive(Debug, Serialize)]
+struct FieldError {
+    field: Option<String>,
+    message: String,
+}
+
+/// The `ApiError` body, plus the fields that failed.
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    error_code: u16,
+    message: String,
+    errors: Vec<FieldError>,
+}
+
+/// Builds the `FieldError` for a serde message, found at `path` inside the payload.
+///
+/// "missing field `title`" at the top level becomes field `title`, message "missing field".
+fn field_error(path: Option<String>, message: &str) -> FieldError {
+    // serde_json appends the position, which means little to API clients
+    let message = message.split(" at line ").next().unwrap_or(message);
+    for prefix in FIELD_MESSAGES {
+        let name = message
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_prefix(" `"))
+            .and_then(|rest| rest.split('`').next());
+        if let Some(name) = name {
+            let field = match path {
+                Some(path) => format!("{}.{}", path, name),
+                None => name.to_owned(),
+            };
+            return FieldError { field: Some(field), message: prefix.to_string() };
+        }
+    }
+    FieldError { field: path, message: message.to_owned() }
+}
+
+/// Responds 422 with `{"error_code": ..., "message": ..., "errors": [{"field": ...,
+/// "message": ...}]}` for a payload that could not be deserialized.
+pub fn unprocessable(path: Option<String>, err: impl Display) -> Error {
+    let err = err.to_string();
+    let body = ErrorBody {
+        error_code: ApiErrorCode::ValidationFailed.code(),
+        message: "request could not be decoded".to_owned(),
+        errors: vec![field_error(path, &err)],
+    };
+    InternalError::from_response(err, HttpResponse::UnprocessableEntity().json(body)).into()
+}
+
+/// Same as `unprocessable`, using the field path that `serde_path_to_error` tracked.
+pub fn from_path_error<E: Display>(err: serde_path_to_error::Error<E>) -> Error {
+    let path = err.path().to_string();
+    // the root path renders as "."
+    let path = if path == "." { None } else { Some(path) };
+    unprocessable(path, err.inner())
+}
+
+pub fn json_config() -> web::JsonConfig {
+    web::JsonConfig::default().error_handler(|err, _req| match err {
+        JsonPayloadError::Deserialize(err) => unprocessable(None, err),
+        err => err.into(),
+    })
+}
+
+pub fn form_config() -> web::FormConfig {
+    web::FormConfig::default().error_handler(|err, _req| match err {
+        UrlencodedError::Parse(err) => unprocessable(None, err),
+        err => err.into(),
+    })
+}
+
+/// Maps a query string that doesn't fit the handler's parameters to a 422, for handlers
+/// that parse the query themselves.
+pub fn query_error(err: QueryPayloadError) -> Error {
+    match err {
+        QueryPayloadError::Deserialize(err) => unprocessable(None, err),
+        err => err.into(),
+    }
+}
+
+pub fn query_config() -> web::QueryConfig {
+    web::QueryConfig::default().error_handler(|err, _req| query_error(err))
+}
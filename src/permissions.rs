@@ -0,0 +1,130 @@
+use actix_web::{delete, put, web, Error, HttpResponse};
+use actix_web::web::Data;
+use sea_orm::{entity::*, query::*, sea_query::OnConflict, ConnectionTrait, DbErr};
+use serde::Deserialize;
+
+use entity::post::{self, Entity as Post, PostStatus};
+use entity::post_permission::{self, Permission};
+use entity::post_permission::Entity as PostPermission;
+
+use crate::api_error::ApiError;
+use crate::auth::{AdminUser, AuthUser};
+use crate::negotiate::Body;
+use crate::tenants::{tenanted_query, Tenant};
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct GrantBody {
+    permission: Permission,
+}
+
+/// Gives `user_id` `permission` on `post_id`, replacing any permission they had.
+pub async fn grant<C: ConnectionTrait>(conn: &C,
+                                       user_id: u64,
+                                       post_id: u64,
+                                       permission: Permission,
+) -> Result<(), DbErr> {
+    let entry = post_permission::ActiveModel {
+        user_id: Set(user_id),
+        post_id: Set(post_id),
+        permission: Set(permission),
+    };
+    PostPermission::insert(entry)
+        .on_conflict(
+            OnConflict::columns([post_permission::Column::UserId, post_permission::Column::PostId])
+                .update_column(post_permission::Column::Permission)
+                .to_owned(),
+        )
+        .exec_without_returning(conn)
+        .await?;
+    Ok(())
+}
+
+/// Returns whether `user` may change `post_id`: global admins may change any post,
+/// everyone else needs a write or admin entry for it.
+pub async fn can_write<C: ConnectionTrait>(conn: &C, user: &AuthUser, post_id: u64) -> Result<bool, DbErr> {
+    if user.admin {
+        return Ok(true);
+    }
+    let entry = PostPermission::find_by_id((user.id, post_id)).one(conn).await?;
+    Ok(matches!(
+        entry.map(|entry| entry.permission),
+        Some(Permission::Write | Permission::Admin)
+    ))
+}
+
+/// Rejects with 403 unless `user` may change `post_id`.
+pub async fn require_write<C: ConnectionTrait>(conn: &C, user: &AuthUser, post_id: u64) -> Result<(), Error> {
+    let allowed = can_write(conn, user, post_id)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve permissions"))?;
+    match allowed {
+        true => Ok(()),
+        false => Err(ApiError::forbidden("you may not change this post").into()),
+    }
+}
+
+/// Whether `user` may see `post`: anyone may see published posts, only those who may
+/// change a post its drafts and archived versions.
+pub async fn can_read<C: ConnectionTrait>(conn: &C, user: Option<&AuthUser>, post: &post::Model) -> Result<bool, DbErr> {
+    if post.status == PostStatus::Published {
+        return Ok(true);
+    }
+    match user {
+        Some(user) => can_write(conn, user, post.id).await,
+        None => Ok(false),
+    }
+}
+
+/// Rejects with 404 unless `user` may see `post`, so unpublished posts look like they
+/// don't exist.
+pub async fn require_read<C: ConnectionTrait>(conn: &C, user: Option<&AuthUser>, post: &post::Model) -> Result<(), Error> {
+    let allowed = can_read(conn, user, post)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve permissions"))?;
+    match allowed {
+        true => Ok(()),
+        false => Err(ApiError::post_not_found().into()),
+    }
+}
+
+#[put("/admin/posts/{post_id}/permissions/{user_id}")]
+async fn grant_permission(data: Data<AppState>,
+                          tenant: Tenant,
+                          _admin: AdminUser,
+                          path: web::Path<(u64, u64)>,
+                          body: Body<GrantBody>,
+) -> Result<HttpResponse, Error> {
+    let (post_id, user_id) = path.into_inner();
+    tenanted_query::<Post>(&tenant.id)
+        .filter(post::Column::Id.eq(post_id))
+        .one(&data.conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+    grant(&data.conn, user_id, post_id, body.permission)
+        .await
+        .map_err(|_| ApiError::database("could not save permission"))?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[delete("/admin/posts/{post_id}/permissions/{user_id}")]
+async fn revoke_permission(data: Data<AppState>,
+                           _admin: AdminUser,
+                           path: web::Path<(u64, u64)>,
+) -> Result<HttpResponse, Error> {
+    let (post_id, user_id) = path.into_inner();
+    let result = PostPermission::delete_by_id((user_id, post_id))
+        .exec(&data.conn)
+        .await
+        .map_err(|_| ApiError::database("could not delete permission"))?;
+    if result.rows_affected == 0 {
+        return Err(ApiError::not_found("permission not found").into());
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(grant_permission);
+    cfg.service(revoke_permission);
+}
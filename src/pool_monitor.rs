@@ -0,0 +1,56 @@
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use sea_orm::DatabaseConnection;
+
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(30);
+const DEFAULT_MIN_IDLE_CONNECTIONS: usize = 1;
+
+/// Counters about the server's own health, shown by `/health`.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsState {
+    pool_exhaustions: Arc<AtomicU64>,
+}
+
+impl MetricsState {
+    /// How many pool checks found no idle connection.
+    pub fn pool_exhaustions(&self) -> u64 {
+        self.pool_exhaustions.load(Ordering::Relaxed)
+    }
+}
+
+/// Checks the connection pool, run by the scheduler every `POOL_MONITOR_INTERVAL_SECS`.
+/// Warns when fewer than `MIN_IDLE_CONNECTIONS` connections are idle, and counts the
+/// checks that found none.
+#[derive(Debug, Clone)]
+pub struct PoolMonitor {
+    min_idle: usize,
+    metrics: MetricsState,
+}
+
+impl PoolMonitor {
+    pub fn from_env(metrics: MetricsState) -> Self {
+        let min_idle = env::var("MIN_IDLE_CONNECTIONS")
+            .map(|min| min.parse().expect("MIN_IDLE_CONNECTIONS must be a number"))
+            .unwrap_or(DEFAULT_MIN_IDLE_CONNECTIONS);
+        PoolMonitor { min_idle, metrics }
+    }
+
+    pub fn check(&self, conn: &DatabaseConnection) {
+        let pool = conn.get_mysql_connection_pool();
+        self.record(pool.size(), pool.num_idle());
+    }
+
+    fn record(&self, size: u32, idle: usize) {
+        if idle >= self.min_idle {
+            return;
+        }
+        let min_idle = self.min_idle;
+        tracing::warn!(size, idle, min_idle, "database pool is running out of idle connections");
+        if idle == 0 {
+            self.metrics.pool_exhaustions.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
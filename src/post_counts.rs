@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix_web::{get, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use sea_orm::{entity::*, query::*, DatabaseConnection, DbErr};
+use serde::{Deserialize, Serialize};
+
+use entity::post::{self, Entity as Post, PostStatus};
+
+use crate::api_error::ApiError;
+use crate::auth::AuthUser;
+use crate::tenants::{tenanted_query, Tenant};
+use crate::{negotiate, AppState};
+
+const CACHE_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Deserialize)]
+pub struct PostCountParams {
+    /// `1` counts again even when the cached counts are fresh.
+    force_refresh: Option<u8>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PostCounts {
+    total: i64,
+    published: i64,
+    draft: i64,
+    archived: i64,
+}
+
+/// Post counts by status of each tenant, recounted at most every minute.
+#[derive(Debug, Clone, Default)]
+pub struct PostCountCache {
+    counts: Arc<Mutex<HashMap<String, (Instant, PostCounts)>>>,
+}
+
+impl PostCountCache {
+    async fn counts(&self, conn: &DatabaseConnection, tenant_id: &str, force_refresh: bool) -> Result<PostCounts, DbErr> {
+        if !force_refresh {
+            if let Some((counted_at, counts)) = self.counts.lock().unwrap().get(tenant_id) {
+                if counted_at.elapsed() < CACHE_TTL {
+                    return Ok(*counts);
+                }
+            }
+        }
+        let rows: Vec<(PostStatus, i64)> = tenanted_query::<Post>(tenant_id)
+            .select_only()
+            .column(post::Column::Status)
+            .column_as(post::Column::Id.count(), "count")
+            .group_by(post::Column::Status)
+            .into_tuple()
+            .all(conn)
+            .await?;
+        let mut counts = PostCounts::default();
+        for (status, count) in rows {
+            counts.total += count;
+            match status {
+                PostStatus::Published => counts.published = count,
+                PostStatus::Draft => counts.draft = count,
+                PostStatus::Archived => counts.archived = count,
+            }
+        }
+        self.counts.lock().unwrap().insert(tenant_id.to_owned(), (Instant::now(), counts));
+        Ok(counts)
+    }
+}
+
+/// How many posts the tenant has in each status, for dashboard widgets. The counts may
+/// be up to a minute old unless `force_refresh=1`.
+#[get("/api/v1/posts/count")]
+async fn post_count(req: HttpRequest,
+                    data: Data<AppState>,
+                    tenant: Tenant,
+                    _user: AuthUser,
+                    params: web::Query<PostCountParams>,
+) -> Result<HttpResponse, Error> {
+    let counts = data
+        .post_counts
+        .counts(&data.conn, &tenant.id, params.force_refresh == Some(1))
+        .await
+        .map_err(|_| ApiError::database("could not count posts"))?;
+    negotiate::respond(&req, HttpResponse::Ok(), &counts)
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(post_count);
+}
@@ -0,0 +1,105 @@
+use actix_web::{get, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use chrono::{NaiveDate, Utc};
+use sea_orm::{entity::*, query::*, sea_query::OnConflict, DatabaseConnection, DbErr};
+use serde::Serialize;
+
+use entity::post::{self, Entity as Post, PostStatus};
+use entity::scheduled_feature;
+use entity::scheduled_feature::Entity as ScheduledFeature;
+use entity::tenant::Entity as TenantEntity;
+
+use crate::api;
+use crate::api_error::ApiError;
+use crate::tenants::{tenanted_query, Tenant};
+use crate::{negotiate, AppState};
+
+#[derive(Debug, Serialize)]
+struct PostOfTheDay {
+    date: NaiveDate,
+    post: post::Model,
+}
+
+/// The post of the day of `tenant_id` on `date`, picking a random published post when the
+/// day has none yet. A pick stays for the whole day, even once its post is unpublished;
+/// `None` means the tenant has no published posts.
+pub async fn find_or_pick(conn: &DatabaseConnection,
+                          tenant_id: &str,
+                          date: NaiveDate,
+) -> Result<Option<post::Model>, DbErr> {
+    let find = || {
+        tenanted_query::<ScheduledFeature>(tenant_id)
+            .filter(scheduled_feature::Column::Date.eq(date))
+            .find_also_related(Post)
+            .one(conn)
+    };
+    if let Some((_, post)) = find().await? {
+        return Ok(post);
+    }
+    let Some(post) = api::random_published(conn.get_database_backend(), tenant_id).one(conn).await? else {
+        return Ok(None);
+    };
+    let feature = scheduled_feature::ActiveModel {
+        tenant_id: Set(tenant_id.to_owned()),
+        post_id: Set(post.id),
+        date: Set(date),
+        ..Default::default()
+    };
+    // a pick made meanwhile by another request wins; the no-op update keeps it, as in
+    // `bookmarks::add_bookmark`
+    ScheduledFeature::insert(feature)
+        .on_conflict(
+            OnConflict::columns([scheduled_feature::Column::TenantId, scheduled_feature::Column::Date])
+                .update_column(scheduled_feature::Column::Date)
+                .to_owned(),
+        )
+        .exec_without_returning(conn)
+        .await?;
+    Ok(find().await?.and_then(|(_, post)| post))
+}
+
+/// Picks today's post of every tenant; run at midnight so the first visitor of the day
+/// doesn't wait for it.
+pub async fn pick_for_all_tenants(conn: &DatabaseConnection) {
+    let today = Utc::now().date_naive();
+    let tenants = match TenantEntity::find().all(conn).await {
+        Ok(tenants) => tenants,
+        Err(err) => {
+            tracing::warn!(%err, "could not retrieve tenants");
+            return;
+        }
+    };
+    for tenant in tenants {
+        if let Err(err) = find_or_pick(conn, &tenant.id, today).await {
+            tracing::warn!(%err, tenant = tenant.id, "could not pick the post of the day");
+        }
+    }
+}
+
+/// Today's post of the day, for the list page; `None` too when it is no longer
+/// published, so drafts never show there.
+pub async fn for_list(conn: &DatabaseConnection, tenant_id: &str) -> Result<Option<post::Model>, Error> {
+    let post = find_or_pick(conn, tenant_id, Utc::now().date_naive())
+        .await
+        .map_err(|_| ApiError::database("could not retrieve the post of the day"))?;
+    Ok(post.filter(|post| post.status == PostStatus::Published))
+}
+
+#[get("/api/v1/posts/today")]
+async fn todays_post(req: HttpRequest,
+                     data: Data<AppState>,
+                     tenant: Tenant,
+) -> Result<HttpResponse, Error> {
+    let date = Utc::now().date_naive();
+    let mut post = find_or_pick(&data.conn, &tenant.id, date)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve the post of the day"))?
+        .filter(|post| post.status == PostStatus::Published)
+        .ok_or_else(ApiError::post_not_found)?;
+    data.reveal(&mut post)?;
+    negotiate::respond(&req, HttpResponse::Ok(), &PostOfTheDay { date, post })
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(todays_post);
+}
@@ -0,0 +1,81 @@
+use std::env;
+
+use actix_web::http::header::HeaderValue;
+
+/// Stylesheets every page links, preloaded when `PUSH_ASSETS` is not set.
+const DEFAULT_PUSH_ASSETS: &[&str] = &[
+    "/static/css/normalize.css",
+    "/static/css/skeleton.css",
+    "/static/css/style.css",
+];
+
+/// The `as` destination of a preloaded asset, by its extension.
+fn destination(path: &str) -> Option<&'static str> {
+    let extension = path.rsplit_once('.')?.1.to_ascii_lowercase();
+    match extension.as_str() {
+        "css" => Some("style"),
+        "js" | "mjs" => Some("script"),
+        "woff" | "woff2" | "ttf" | "otf" => Some("font"),
+        "png" | "jpg" | "jpeg" | "gif" | "webp" | "svg" | "ico" => Some("image"),
+        _ => None,
+    }
+}
+
+/// One `Link` entry per asset. Fonts are always fetched in CORS mode, so their preload
+/// only matches the real request with `crossorigin`.
+fn render(paths: &[&str]) -> String {
+    paths
+        .iter()
+        .filter_map(|path| match destination(path)? {
+            "font" => Some(format!("<{}>; rel=preload; as=font; crossorigin", path)),
+            kind => Some(format!("<{}>; rel=preload; as={}", path, kind)),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The `Link` header preloading the assets in `PUSH_ASSETS` (comma-separated paths), read
+/// once at startup. Paths of no known asset type are skipped, since a preload without
+/// `as` is fetched twice.
+///
+/// HTTP/2 servers that push can turn these into `PUSH_PROMISE` frames; browsers preload
+/// them either way.
+pub fn link_header_from_env() -> Option<HeaderValue> {
+    let assets = env::var("PUSH_ASSETS").ok();
+    let paths: Vec<&str> = match &assets {
+        Some(assets) => assets.split(',').map(str::trim).filter(|path| !path.is_empty()).collect(),
+        None => DEFAULT_PUSH_ASSETS.to_vec(),
+    };
+    let links = render(&paths);
+    match links.is_empty() {
+        true => None,
+        false => Some(HeaderValue::from_str(&links).expect("PUSH_ASSETS must be printable ASCII")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_links_each_asset_by_type() {
+        let links = render(&["/static/css/style.css", "/static/js/app.JS", "/static/img/logo.svg"]);
+        assert_eq!(
+            links,
+            "</static/css/style.css>; rel=preload; as=style, \
+             </static/js/app.JS>; rel=preload; as=script, \
+             </static/img/logo.svg>; rel=preload; as=image",
+        );
+    }
+
+    #[test]
+    fn render_marks_fonts_crossorigin() {
+        let links = render(&["/static/fonts/body.woff2"]);
+        assert_eq!(links, "</static/fonts/body.woff2>; rel=preload; as=font; crossorigin");
+    }
+
+    #[test]
+    fn render_skips_unknown_types() {
+        assert_eq!(render(&["/static/data.json", "/static/noextension"]), "");
+    }
+}
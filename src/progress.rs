@@ -0,0 +1,95 @@
+use actix_web::{get, post, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use sea_orm::{entity::*, query::*, sea_query::OnConflict};
+use serde::{Deserialize, Serialize};
+
+use entity::post::{self, Entity as Post};
+use entity::reading_progress;
+use entity::reading_progress::Entity as ReadingProgress;
+
+use crate::api_error::ApiError;
+use crate::auth::AuthUser;
+use crate::circuit_breaker::with_circuit_breaker;
+use crate::negotiate::{self, Body};
+use crate::tenants::{tenanted_query, Tenant};
+use crate::AppState;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ProgressBody {
+    scroll_percent: u8,
+}
+
+/// Returns the stored scroll position of `user_id` for `post_id`, if any.
+pub async fn find_progress(
+    data: &AppState,
+    user_id: u64,
+    post_id: u64,
+) -> Result<Option<u8>, Error> {
+    let find = || ReadingProgress::find_by_id((user_id, post_id)).one(&data.conn);
+    let progress = with_circuit_breaker(&data.circuit_breaker, find)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not retrieve reading progress"))?;
+    Ok(progress.map(|progress| progress.scroll_percent))
+}
+
+#[post("/api/v1/posts/{id}/progress")]
+async fn save_progress(req: HttpRequest,
+                       data: Data<AppState>,
+                       tenant: Tenant,
+                       user: AuthUser,
+                       id: web::Path<u64>,
+                       body: Body<ProgressBody>,
+) -> Result<HttpResponse, Error> {
+    let conn = &data.conn;
+    let post_id = id.into_inner();
+    if body.scroll_percent > 100 {
+        return Err(ApiError::validation("scroll_percent must be between 0 and 100").into());
+    }
+    let breaker = &data.circuit_breaker;
+    let find = || tenanted_query::<Post>(&tenant.id).filter(post::Column::Id.eq(post_id)).one(conn);
+    with_circuit_breaker(breaker, find)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+
+    let progress = reading_progress::ActiveModel {
+        user_id: Set(user.id),
+        post_id: Set(post_id),
+        scroll_percent: Set(body.scroll_percent),
+        updated_at: Set(chrono::Utc::now()),
+    };
+    let save = ReadingProgress::insert(progress)
+        .on_conflict(
+            OnConflict::columns([
+                reading_progress::Column::UserId,
+                reading_progress::Column::PostId,
+            ])
+                .update_columns([
+                    reading_progress::Column::ScrollPercent,
+                    reading_progress::Column::UpdatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec_without_returning(conn);
+    with_circuit_breaker(breaker, || save)
+        .await
+        .map_err(|err| ApiError::from_db(&err, "could not save reading progress"))?;
+    negotiate::respond(&req, HttpResponse::Ok(), &ProgressBody { scroll_percent: body.scroll_percent })
+}
+
+#[get("/api/v1/posts/{id}/progress")]
+async fn get_progress(req: HttpRequest,
+                      data: Data<AppState>,
+                      user: AuthUser,
+                      id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
+    let scroll_percent = find_progress(&data, user.id, id.into_inner())
+        .await?
+        .ok_or_else(|| ApiError::not_found("no reading progress for this post"))?;
+    negotiate::respond(&req, HttpResponse::Ok(), &ProgressBody { scroll_percent })
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(save_progress);
+    cfg.service(get_progress);
+}
@@ -0,0 +1,44 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpMessage};
+use sea_orm::DatabaseConnection;
+
+const QUERY_COUNT_HEADER: HeaderName = HeaderName::from_static("x-db-query-count");
+
+tokio::task_local! {
+    static CURRENT: QueryCounter;
+}
+
+/// Number of queries run while handling one request, kept in the request extensions.
+#[derive(Debug, Clone, Default)]
+pub struct QueryCounter(Arc<AtomicUsize>);
+
+impl QueryCounter {
+    pub fn count(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Makes `conn` count every query it runs towards the request running it. Queries from
+/// outside a request, such as background jobs, are not counted.
+pub fn count_queries(conn: &mut DatabaseConnection) {
+    conn.set_metric_callback(|_| {
+        let _ = CURRENT.try_with(|counter| counter.0.fetch_add(1, Ordering::Relaxed));
+    });
+}
+
+/// Counts the queries of each request and reports them in `X-DB-Query-Count`.
+pub async fn middleware(req: ServiceRequest,
+                        next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let counter = QueryCounter::default();
+    req.extensions_mut().insert(counter.clone());
+    let mut res = CURRENT.scope(counter.clone(), next.call(req)).await?;
+    res.headers_mut().insert(QUERY_COUNT_HEADER, HeaderValue::from(counter.count()));
+    Ok(res)
+}
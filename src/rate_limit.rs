@@ -0,0 +1,62 @@
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix_web::error::InternalError;
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::{Error, ResponseError};
+
+use crate::api_error::{ApiError, ApiErrorCode};
+
+const DEFAULT_MAX_POSTS_PER_HOUR: usize = 10;
+const WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Limits how many posts each user may create within a sliding hour, from
+/// `MAX_POSTS_PER_HOUR`.
+#[derive(Debug, Clone)]
+pub struct PostRateLimiter {
+    max_per_window: usize,
+    /// Times of each user's creations within the window, oldest first.
+    creations: Arc<Mutex<HashMap<u64, VecDeque<Instant>>>>,
+}
+
+impl PostRateLimiter {
+    pub fn from_env() -> Self {
+        let max_per_window = env::var("MAX_POSTS_PER_HOUR")
+            .map(|max| max.parse().expect("MAX_POSTS_PER_HOUR must be a number"))
+            .unwrap_or(DEFAULT_MAX_POSTS_PER_HOUR);
+        PostRateLimiter { max_per_window, creations: Arc::default() }
+    }
+
+    /// Counts a creation by `user_id`, or returns how long until the user may create
+    /// again.
+    fn try_acquire(&self, user_id: u64) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut creations = self.creations.lock().unwrap();
+        let times = creations.entry(user_id).or_default();
+        while times.front().is_some_and(|&time| now.duration_since(time) >= WINDOW) {
+            times.pop_front();
+        }
+        if times.len() >= self.max_per_window {
+            let oldest = times.front().copied().unwrap_or(now);
+            return Err(WINDOW.saturating_sub(now.duration_since(oldest)));
+        }
+        times.push_back(now);
+        Ok(())
+    }
+
+    /// Counts a creation by `user_id`, answering 429 with `Retry-After` when the user is
+    /// over the limit.
+    pub fn check(&self, user_id: u64) -> Result<(), Error> {
+        self.try_acquire(user_id).map_err(|retry_after| {
+            // round up so a client waiting exactly this long is let through
+            let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            let message = format!("at most {} posts can be created per hour", self.max_per_window);
+            let error = ApiError::new(ApiErrorCode::RateLimitExceeded, message);
+            let mut response = error.error_response();
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+            InternalError::from_response(error, response).into()
+        })
+    }
+}
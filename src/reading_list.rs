@@ -0,0 +1,179 @@
+use std::collections::HashSet;
+
+use actix_web::{delete, get, post, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use chrono::{DateTime, Utc};
+use sea_orm::{entity::*, query::*, sea_query::CaseStatement};
+use serde::{Deserialize, Serialize};
+
+use entity::post::{self, Entity as Post};
+use entity::reading_list_item;
+use entity::reading_list_item::Entity as ReadingListItem;
+
+use crate::api_error::ApiError;
+use crate::auth::AuthUser;
+use crate::negotiate::{self, Body};
+use crate::tenants::{tenanted_query, Tenant};
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct AddInput {
+    post_id: u64,
+}
+
+/// Body of `POST /api/v1/reading-list/reorder`: post ids, first to last.
+#[derive(Debug, Deserialize)]
+pub struct ReorderInput {
+    ordered_ids: Vec<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct ReadingListEntry {
+    position: i32,
+    added_at: DateTime<Utc>,
+    post: post::Model,
+}
+
+/// Queues a post at the end of the user's reading list; a queued post keeps its place.
+#[post("/api/v1/reading-list")]
+async fn add_item(data: Data<AppState>,
+                  tenant: Tenant,
+                  user: AuthUser,
+                  body: Body<AddInput>,
+) -> Result<HttpResponse, Error> {
+    let post_id = body.into_inner().post_id;
+    let txn = data
+        .conn
+        .begin()
+        .await
+        .map_err(|_| ApiError::database("could not start transaction"))?;
+    tenanted_query::<Post>(&tenant.id)
+        .filter(post::Column::Id.eq(post_id))
+        .one(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+    // locking the user's items keeps two appends from taking the same position
+    let items = ReadingListItem::find()
+        .filter(reading_list_item::Column::UserId.eq(user.id))
+        .order_by_desc(reading_list_item::Column::Position)
+        .lock_exclusive()
+        .all(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve reading list"))?;
+    if items.iter().any(|item| item.post_id == post_id) {
+        return Ok(HttpResponse::NoContent().finish());
+    }
+    let position = items.first().map_or(0, |last| last.position + 1);
+    reading_list_item::ActiveModel {
+        user_id: Set(user.id),
+        post_id: Set(post_id),
+        position: Set(position),
+        added_at: Set(Utc::now()),
+    }
+        .insert(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not save reading list item"))?;
+    txn.commit()
+        .await
+        .map_err(|_| ApiError::database("could not commit reading list"))?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[delete("/api/v1/reading-list/{post_id}")]
+async fn remove_item(data: Data<AppState>,
+                     user: AuthUser,
+                     post_id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
+    let result = ReadingListItem::delete_by_id((user.id, post_id.into_inner()))
+        .exec(&data.conn)
+        .await
+        .map_err(|_| ApiError::database("could not delete reading list item"))?;
+    if result.rows_affected == 0 {
+        return Err(ApiError::not_found("post is not on the reading list").into());
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Puts the listed posts first, in the given order, and the rest after them in the order
+/// they had.
+#[post("/api/v1/reading-list/reorder")]
+async fn reorder_items(data: Data<AppState>,
+                       user: AuthUser,
+                       body: Body<ReorderInput>,
+) -> Result<HttpResponse, Error> {
+    let ordered_ids = body.into_inner().ordered_ids;
+    let mut seen = HashSet::with_capacity(ordered_ids.len());
+    if let Some(duplicate) = ordered_ids.iter().find(|id| !seen.insert(**id)) {
+        return Err(ApiError::bad_request(format!("post {} is listed more than once", duplicate)).into());
+    }
+    let txn = data
+        .conn
+        .begin()
+        .await
+        .map_err(|_| ApiError::database("could not start transaction"))?;
+    let items = ReadingListItem::find()
+        .filter(reading_list_item::Column::UserId.eq(user.id))
+        .order_by_asc(reading_list_item::Column::Position)
+        .lock_exclusive()
+        .all(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve reading list"))?;
+    let queued: HashSet<u64> = items.iter().map(|item| item.post_id).collect();
+    if let Some(missing) = ordered_ids.iter().find(|id| !queued.contains(id)) {
+        return Err(ApiError::not_found(format!("post {} is not on the reading list", missing)).into());
+    }
+    if items.is_empty() {
+        return Ok(HttpResponse::NoContent().finish());
+    }
+    let rest = items.iter().map(|item| item.post_id).filter(|id| !seen.contains(id));
+    let position = ordered_ids
+        .iter()
+        .copied()
+        .chain(rest)
+        .enumerate()
+        .fold(CaseStatement::new(), |case, (index, id)| {
+            case.case(reading_list_item::Column::PostId.eq(id), index as i32)
+        });
+    ReadingListItem::update_many()
+        .col_expr(reading_list_item::Column::Position, position.into())
+        .filter(reading_list_item::Column::UserId.eq(user.id))
+        .exec(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not reorder reading list"))?;
+    txn.commit()
+        .await
+        .map_err(|_| ApiError::database("could not commit reading list"))?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[get("/api/v1/reading-list")]
+async fn list_items(req: HttpRequest,
+                    data: Data<AppState>,
+                    tenant: Tenant,
+                    user: AuthUser,
+) -> Result<HttpResponse, Error> {
+    let items = ReadingListItem::find()
+        .filter(reading_list_item::Column::UserId.eq(user.id))
+        .order_by_asc(reading_list_item::Column::Position)
+        .find_also_related(Post)
+        .filter(post::Column::TenantId.eq(tenant.id.as_str()))
+        .all(&data.conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve reading list"))?;
+    let mut entries = Vec::with_capacity(items.len());
+    for (item, post) in items {
+        if let Some(mut post) = post {
+            data.reveal(&mut post)?;
+            entries.push(ReadingListEntry { position: item.position, added_at: item.added_at, post });
+        }
+    }
+    negotiate::respond(&req, HttpResponse::Ok(), &entries)
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(add_item);
+    cfg.service(remove_item);
+    cfg.service(reorder_items);
+    cfg.service(list_items);
+}
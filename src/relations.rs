@@ -0,0 +1,138 @@
+use std::collections::{BTreeMap, HashMap};
+
+use actix_web::{delete, get, post, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use sea_orm::{entity::*, query::*, DatabaseConnection};
+use serde::{Deserialize, Serialize};
+
+use entity::post::{self, Entity as Post};
+use entity::post_relation::{self, RelationType};
+use entity::post_relation::Entity as PostRelation;
+
+use crate::api_error::ApiError;
+use crate::auth::AdminUser;
+use crate::negotiate::{self, Body};
+use crate::tenants::{tenanted_query, Tenant};
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct RelationInput {
+    related_post_id: u64,
+    relation_type: RelationType,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RelatedPost {
+    id: u64,
+    title: String,
+}
+
+/// Posts `post_id` links to, by the type of link, each group in id order.
+pub async fn find_related_posts(conn: &DatabaseConnection,
+                                post_id: u64,
+) -> Result<BTreeMap<RelationType, Vec<RelatedPost>>, Error> {
+    let relations = PostRelation::find()
+        .filter(post_relation::Column::PostId.eq(post_id))
+        .order_by_asc(post_relation::Column::RelatedPostId)
+        .all(conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve relations"))?;
+    let ids: Vec<u64> = relations.iter().map(|relation| relation.related_post_id).collect();
+    let mut titles: HashMap<u64, String> = Post::find()
+        .filter(post::Column::Id.is_in(ids))
+        .all(conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve related posts"))?
+        .into_iter()
+        .map(|post| (post.id, post.title))
+        .collect();
+
+    let mut grouped: BTreeMap<RelationType, Vec<RelatedPost>> = BTreeMap::new();
+    for relation in relations {
+        if let Some(title) = titles.remove(&relation.related_post_id) {
+            let related = RelatedPost { id: relation.related_post_id, title };
+            grouped.entry(relation.relation_type).or_default().push(related);
+        }
+    }
+    Ok(grouped)
+}
+
+async fn require_post(conn: &DatabaseConnection, tenant_id: &str, id: u64) -> Result<(), Error> {
+    tenanted_query::<Post>(tenant_id)
+        .filter(post::Column::Id.eq(id))
+        .one(conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+    Ok(())
+}
+
+#[get("/admin/posts/{id}/relations")]
+async fn list_relations(req: HttpRequest,
+                        data: Data<AppState>,
+                        tenant: Tenant,
+                        _admin: AdminUser,
+                        id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
+    let post_id = id.into_inner();
+    require_post(&data.conn, &tenant.id, post_id).await?;
+    let related = find_related_posts(&data.conn, post_id).await?;
+    negotiate::respond(&req, HttpResponse::Ok(), &related)
+}
+
+#[post("/admin/posts/{id}/relations")]
+async fn add_relation(data: Data<AppState>,
+                      tenant: Tenant,
+                      _admin: AdminUser,
+                      id: web::Path<u64>,
+                      body: Body<RelationInput>,
+) -> Result<HttpResponse, Error> {
+    let conn = &data.conn;
+    let post_id = id.into_inner();
+    let input = body.into_inner();
+    if input.related_post_id == post_id {
+        return Err(ApiError::bad_request("a post cannot be related to itself").into());
+    }
+    require_post(conn, &tenant.id, post_id).await?;
+    require_post(conn, &tenant.id, input.related_post_id).await?;
+    let existing = PostRelation::find_by_id((post_id, input.related_post_id))
+        .one(conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve relation"))?;
+    if existing.is_some() {
+        return Err(ApiError::conflict("the posts are already related").into());
+    }
+    post_relation::ActiveModel {
+        post_id: Set(post_id),
+        related_post_id: Set(input.related_post_id),
+        relation_type: Set(input.relation_type),
+    }
+        .insert(conn)
+        .await
+        .map_err(|_| ApiError::database("could not save relation"))?;
+    Ok(HttpResponse::Created().finish())
+}
+
+#[delete("/admin/posts/{id}/relations/{related_post_id}")]
+async fn remove_relation(data: Data<AppState>,
+                         tenant: Tenant,
+                         _admin: AdminUser,
+                         path: web::Path<(u64, u64)>,
+) -> Result<HttpResponse, Error> {
+    let (post_id, related_post_id) = path.into_inner();
+    require_post(&data.conn, &tenant.id, post_id).await?;
+    let result = PostRelation::delete_by_id((post_id, related_post_id))
+        .exec(&data.conn)
+        .await
+        .map_err(|_| ApiError::database("could not delete relation"))?;
+    if result.rows_affected == 0 {
+        return Err(ApiError::not_found("relation not found").into());
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_relations);
+    cfg.service(add_relation);
+    cfg.service(remove_relation);
+}
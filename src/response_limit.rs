@@ -0,0 +1,100 @@
+use std::env;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use actix_web::body::{BodySize, BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header;
+use actix_web::middleware::Next;
+use actix_web::web::{Bytes, Data};
+use actix_web::Error;
+
+use crate::AppState;
+
+const DEFAULT_RESPONSE_SIZE_LIMIT: usize = 1024 * 1024;
+/// Appended where a body was cut, so the cut shows in the page source.
+const TRUNCATED_MARKER: &[u8] = b"\n<!-- truncated -->";
+
+/// Most bytes of an HTML body sent, from `RESPONSE_SIZE_LIMIT_BYTES`.
+pub fn limit_from_env() -> usize {
+    env::var("RESPONSE_SIZE_LIMIT_BYTES")
+        .map(|limit| limit.parse().expect("RESPONSE_SIZE_LIMIT_BYTES must be a number"))
+        .unwrap_or(DEFAULT_RESPONSE_SIZE_LIMIT)
+}
+
+/// The longest prefix of `chunk` of at most `len` bytes that does not split a UTF-8
+/// character.
+fn char_boundary(chunk: &[u8], len: usize) -> usize {
+    let mut len = len.min(chunk.len());
+    while len > 0 && len < chunk.len() && chunk[len] & 0xC0 == 0x80 {
+        len -= 1;
+    }
+    len
+}
+
+/// A body that passes on at most `limit` bytes of `inner`, then the truncation marker
+/// and nothing more.
+struct LimitedBody {
+    inner: BoxBody,
+    remaining: usize,
+    /// Logged with the warning, from the `{id}` of the route.
+    post_id: Option<String>,
+    done: bool,
+}
+
+impl MessageBody for LimitedBody {
+    type Error = Box<dyn std::error::Error>;
+
+    fn size(&self) -> BodySize {
+        match self.inner.size() {
+            BodySize::Sized(len) if len as usize <= self.remaining => BodySize::Sized(len),
+            BodySize::None => BodySize::None,
+            _ => BodySize::Stream,
+        }
+    }
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+        let chunk = match Pin::new(&mut this.inner).poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => chunk,
+            other => return other,
+        };
+        if chunk.len() <= this.remaining {
+            this.remaining -= chunk.len();
+            return Poll::Ready(Some(Ok(chunk)));
+        }
+        this.done = true;
+        tracing::warn!(post_id = this.post_id.as_deref(), "html response truncated");
+        let mut truncated = chunk[..char_boundary(&chunk, this.remaining)].to_vec();
+        truncated.extend_from_slice(TRUNCATED_MARKER);
+        Poll::Ready(Some(Ok(Bytes::from(truncated))))
+    }
+}
+
+/// ResponseSizeLimit: cuts HTML bodies longer than the limit and marks the cut with
+/// `<!-- truncated -->`. Other content types, binary and JSON alike, are never cut.
+pub async fn middleware(req: ServiceRequest,
+                        next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let limit = req.app_data::<Data<AppState>>().map(|data| data.response_size_limit);
+    let res = next.call(req).await?;
+    let is_html = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("text/html"));
+    let Some(limit) = limit.filter(|_| is_html) else {
+        return Ok(res.map_into_boxed_body());
+    };
+    let post_id = res.request().match_info().get("id").map(str::to_owned);
+    Ok(res.map_body(|head, body| {
+        let body = LimitedBody { inner: body.boxed(), remaining: limit, post_id, done: false };
+        if matches!(body.size(), BodySize::Stream) {
+            head.headers_mut().remove(header::CONTENT_LENGTH);
+        }
+        BoxBody::new(body)
+    }))
+}
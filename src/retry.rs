@@ -0,0 +1,69 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use rand::Rng;
+use sea_orm::{DatabaseConnection, DatabaseTransaction, DbErr, TransactionTrait};
+
+/// Retries the handlers allow a transaction before giving up.
+pub const MAX_RETRIES: u32 = 3;
+const BASE_BACKOFF: Duration = Duration::from_millis(10);
+/// Lowercase fragments of the errors a database raises when a transaction lost a race
+/// with another one: PostgreSQL serialization failures, MySQL deadlocks and lock timeouts.
+const RETRYABLE_MESSAGES: &[&str] = &[
+    "serialization failure",
+    "could not serialize access",
+    "deadlock found",
+    "lock wait timeout",
+];
+
+/// The future a `with_retry` closure returns; it borrows the transaction it runs in.
+pub type TxnFuture<'c, T> = Pin<Box<dyn Future<Output = Result<T, DbErr>> + Send + 'c>>;
+
+/// Runs `f` in a new transaction and commits it, starting over up to `max_retries` times
+/// when the transaction fails because of a concurrent one.
+///
+/// Retries wait a random time of up to 10ms, 20ms, 40ms, ... so colliding requests
+/// spread out. Every other error is returned straight away.
+pub async fn with_retry<F, T>(conn: &DatabaseConnection, max_retries: u32, f: F) -> Result<T, DbErr>
+where
+    F: for<'c> Fn(&'c DatabaseTransaction) -> TxnFuture<'c, T>,
+{
+    let mut attempt = 0;
+    loop {
+        match run_once(conn, &f).await {
+            Err(err) if attempt < max_retries && is_retryable(&err) => {
+                tracing::debug!(attempt, %err, "retrying transaction");
+                tokio::time::sleep(backoff(attempt)).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+async fn run_once<F, T>(conn: &DatabaseConnection, f: &F) -> Result<T, DbErr>
+where
+    F: for<'c> Fn(&'c DatabaseTransaction) -> TxnFuture<'c, T>,
+{
+    // dropping the transaction on an early return rolls it back
+    let txn = conn.begin().await?;
+    let value = f(&txn).await?;
+    txn.commit().await?;
+    Ok(value)
+}
+
+fn is_retryable(err: &DbErr) -> bool {
+    match err {
+        DbErr::Exec(err) | DbErr::Query(err) | DbErr::Conn(err) => {
+            let message = err.to_string().to_lowercase();
+            RETRYABLE_MESSAGES.iter().any(|fragment| message.contains(fragment))
+        }
+        _ => false,
+    }
+}
+
+fn backoff(attempt: u32) -> Duration {
+    let max = BASE_BACKOFF * 2u32.saturating_pow(attempt);
+    rand::thread_rng().gen_range(Duration::ZERO..=max)
+}
@@ -0,0 +1,95 @@
+use std::env;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::web::Data;
+use actix_web::{get, web, Error, HttpResponse};
+
+use entity::post::PostStatus;
+
+use crate::AppState;
+
+pub const ROBOTS_TAG_HEADER: HeaderName = HeaderName::from_static("x-robots-tag");
+pub const NOINDEX: &str = "noindex, nofollow";
+/// For the post list, which changes too often for a cached copy to be useful.
+pub const NOARCHIVE: &str = "noarchive";
+
+/// `X-Robots-Tag` of a post page: only published posts are meant to be found.
+pub fn post_directives(status: PostStatus) -> &'static str {
+    match status {
+        PostStatus::Published => "index, follow",
+        PostStatus::Draft | PostStatus::Archived => NOINDEX,
+    }
+}
+
+/// Keeps crawlers out of the admin pages, debug routes included, by sending
+/// `X-Robots-Tag: noindex, nofollow` on every response under `/admin`.
+pub async fn middleware(req: ServiceRequest,
+                        next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let is_admin = req.path() == "/admin" || req.path().starts_with("/admin/");
+    let mut res = next.call(req).await?;
+    if is_admin {
+        res.headers_mut().insert(ROBOTS_TAG_HEADER, HeaderValue::from_static(NOINDEX));
+    }
+    Ok(res)
+}
+
+/// Builds robots.txt: one `Disallow` per path, or `Disallow: /` alone when everything is
+/// off limits, followed by the sitemap location.
+fn render(disallow_all: bool, paths: &[&str], base_url: &str) -> String {
+    let mut text = String::from("User-agent: *\n");
+    if disallow_all {
+        text.push_str("Disallow: /\n");
+    } else {
+        for path in paths {
+            text.push_str(&format!("Disallow: {}\n", path));
+        }
+    }
+    text.push_str(&format!("\nSitemap: {}/sitemap.xml\n", base_url.trim_end_matches('/')));
+    text
+}
+
+/// Built from `ROBOTS_DISALLOW_PATHS` (comma-separated) and `ROBOTS_DISALLOW_ALL=1` on
+/// every request, so newly added routes can be excluded without a static file.
+#[get("/robots.txt")]
+async fn robots(data: Data<AppState>) -> HttpResponse {
+    let disallow_all = env::var("ROBOTS_DISALLOW_ALL").as_deref() == Ok("1");
+    let paths = env::var("ROBOTS_DISALLOW_PATHS").unwrap_or_default();
+    let paths: Vec<&str> = paths.split(',').map(str::trim).filter(|path| !path.is_empty()).collect();
+    HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .body(render(disallow_all, &paths, &data.base_url))
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(robots);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_disallows_each_path() {
+        let text = render(false, &["/admin", "/preview"], "https://example.com/");
+        assert_eq!(
+            text,
+            "User-agent: *\nDisallow: /admin\nDisallow: /preview\n\nSitemap: https://example.com/sitemap.xml\n",
+        );
+    }
+
+    #[test]
+    fn render_disallows_everything() {
+        let text = render(true, &["/admin"], "https://example.com");
+        assert_eq!(text, "User-agent: *\nDisallow: /\n\nSitemap: https://example.com/sitemap.xml\n");
+    }
+
+    #[test]
+    fn render_allows_everything_without_paths() {
+        let text = render(false, &[], "https://example.com");
+        assert_eq!(text, "User-agent: *\n\nSitemap: https://example.com/sitemap.xml\n");
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::{BTreeMap, HashMap};
+
+use tera::{Tera, Value};
+
+/// Names and URL patterns of the page routes that templates link to. Keep these in step
+/// with the routes `init` registers.
+const ROUTES: &[(&str, &str)] = &[
+    ("list", "/"),
+    ("new", "/new"),
+    ("create", "/"),
+    ("edit", "/{id}"),
+    ("update", "/{id}"),
+    ("delete", "/delete/{id}"),
+    ("qr_code", "/posts/{id}/qr.png"),
+    ("print", "/posts/{id}/print"),
+    ("reader", "/posts/{id}/reader"),
+    ("upload_image", "/posts/{id}/image"),
+];
+
+/// Builds URLs from route names so templates don't spell out paths.
+#[derive(Debug, Clone)]
+pub struct RouteMap(HashMap<&'static str, &'static str>);
+
+impl Default for RouteMap {
+    fn default() -> Self {
+        RouteMap(ROUTES.iter().copied().collect())
+    }
+}
+
+impl RouteMap {
+    /// Returns the URL of route `name`, with `params` filling the `{...}` segments of its
+    /// pattern and any other params appended as the query string.
+    pub fn url_for(&self, name: &str, params: &BTreeMap<String, String>) -> Result<String, String> {
+        let pattern = self.0.get(name).ok_or_else(|| format!("unknown route {}", name))?;
+        let mut url = pattern.to_string();
+        let mut query = Vec::new();
+        for (key, value) in params {
+            let segment = format!("{{{}}}", key);
+            if url.contains(&segment) {
+                url = url.replace(&segment, value);
+            } else {
+                query.push((key, value));
+            }
+        }
+        if url.contains('{') {
+            return Err(format!("missing parameter for route {}: {}", name, pattern));
+        }
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(&serde_urlencoded::to_string(query).map_err(|err| err.to_string())?);
+        }
+        Ok(url)
+    }
+}
+
+/// Registers `url_for(name="edit", id=post.id)` with `tera`; every argument besides
+/// `name` is a route parameter.
+pub fn register(tera: &mut Tera, routes: RouteMap) {
+    tera.register_function("url_for", move |args: &HashMap<String, Value>| {
+        let name = args
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| tera::Error::msg("url_for needs a name argument"))?;
+        let params = args
+            .iter()
+            .filter(|(key, _)| key.as_str() != "name")
+            .map(|(key, value)| {
+                let value = match value {
+                    Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                };
+                (key.clone(), value)
+            })
+            .collect();
+        routes.url_for(name, &params).map(Value::String).map_err(tera::Error::msg)
+    });
+}
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Timelike, Utc};
+use futures_util::future::BoxFuture;
+
+/// Builds the future for one run of a periodic task.
+pub type Task = Box<dyn Fn() -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// When a task runs.
+enum Schedule {
+    /// Every period, starting immediately.
+    Every(Duration),
+    /// At each midnight UTC.
+    Daily,
+}
+
+/// Time from `now` to the next midnight UTC.
+fn until_midnight(now: DateTime<Utc>) -> Duration {
+    Duration::from_secs(24 * 3600 - u64::from(now.num_seconds_from_midnight()))
+}
+
+/// Runs tasks at fixed intervals or daily, each on its own tokio task.
+#[derive(Default)]
+pub struct Scheduler {
+    tasks: Vec<(&'static str, Schedule, Task)>,
+    last_runs: Arc<Mutex<HashMap<&'static str, DateTime<Utc>>>>,
+}
+
+impl Scheduler {
+    /// Registers `task` to run every `period`, starting immediately.
+    pub fn every<F>(&mut self, name: &'static str, period: Duration, task: F) -> &mut Self
+        where F: Fn() -> BoxFuture<'static, ()> + Send + Sync + 'static,
+    {
+        self.tasks.push((name, Schedule::Every(period), Box::new(task)));
+        self
+    }
+
+    /// Registers `task` to run at each midnight UTC, the first time at the next one.
+    pub fn daily<F>(&mut self, name: &'static str, task: F) -> &mut Self
+        where F: Fn() -> BoxFuture<'static, ()> + Send + Sync + 'static,
+    {
+        self.tasks.push((name, Schedule::Daily, Box::new(task)));
+        self
+    }
+
+    /// Spawns the registered tasks. A run that panics is logged and the task keeps its
+    /// schedule.
+    pub fn start(self) {
+        for (name, schedule, task) in self.tasks {
+            let last_runs = self.last_runs.clone();
+            tokio::spawn(async move {
+                let mut interval = match schedule {
+                    Schedule::Every(period) => Some(tokio::time::interval(period)),
+                    Schedule::Daily => None,
+                };
+                loop {
+                    match &mut interval {
+                        Some(interval) => {
+                            interval.tick().await;
+                        }
+                        None => tokio::time::sleep(until_midnight(Utc::now())).await,
+                    }
+                    let last_run = last_runs.lock().unwrap().insert(name, Utc::now());
+                    tracing::info!(task = name, ?last_run, "running scheduled task");
+                    // each run gets its own tokio task so a panic only ends that run
+                    if let Err(err) = tokio::spawn(task()).await {
+                        tracing::error!(task = name, %err, "scheduled task failed");
+                    }
+                }
+            });
+        }
+    }
+}
+
+/// Reads a period in seconds from the env var `name`, falling back to `default`.
+pub fn period_from_env(name: &str, default: Duration) -> Duration {
+    std::env::var(name)
+        .map(|secs| match secs.parse() {
+            Ok(secs) if secs > 0 => Duration::from_secs(secs),
+            _ => panic!("{} must be a positive number of seconds", name),
+        })
+        .unwrap_or(default)
+}
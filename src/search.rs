@@ -0,0 +1,72 @@
+use actix_web::{get, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use sea_orm::{entity::*, query::*, sea_query::Expr, DatabaseConnection, DbBackend, DbErr};
+use serde::Deserialize;
+
+use entity::post::{self, Entity as Post, PostStatus};
+
+use crate::api_error::ApiError;
+use crate::tenants::{tenanted_query, Tenant};
+use crate::{negotiate, AppState};
+
+/// Most posts one search returns.
+const MAX_RESULTS: u64 = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    q: String,
+}
+
+/// Published posts of `tenant_id` whose title or text match `query`, using the full-text
+/// index each backend got from the `add_post_full_text_index` migration; SQLite, which has
+/// none, falls back to `LIKE`. MySQL and PostgreSQL return the best matches first.
+///
+/// Encrypted texts are indexed as ciphertext, so only their titles can match.
+pub async fn full_text_search(query: &str,
+                              tenant_id: &str,
+                              conn: &DatabaseConnection,
+) -> Result<Vec<post::Model>, DbErr> {
+    let select = tenanted_query::<Post>(tenant_id).filter(post::Column::Status.eq(PostStatus::Published));
+    let select = match conn.get_database_backend() {
+        // natural language mode orders by relevance on its own
+        DbBackend::MySql => select.filter(Expr::cust_with_values(
+            "MATCH (title, text) AGAINST (? IN NATURAL LANGUAGE MODE)",
+            [query],
+        )),
+        // `plainto_tsquery` takes plain words, where `to_tsquery` rejects stray operators;
+        // custom expressions use the backend's own placeholders
+        DbBackend::Postgres => select
+            .filter(Expr::cust_with_values("ts_vector @@ plainto_tsquery('english', $1)", [query]))
+            .order_by(
+                Expr::cust_with_values("ts_rank(ts_vector, plainto_tsquery('english', $1))", [query]),
+                Order::Desc,
+            ),
+        DbBackend::Sqlite => select
+            .filter(post::Column::Title.contains(query).or(post::Column::Text.contains(query)))
+            .order_by_asc(post::Column::Id),
+    };
+    select.limit(MAX_RESULTS).all(conn).await
+}
+
+#[get("/api/v1/posts/search")]
+async fn search_posts(req: HttpRequest,
+                      data: Data<AppState>,
+                      tenant: Tenant,
+                      params: web::Query<SearchParams>,
+) -> Result<HttpResponse, Error> {
+    let query = params.q.trim();
+    if query.is_empty() {
+        return Err(ApiError::validation("q must not be empty").into());
+    }
+    let mut posts = full_text_search(query, &tenant.id, &data.conn)
+        .await
+        .map_err(|_| ApiError::database("could not search posts"))?;
+    for post in &mut posts {
+        data.reveal(post)?;
+    }
+    negotiate::respond(&req, HttpResponse::Ok(), &posts)
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(search_posts);
+}
@@ -0,0 +1,109 @@
+use std::env;
+
+use argon2::password_hash::{rand_core::OsRng, SaltString};
+use argon2::{Argon2, PasswordHasher};
+use sea_orm::{entity::*, query::*, DatabaseConnection, DbErr};
+
+use entity::post::{self, Entity as Post, PostStatus};
+use entity::post_permission::Permission;
+use entity::user::{self, Entity as User};
+
+use crate::permissions;
+
+const DEFAULT_SEED_POSTS: usize = 12;
+/// Login for the seeded users; development databases only.
+const SEED_PASSWORD: &str = "password";
+/// Username and admin flag of each seeded user.
+const SEED_USERS: &[(&str, bool)] = &[("admin", true), ("alice", false), ("bob", false)];
+
+/// How `run_seeds` treats data that is already there, from `SEED_STRATEGY`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Strategy {
+    /// Only insert the seed rows that are missing.
+    Idempotent,
+    /// Delete every post and user first.
+    Truncate,
+}
+
+impl Strategy {
+    fn from_env() -> Self {
+        match env::var("SEED_STRATEGY").as_deref() {
+            Err(_) | Ok("idempotent") => Strategy::Idempotent,
+            Ok("truncate") => Strategy::Truncate,
+            Ok(other) => panic!("SEED_STRATEGY must be idempotent or truncate, not {}", other),
+        }
+    }
+}
+
+/// Fills the database with sample users and `SEED_POSTS` sample posts in one transaction.
+///
+/// The first user is an admin and owns every seeded post. All users log in with the
+/// password `password`. There are no tags or comments in this app to seed.
+pub async fn run_seeds(conn: &DatabaseConnection) -> Result<(), DbErr> {
+    let strategy = Strategy::from_env();
+    let post_count = env::var("SEED_POSTS")
+        .map(|count| count.parse().expect("SEED_POSTS must be a number"))
+        .unwrap_or(DEFAULT_SEED_POSTS);
+
+    let txn = conn.begin().await?;
+    if strategy == Strategy::Truncate {
+        // the other tables reference these with ON DELETE CASCADE, so they empty too
+        Post::delete_many().exec(&txn).await?;
+        User::delete_many().exec(&txn).await?;
+    }
+
+    let mut owner = None;
+    for &(username, is_admin) in SEED_USERS {
+        let existing = User::find()
+            .filter(user::Column::Username.eq(username))
+            .one(&txn)
+            .await?;
+        let user = match existing {
+            Some(user) => user,
+            None => {
+                user::ActiveModel {
+                    username: Set(username.to_owned()),
+                    password_hash: Set(Some(hash_password(SEED_PASSWORD)?)),
+                    is_admin: Set(is_admin),
+                    totp_enabled: Set(false),
+                    ..Default::default()
+                }
+                    .insert(&txn)
+                    .await?
+            }
+        };
+        owner.get_or_insert(user.id);
+    }
+    let owner = owner.expect("SEED_USERS is empty");
+
+    for number in 1..=post_count {
+        let title = format!("Sample post {}", number);
+        let exists = Post::find()
+            .filter(post::Column::Title.eq(title.as_str()))
+            .count(&txn)
+            .await?
+            > 0;
+        if exists {
+            continue;
+        }
+        let text = format!("This is sample post {}. Read more at https://www.sea-ql.org/SeaORM/", number);
+        let post = post::ActiveModel {
+            title: Set(title),
+            text: Set(text),
+            status: Set(PostStatus::Published),
+            ..Default::default()
+        }
+            .insert(&txn)
+            .await?;
+        permissions::grant(&txn, owner, post.id, Permission::Admin).await?;
+    }
+    txn.commit().await
+}
+
+fn hash_password(password: &str) -> Result<String, DbErr> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|err| DbErr::Custom(format!("could not hash password: {}", err)))
+}
@@ -0,0 +1,100 @@
+use std::collections::{BTreeMap, HashMap};
+
+use actix_web::web::Data;
+use actix_web::{get, patch, web, Error, HttpRequest, HttpResponse};
+use sea_orm::{entity::*, sea_query::OnConflict, DatabaseConnection, DbErr, TransactionTrait};
+
+use entity::site_setting;
+use entity::site_setting::Entity as SiteSetting;
+
+use crate::api_error::ApiError;
+use crate::auth::AdminUser;
+use crate::negotiate::{self, Body};
+use crate::AppState;
+
+/// Default page size of post lists, for requests that don't give one.
+pub const POSTS_PER_PAGE: &str = "posts_per_page";
+
+/// Copy of the `site_settings` table, loaded at startup and kept current by
+/// `PATCH /admin/settings`.
+#[derive(Debug, Clone, Default)]
+pub struct SiteSettings(HashMap<String, String>);
+
+impl SiteSettings {
+    pub async fn load(conn: &DatabaseConnection) -> Result<Self, DbErr> {
+        let settings = SiteSetting::find().all(conn).await?;
+        Ok(SiteSettings(settings.into_iter().map(|setting| (setting.key, setting.value)).collect()))
+    }
+
+    pub fn get_setting(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    fn sorted(&self) -> BTreeMap<&str, &str> {
+        self.0.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect()
+    }
+}
+
+/// Checks the values of settings the server reads, so a typo cannot break lists.
+fn validate(data: &AppState, key: &str, value: &str) -> Result<(), Error> {
+    if key == POSTS_PER_PAGE {
+        let limits = &data.page_size_limits;
+        match value.parse::<usize>() {
+            Ok(size) if (limits.min..=limits.max).contains(&size) => {}
+            _ => {
+                let message = format!("{} must be between {} and {}", key, limits.min, limits.max);
+                return Err(ApiError::validation(message).into());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[get("/admin/settings")]
+async fn get_settings(req: HttpRequest,
+                      data: Data<AppState>,
+                      _admin: AdminUser,
+) -> Result<HttpResponse, Error> {
+    let settings = data.site_settings.read().unwrap();
+    negotiate::respond(&req, HttpResponse::Ok(), &settings.sorted())
+}
+
+/// Sets the given settings, leaving the others as they are.
+#[patch("/admin/settings")]
+async fn update_settings(req: HttpRequest,
+                         data: Data<AppState>,
+                         _admin: AdminUser,
+                         body: Body<HashMap<String, String>>,
+) -> Result<HttpResponse, Error> {
+    let changes = body.into_inner();
+    for (key, value) in &changes {
+        validate(&data, key, value)?;
+    }
+    let txn = data
+        .conn
+        .begin()
+        .await
+        .map_err(|_| ApiError::database("could not start transaction"))?;
+    for (key, value) in &changes {
+        let setting = site_setting::ActiveModel { key: Set(key.clone()), value: Set(value.clone()) };
+        SiteSetting::insert(setting)
+            .on_conflict(
+                OnConflict::column(site_setting::Column::Key)
+                    .update_column(site_setting::Column::Value)
+                    .to_owned(),
+            )
+            .exec_without_returning(&txn)
+            .await
+            .map_err(|_| ApiError::database("could not save setting"))?;
+    }
+    txn.commit().await.map_err(|_| ApiError::database("could not commit settings"))?;
+
+    let mut settings = data.site_settings.write().unwrap();
+    settings.0.extend(changes);
+    negotiate::respond(&req, HttpResponse::Ok(), &settings.sorted())
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_settings);
+    cfg.service(update_settings);
+}
@@ -0,0 +1,119 @@
+use actix_web::{get, post, web, Error, HttpRequest, HttpResponse};
+use actix_web::http::header;
+use actix_web::web::Data;
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{entity::*, query::*, sea_query::Expr};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use entity::post::{self, Entity as Post};
+
+use crate::api_error::ApiError;
+use crate::auth::AdminUser;
+use crate::tenants::{tenanted_query, Tenant};
+use crate::{blocks, negotiate, robots, AppState, FontSize};
+
+const DEFAULT_EXPIRY_DAYS: i64 = 7;
+const MAX_EXPIRY_DAYS: i64 = 90;
+
+#[derive(Debug, Deserialize)]
+pub struct ShareLinkParams {
+    /// How long the link works, 7 days by default.
+    expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct ShareLink {
+    url: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// Creates a link anyone can preview the post at without signing in, replacing the
+/// post's previous link if it had one.
+#[post("/admin/posts/{id}/share-link")]
+async fn create_share_link(req: HttpRequest,
+                           data: Data<AppState>,
+                           tenant: Tenant,
+                           _admin: AdminUser,
+                           id: web::Path<u64>,
+                           params: web::Query<ShareLinkParams>,
+) -> Result<HttpResponse, Error> {
+    let days = params.expires_in_days.unwrap_or(DEFAULT_EXPIRY_DAYS);
+    if !(1..=MAX_EXPIRY_DAYS).contains(&days) {
+        let message = format!("expires_in_days must be between 1 and {}", MAX_EXPIRY_DAYS);
+        return Err(ApiError::validation(message).into());
+    }
+    let token = Uuid::new_v4().simple().to_string();
+    let expires_at = Utc::now() + Duration::days(days);
+    update_share_token(&data, &tenant.id, id.into_inner(), Some((&token, expires_at))).await?;
+    let url = format!("{}/preview/{}", data.base_url.trim_end_matches('/'), token);
+    negotiate::respond(&req, HttpResponse::Created(), &ShareLink { url, expires_at })
+}
+
+/// Stops the post's share link from working.
+#[post("/admin/posts/{id}/revoke-share-link")]
+async fn revoke_share_link(data: Data<AppState>,
+                           tenant: Tenant,
+                           _admin: AdminUser,
+                           id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
+    update_share_token(&data, &tenant.id, id.into_inner(), None).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+async fn update_share_token(data: &AppState,
+                            tenant_id: &str,
+                            id: u64,
+                            token: Option<(&str, DateTime<Utc>)>,
+) -> Result<(), Error> {
+    let result = Post::update_many()
+        .col_expr(post::Column::ShareToken, Expr::value(token.map(|(token, _)| token.to_owned())))
+        .col_expr(post::Column::ShareTokenExpiresAt, Expr::value(token.map(|(_, at)| at)))
+        .filter(post::Column::TenantId.eq(tenant_id))
+        .filter(post::Column::Id.eq(id))
+        .exec(&data.conn)
+        .await
+        .map_err(|_| ApiError::database("could not update share link"))?;
+    if result.rows_affected == 0 {
+        return Err(ApiError::post_not_found().into());
+    }
+    Ok(())
+}
+
+/// The post a share link points to, drafts included, in the reader layout. Unknown and
+/// revoked tokens get 404, expired ones 410.
+#[get("/preview/{token}")]
+async fn preview(data: Data<AppState>,
+                 tenant: Tenant,
+                 token: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let mut post = tenanted_query::<Post>(&tenant.id)
+        .filter(post::Column::ShareToken.eq(token.into_inner()))
+        .one(&data.conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve post"))?
+        .ok_or_else(|| ApiError::not_found("share link not found"))?;
+    if post.share_token_expires_at.is_none_or(|at| at <= Utc::now()) {
+        return Err(ApiError::gone("share link has expired").into());
+    }
+    data.reveal(&mut post)?;
+    let mut ctx = tera::Context::new();
+    ctx.insert("post", &post);
+    ctx.insert("blocks_html", &blocks::blocks_html(&post));
+    ctx.insert("font_size", &FontSize::default());
+    ctx.insert("preview", &true);
+    let body = data.render("reader.html.tera", ctx).await?;
+    Ok(HttpResponse::Ok()
+        .insert_header((robots::ROBOTS_TAG_HEADER, robots::NOINDEX))
+        // the token is in the URL, so keep it out of caches and other sites' logs
+        .insert_header((header::CACHE_CONTROL, "no-store"))
+        .insert_header((header::REFERRER_POLICY, "no-referrer"))
+        .content_type("text/html")
+        .body(body))
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(create_share_link);
+    cfg.service(revoke_share_link);
+    cfg.service(preview);
+}
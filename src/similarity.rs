@@ -0,0 +1,211 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use actix_web::{get, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use sea_orm::{entity::*, query::*, DatabaseConnection, DbErr};
+use serde::Serialize;
+
+use entity::post::{self, Entity as Post, PostStatus};
+
+use crate::api_error::ApiError;
+use crate::tenants::{tenanted_query, Tenant};
+use crate::{analytics, filters, negotiate, AppState};
+
+/// Most similar posts returned for one post.
+const MAX_SIMILAR: usize = 5;
+
+/// A term's weight in each document that has it.
+type Vector<'a> = HashMap<&'a str, f64>;
+/// Ids of similar posts with their scores, best first.
+type Scores = Arc<Vec<(u64, f64)>>;
+
+#[derive(Debug, Serialize)]
+struct SimilarPost {
+    id: u64,
+    title: String,
+    score: f64,
+}
+
+/// The similar posts of each post asked about so far. Any post change moves the document
+/// frequencies every score depends on, so `invalidate` drops them all.
+#[derive(Debug, Clone, Default)]
+pub struct SimilarityCache {
+    similar: Arc<Mutex<HashMap<u64, Scores>>>,
+}
+
+impl SimilarityCache {
+    pub fn invalidate(&self) {
+        self.similar.lock().unwrap().clear();
+    }
+
+    async fn get(&self,
+                 base_post_id: u64,
+                 stop_words: &HashSet<String>,
+                 conn: &DatabaseConnection,
+    ) -> Result<Scores, DbErr> {
+        if let Some(similar) = self.similar.lock().unwrap().get(&base_post_id) {
+            return Ok(similar.clone());
+        }
+        let similar = Arc::new(compute_tfidf_similarity(base_post_id, stop_words, conn).await?);
+        self.similar.lock().unwrap().insert(base_post_id, similar.clone());
+        Ok(similar)
+    }
+}
+
+/// The TF-IDF vector of each document. IDF is smoothed as `ln((1 + n) / (1 + df)) + 1`,
+/// so a term every document has still counts.
+fn tfidf_vectors(documents: &[Vec<String>]) -> Vec<Vector<'_>> {
+    let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+    for terms in documents {
+        let unique: HashSet<&str> = terms.iter().map(String::as_str).collect();
+        for term in unique {
+            *document_frequency.entry(term).or_default() += 1;
+        }
+    }
+    let n = documents.len() as f64;
+    documents
+        .iter()
+        .map(|terms| {
+            let mut counts: HashMap<&str, f64> = HashMap::new();
+            for term in terms {
+                *counts.entry(term.as_str()).or_default() += 1.0;
+            }
+            let len = terms.len() as f64;
+            counts
+                .into_iter()
+                .map(|(term, count)| {
+                    let idf = ((1.0 + n) / (1.0 + document_frequency[term] as f64)).ln() + 1.0;
+                    (term, count / len * idf)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// 1 for vectors pointing the same way, 0 for vectors sharing no term or for an empty one.
+fn cosine_similarity(a: &Vector, b: &Vector) -> f64 {
+    let dot: f64 = a.iter().filter_map(|(term, weight)| b.get(term).map(|other| weight * other)).sum();
+    let norm = |vector: &Vector| vector.values().map(|weight| weight * weight).sum::<f64>().sqrt();
+    let denominator = norm(a) * norm(b);
+    match denominator == 0.0 {
+        true => 0.0,
+        false => dot / denominator,
+    }
+}
+
+/// The published posts most similar to `base_post_id`, best first with their cosine
+/// similarity, compared by the TF-IDF vectors of their texts over the posts of the same
+/// tenant. Encrypted posts are left out, since their stored text is ciphertext.
+pub async fn compute_tfidf_similarity(base_post_id: u64,
+                                      stop_words: &HashSet<String>,
+                                      conn: &DatabaseConnection,
+) -> Result<Vec<(u64, f64)>, DbErr> {
+    let Some(base) = Post::find_by_id(base_post_id).one(conn).await? else {
+        return Ok(Vec::new());
+    };
+    if base.is_encrypted {
+        return Ok(Vec::new());
+    }
+    let others: Vec<(u64, String)> = tenanted_query::<Post>(&base.tenant_id)
+        .filter(post::Column::Id.ne(base.id))
+        .filter(post::Column::Status.eq(PostStatus::Published))
+        .filter(post::Column::IsEncrypted.eq(false))
+        .select_only()
+        .column(post::Column::Id)
+        .column(post::Column::Text)
+        .into_tuple()
+        .all(conn)
+        .await?;
+
+    let documents: Vec<Vec<String>> = std::iter::once(base.text.as_str())
+        .chain(others.iter().map(|(_, text)| text.as_str()))
+        .map(|text| analytics::tokenize(&filters::html_to_text(text), stop_words).collect())
+        .collect();
+    let vectors = tfidf_vectors(&documents);
+    let mut similar: Vec<(u64, f64)> = others
+        .iter()
+        .zip(&vectors[1..])
+        .map(|((id, _), vector)| (*id, cosine_similarity(&vectors[0], vector)))
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+    similar.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    similar.truncate(MAX_SIMILAR);
+    Ok(similar)
+}
+
+#[get("/api/v1/posts/{id}/similar")]
+async fn similar_posts(req: HttpRequest,
+                       data: Data<AppState>,
+                       tenant: Tenant,
+                       id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
+    let conn = &data.conn;
+    let post_id = id.into_inner();
+    tenanted_query::<Post>(&tenant.id)
+        .filter(post::Column::Id.eq(post_id))
+        .one(conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+    let similar = data
+        .similar_posts
+        .get(post_id, data.word_frequency.stop_words(), conn)
+        .await
+        .map_err(|_| ApiError::database("could not compare posts"))?;
+    let mut titles: HashMap<u64, String> = tenanted_query::<Post>(&tenant.id)
+        .filter(post::Column::Id.is_in(similar.iter().map(|(id, _)| *id)))
+        .all(conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve similar posts"))?
+        .into_iter()
+        .map(|post| (post.id, post.title))
+        .collect();
+    // a post gone since the scores were cached is skipped
+    let similar: Vec<SimilarPost> = similar
+        .iter()
+        .filter_map(|(id, score)| Some(SimilarPost { id: *id, title: titles.remove(id)?, score: *score }))
+        .collect();
+    negotiate::respond(&req, HttpResponse::Ok(), &similar)
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(similar_posts);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn documents(texts: &[&str]) -> Vec<Vec<String>> {
+        texts.iter().map(|text| text.split(' ').map(str::to_owned).collect()).collect()
+    }
+
+    #[test]
+    fn tfidf_vectors_weigh_rare_terms_higher() {
+        let documents = documents(&["rust orm", "rust web", "rust web"]);
+        let vectors = tfidf_vectors(&documents);
+        assert!(vectors[0]["orm"] > vectors[0]["rust"]);
+        // a term every document has keeps an idf of 1
+        assert!((vectors[0]["rust"] - 0.5).abs() < 1e-9);
+        let orm = ((1.0 + 3.0) / (1.0 + 1.0_f64)).ln() + 1.0;
+        assert!((vectors[0]["orm"] - 0.5 * orm).abs() < 1e-9);
+    }
+
+    #[test]
+    fn tfidf_vectors_weigh_repeated_terms_higher() {
+        let documents = documents(&["rust rust orm", "web"]);
+        let vectors = tfidf_vectors(&documents);
+        assert!((vectors[0]["rust"] - 2.0 * vectors[0]["orm"]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cosine_similarity_ranks_shared_terms() {
+        let documents = documents(&["rust orm database", "rust orm web", "cooking recipes"]);
+        let vectors = tfidf_vectors(&documents);
+        assert!((cosine_similarity(&vectors[0], &vectors[0]) - 1.0).abs() < 1e-9);
+        assert!(cosine_similarity(&vectors[0], &vectors[1]) > 0.0);
+        assert_eq!(cosine_similarity(&vectors[0], &vectors[2]), 0.0);
+        assert_eq!(cosine_similarity(&vectors[0], &Vector::new()), 0.0);
+    }
+}
@@ -0,0 +1,161 @@
+use std::fmt::{self, Debug, Display};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::{env, fs, io};
+
+use actix_web::web::{self, Bytes};
+use async_trait::async_trait;
+use aws_sdk_s3::primitives::ByteStream;
+
+const LOCAL_UPLOADS_URL: &str = "/uploads";
+
+#[derive(Debug)]
+pub enum StorageError {
+    Io(io::Error),
+    S3(String),
+}
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Io(e) => write!(f, "storage io error: {}", e),
+            StorageError::S3(e) => write!(f, "s3 error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<io::Error> for StorageError {
+    fn from(e: io::Error) -> Self {
+        StorageError::Io(e)
+    }
+}
+
+/// A place to put uploaded files.
+///
+/// Callers persist object keys, not URLs, and resolve them with `url` when
+/// rendering, so switching backends does not invalidate stored rows.
+#[async_trait]
+pub trait ObjectStorage: Debug + Send + Sync {
+    /// Public URL of the object stored under `key`.
+    fn url(&self, key: &str) -> String;
+    /// Stores `data` under `key` and returns its public URL.
+    async fn put_object(&self, key: &str, data: Bytes, content_type: &str) -> Result<String, StorageError>;
+    async fn delete_object(&self, key: &str) -> Result<(), StorageError>;
+}
+
+/// Stores objects below a directory that is served under `/uploads`.
+#[derive(Debug, Clone)]
+pub struct LocalObjectStorage {
+    root: PathBuf,
+}
+
+impl LocalObjectStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        LocalObjectStorage { root: root.into() }
+    }
+}
+
+#[async_trait]
+impl ObjectStorage for LocalObjectStorage {
+    fn url(&self, key: &str) -> String {
+        format!("{}/{}", LOCAL_UPLOADS_URL, key)
+    }
+
+    async fn put_object(&self, key: &str, data: Bytes, _content_type: &str) -> Result<String, StorageError> {
+        let path = self.root.join(key);
+        web::block(move || {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(path, &data)
+        })
+            .await
+            .map_err(|e| io::Error::other(e.to_string()))??;
+        Ok(self.url(key))
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), StorageError> {
+        let path = self.root.join(key);
+        web::block(move || fs::remove_file(path))
+            .await
+            .map_err(|e| io::Error::other(e.to_string()))??;
+        Ok(())
+    }
+}
+
+/// Stores objects in an S3 bucket; `S3_ENDPOINT` points it at MinIO or other compatible servers.
+#[derive(Debug, Clone)]
+pub struct S3ObjectStorage {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+    public_url: String,
+}
+
+impl S3ObjectStorage {
+    pub async fn from_env() -> Self {
+        let bucket = env::var("S3_BUCKET").expect("S3_BUCKET is not set in .env file");
+        let endpoint = env::var("S3_ENDPOINT").ok();
+        let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        let mut builder = aws_sdk_s3::config::Builder::from(&config);
+        if let Some(endpoint) = &endpoint {
+            builder = builder.endpoint_url(endpoint).force_path_style(true);
+        }
+        let public_url = match env::var("S3_PUBLIC_URL") {
+            Ok(url) => url,
+            Err(_) => match &endpoint {
+                Some(endpoint) => format!("{}/{}", endpoint.trim_end_matches('/'), bucket),
+                None => {
+                    let region = config.region().map(|r| r.to_string()).unwrap_or_else(|| "us-east-1".into());
+                    format!("https://{}.s3.{}.amazonaws.com", bucket, region)
+                }
+            },
+        };
+        S3ObjectStorage {
+            client: aws_sdk_s3::Client::from_conf(builder.build()),
+            bucket,
+            public_url: public_url.trim_end_matches('/').to_owned(),
+        }
+    }
+}
+
+#[async_trait]
+impl ObjectStorage for S3ObjectStorage {
+    fn url(&self, key: &str) -> String {
+        format!("{}/{}", self.public_url, key)
+    }
+
+    async fn put_object(&self, key: &str, data: Bytes, content_type: &str) -> Result<String, StorageError> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(content_type)
+            .body(ByteStream::from(data))
+            .send()
+            .await
+            .map_err(|e| StorageError::S3(e.to_string()))?;
+        Ok(self.url(key))
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), StorageError> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| StorageError::S3(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Picks the storage backend from `STORAGE_BACKEND` (`local` by default).
+pub async fn storage_from_env(upload_dir: &str) -> Arc<dyn ObjectStorage> {
+    match env::var("STORAGE_BACKEND").as_deref() {
+        Ok("s3") => Arc::new(S3ObjectStorage::from_env().await),
+        Ok("local") | Err(_) => Arc::new(LocalObjectStorage::new(upload_dir)),
+        Ok(other) => panic!("unknown STORAGE_BACKEND {}, expected s3 or local", other),
+    }
+}
@@ -0,0 +1,116 @@
+use sea_orm::{entity::*, query::*, DatabaseConnection, DbErr};
+use serde::Serialize;
+
+use entity::post::{self, Entity as Post, PostStatus};
+
+use crate::tenants::tenanted_query;
+
+/// Largest edit distance between a path and a slug that is still suggested.
+const MAX_DISTANCE: usize = 3;
+
+/// A post offered on the 404 page, linked by its id.
+#[derive(Debug, Serialize)]
+pub struct Suggestion {
+    title: String,
+    url: String,
+}
+
+/// `title` in lowercase with every run of other characters than letters and digits
+/// turned into one `-`, e.g. `my-post` for "My post!".
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+    for c in title.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let len = slug.trim_end_matches('-').len();
+    slug.truncate(len);
+    slug
+}
+
+/// The slug `path` looks like: a single segment of lowercase letters, digits and `-`,
+/// at least one of them a letter, so `/42` and `/static/style.css` are not slugs.
+fn slug_of(path: &str) -> Option<&str> {
+    let slug = path.strip_prefix('/')?.trim_end_matches('/');
+    let is_slug = !slug.is_empty()
+        && slug.chars().all(|c| c.is_lowercase() || c.is_ascii_digit() || c == '-')
+        && slug.chars().any(char::is_alphabetic);
+    is_slug.then_some(slug)
+}
+
+/// The published post whose slugified title is closest to `slug`, if it is at most
+/// `MAX_DISTANCE` edits away. Ties go to the older post.
+fn closest<'a>(slug: &str, posts: &'a [(u64, String)]) -> Option<&'a (u64, String)> {
+    posts
+        .iter()
+        .map(|post| (edit_distance::edit_distance(slug, slugify(&post.1)), post))
+        .filter(|(distance, _)| *distance <= MAX_DISTANCE)
+        .min_by_key(|(distance, post)| (*distance, post.0))
+        .map(|(_, post)| post)
+}
+
+/// A post to offer on the 404 page of `path`. Posts have no slugs, so a path that looks
+/// like one is compared with the slugs their titles would have.
+pub async fn suggest_post(conn: &DatabaseConnection,
+                          tenant_id: &str,
+                          path: &str,
+) -> Result<Option<Suggestion>, DbErr> {
+    let Some(slug) = slug_of(path) else {
+        return Ok(None);
+    };
+    let posts: Vec<(u64, String)> = tenanted_query::<Post>(tenant_id)
+        .filter(post::Column::Status.eq(PostStatus::Published))
+        .select_only()
+        .column(post::Column::Id)
+        .column(post::Column::Title)
+        .into_tuple()
+        .all(conn)
+        .await?;
+    Ok(closest(slug, &posts).map(|(id, title)| Suggestion { title: title.clone(), url: format!("/{}", id) }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn posts() -> Vec<(u64, String)> {
+        vec![(1, "Hello World".to_owned()), (2, "Getting started".to_owned()), (3, "Hello Worlds".to_owned())]
+    }
+
+    #[test]
+    fn slugify_joins_words_with_dashes() {
+        assert_eq!(slugify("My post!"), "my-post");
+        assert_eq!(slugify("  Rust & SeaORM -- 2023 "), "rust-seaorm-2023");
+        assert_eq!(slugify("Ünïcode Títle"), "ünïcode-títle");
+    }
+
+    #[test]
+    fn slug_of_accepts_single_segments_only() {
+        assert_eq!(slug_of("/hello-world/"), Some("hello-world"));
+        assert_eq!(slug_of("/42"), None);
+        assert_eq!(slug_of("/static/style.css"), None);
+        assert_eq!(slug_of("/Hello"), None);
+    }
+
+    #[test]
+    fn closest_finds_the_nearest_title() {
+        let posts = posts();
+        assert_eq!(closest("getting-startd", &posts).map(|post| post.0), Some(2));
+        assert_eq!(closest("hello-world", &posts).map(|post| post.0), Some(1));
+    }
+
+    #[test]
+    fn closest_breaks_ties_by_age() {
+        let posts = posts();
+        // one edit from both "hello-world" and "hello-worlds"
+        assert_eq!(closest("hello-worldx", &posts).map(|post| post.0), Some(1));
+    }
+
+    #[test]
+    fn closest_ignores_distant_titles() {
+        assert_eq!(closest("something-else", &posts()), None);
+    }
+}
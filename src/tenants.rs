@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+use std::future::{ready, Ready};
+use std::sync::{Arc, RwLock};
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderName};
+use actix_web::middleware::Next;
+use actix_web::web::Data;
+use actix_web::{Error, FromRequest, HttpMessage, HttpRequest};
+use sea_orm::{entity::*, query::*, DatabaseConnection, DbErr};
+
+use entity::tenant::{Entity as TenantEntity, TenantScoped};
+
+use crate::api_error::ApiError;
+use crate::AppState;
+
+/// Tenant of requests that name none, which owns the posts from before tenants existed.
+pub const DEFAULT_TENANT: &str = "default";
+const TENANT_HEADER: HeaderName = HeaderName::from_static("x-tenant-id");
+
+/// The tenant a request is for, put in its extensions by `middleware`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tenant {
+    pub id: String,
+}
+
+impl FromRequest for Tenant {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<Tenant>()
+                .cloned()
+                .ok_or_else(|| ApiError::internal("tenant middleware is not installed").into()),
+        )
+    }
+}
+
+/// Tenants known to exist, and the host whose subdomains name tenants.
+#[derive(Debug, Clone)]
+pub struct TenantRegistry {
+    known: Arc<RwLock<HashSet<String>>>,
+    base_host: String,
+}
+
+/// The host of `url`, without scheme, port or path.
+fn host_of(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split(['/', ':']).next().unwrap_or_default()
+}
+
+impl TenantRegistry {
+    pub fn new(base_url: &str) -> Self {
+        TenantRegistry { known: Arc::default(), base_host: host_of(base_url).to_owned() }
+    }
+
+    /// The tenant `req` asks for: the `X-Tenant-ID` header, else the subdomain of the base
+    /// host it was sent to, else the default tenant.
+    fn requested(&self, req: &ServiceRequest) -> String {
+        if let Some(id) = req.headers().get(TENANT_HEADER).and_then(|value| value.to_str().ok()) {
+            return id.to_owned();
+        }
+        let host = req
+            .headers()
+            .get(header::HOST)
+            .and_then(|value| value.to_str().ok())
+            .map(host_of)
+            .unwrap_or_default();
+        match host.strip_suffix(&self.base_host).and_then(|prefix| prefix.strip_suffix('.')) {
+            Some(subdomain) if !subdomain.is_empty() && !subdomain.contains('.') => subdomain.to_owned(),
+            _ => DEFAULT_TENANT.to_owned(),
+        }
+    }
+
+    /// Whether the `tenants` table has `id`; tenants, once seen, are remembered.
+    async fn exists(&self, conn: &DatabaseConnection, id: &str) -> Result<bool, DbErr> {
+        if self.known.read().unwrap().contains(id) {
+            return Ok(true);
+        }
+        let found = TenantEntity::find_by_id(id.to_owned()).one(conn).await?.is_some();
+        if found {
+            self.known.write().unwrap().insert(id.to_owned());
+        }
+        Ok(found)
+    }
+}
+
+/// Resolves the tenant of each request and rejects tenants that don't exist with 404.
+pub async fn middleware(req: ServiceRequest,
+                        next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let data = req
+        .app_data::<Data<AppState>>()
+        .cloned()
+        .ok_or_else(|| ApiError::internal("app state is missing"))?;
+    let id = data.tenants.requested(&req);
+    let exists = data
+        .tenants
+        .exists(&data.conn, &id)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve tenant"))?;
+    if !exists {
+        return Err(ApiError::not_found(format!("unknown tenant {}", id)).into());
+    }
+    req.extensions_mut().insert(Tenant { id });
+    next.call(req).await
+}
+
+/// `E::find()` limited to the rows of `tenant_id`. Queries made for a request go through
+/// this, so one tenant never sees another's rows.
+pub fn tenanted_query<E: TenantScoped>(tenant_id: &str) -> Select<E> {
+    E::find().filter(E::tenant_column().eq(tenant_id))
+}
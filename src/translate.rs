@@ -0,0 +1,208 @@
+use std::env;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use actix_web::{post, web, Error, HttpRequest, HttpResponse};
+use actix_web::web::Data;
+use regex::Regex;
+use sea_orm::{entity::*, query::*};
+use serde::{Deserialize, Serialize};
+
+use entity::post::{self, Entity as Post, PostStatus};
+use entity::post_permission::Permission;
+
+use crate::api::{MAX_TEXT_LEN, MAX_TITLE_LEN};
+use crate::api_error::ApiError;
+use crate::auth::AuthUser;
+use crate::negotiate;
+use crate::tenants::{tenanted_query, Tenant};
+use crate::{audit, permissions, AppState};
+
+const TRANSLATE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Settings of `POST /api/v1/posts/{id}/translate`, which is disabled unless they are set.
+#[derive(Debug, Clone)]
+pub struct TranslatorConfig {
+    /// The `/translate` endpoint of a LibreTranslate compatible API.
+    url: String,
+    api_key: Option<String>,
+}
+
+/// Reads `TRANSLATE_API_URL` and `TRANSLATE_API_KEY`.
+///
+/// Returns `None` when no URL is set, which leaves translation disabled.
+pub fn config_from_env() -> Option<TranslatorConfig> {
+    let url = env::var("TRANSLATE_API_URL").ok()?;
+    Some(TranslatorConfig { url, api_key: env::var("TRANSLATE_API_KEY").ok() })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TranslateParams {
+    /// BCP 47 tag of the language to translate to, e.g. `es` or `pt-BR`.
+    target_lang: String,
+    /// `1` also saves the translation as a new draft.
+    save: Option<u8>,
+}
+
+#[derive(Debug, Serialize)]
+struct TranslateRequest<'a> {
+    q: [&'a str; 2],
+    source: &'static str,
+    target: &'a str,
+    format: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_key: Option<&'a str>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DetectedLanguage {
+    language: String,
+}
+
+/// Detections come one per text when several are sent, but some servers send one.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Detected {
+    One(DetectedLanguage),
+    Each(Vec<DetectedLanguage>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TranslateResponse {
+    translated_text: Vec<String>,
+    detected_language: Option<Detected>,
+}
+
+#[derive(Debug, Serialize)]
+struct Translation {
+    translated_title: String,
+    translated_text: String,
+    /// As detected by the API; `auto` when it didn't say.
+    source_lang: String,
+    target_lang: String,
+    /// The new draft, with `save=1`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    post_id: Option<u64>,
+}
+
+fn language_tag_pattern() -> &'static Regex {
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    // the language and any subtags, without checking them against the registry
+    TAG.get_or_init(|| Regex::new(r"^[A-Za-z]{2,3}(-[A-Za-z0-9]{1,8})*$").expect("the tag pattern is valid"))
+}
+
+/// Sends the title and the text of a post to the translation API, with the language
+/// detected.
+async fn translate(config: &TranslatorConfig, title: &str, text: &str, target: &str) -> Result<Translation, Error> {
+    let request = TranslateRequest {
+        q: [title, text],
+        source: "auto",
+        target,
+        format: "text",
+        api_key: config.api_key.as_deref(),
+    };
+    let client = reqwest::Client::builder()
+        .timeout(TRANSLATE_TIMEOUT)
+        .build()
+        .map_err(|_| ApiError::internal("could not create http client"))?;
+    let response: TranslateResponse = client
+        .post(&config.url)
+        .json(&request)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|_| ApiError::upstream("translation failed"))?
+        .json()
+        .await
+        .map_err(|_| ApiError::upstream("could not read translation"))?;
+    let source_lang = match response.detected_language {
+        Some(Detected::One(detected)) => Some(detected.language),
+        Some(Detected::Each(detected)) => detected.into_iter().last().map(|detected| detected.language),
+        None => None,
+    };
+    let mut translated = response.translated_text.into_iter();
+    let (Some(translated_title), Some(translated_text)) = (translated.next(), translated.next()) else {
+        return Err(ApiError::upstream("translation is missing texts").into());
+    };
+    Ok(Translation {
+        translated_title,
+        translated_text,
+        source_lang: source_lang.unwrap_or_else(|| "auto".to_owned()),
+        target_lang: target.to_owned(),
+        post_id: None,
+    })
+}
+
+/// Translates a post with the configured machine translation API. Nothing is saved unless
+/// `save=1`, which adds the translation as a new draft of the user.
+///
+/// Encrypted posts are refused, since their plaintext would leave the server.
+#[post("/api/v1/posts/{id}/translate")]
+async fn translate_post(req: HttpRequest,
+                        data: Data<AppState>,
+                        tenant: Tenant,
+                        user: AuthUser,
+                        id: web::Path<u64>,
+                        params: web::Query<TranslateParams>,
+) -> Result<HttpResponse, Error> {
+    let config = data
+        .translator
+        .as_ref()
+        .ok_or_else(|| ApiError::not_found("translation is not configured"))?;
+    if !language_tag_pattern().is_match(&params.target_lang) {
+        return Err(ApiError::validation("target_lang must be a BCP 47 language tag such as es").into());
+    }
+    let post = tenanted_query::<Post>(&tenant.id)
+        .filter(post::Column::Id.eq(id.into_inner()))
+        .one(&data.conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve post"))?
+        .ok_or_else(ApiError::post_not_found)?;
+    if post.is_encrypted {
+        return Err(ApiError::validation("encrypted posts cannot be translated").into());
+    }
+    let mut translation = translate(config, &post.title, &post.text, &params.target_lang).await?;
+    if params.save != Some(1) {
+        return negotiate::respond(&req, HttpResponse::Ok(), &translation);
+    }
+
+    let title: String = translation.translated_title.chars().take(MAX_TITLE_LEN).collect();
+    if translation.translated_text.len() > MAX_TEXT_LEN {
+        let message = format!("the translated text must be at most {} bytes to be saved", MAX_TEXT_LEN);
+        return Err(ApiError::validation(message).into());
+    }
+    data.blocked_words.check(&title, &translation.translated_text)?;
+    data.post_rate_limiter.check(user.id)?;
+    let txn = data
+        .conn
+        .begin()
+        .await
+        .map_err(|_| ApiError::database("could not start transaction"))?;
+    let saved = post::ActiveModel {
+        tenant_id: Set(tenant.id.clone()),
+        title: Set(title),
+        text: Set(translation.translated_text.clone()),
+        status: Set(PostStatus::Draft),
+        ..Default::default()
+    }
+        .insert(&txn)
+        .await
+        .map_err(|_| ApiError::database("could not insert post"))?;
+    permissions::grant(&txn, user.id, saved.id, Permission::Admin)
+        .await
+        .map_err(|_| ApiError::database("could not grant permission"))?;
+    audit::post_created(&txn, Some(user.id), &saved)
+        .await
+        .map_err(|_| ApiError::database("could not record translation"))?;
+    txn.commit()
+        .await
+        .map_err(|_| ApiError::database("could not commit translation"))?;
+    data.similar_posts.invalidate();
+    translation.post_id = Some(saved.id);
+    negotiate::respond(&req, HttpResponse::Created(), &translation)
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(translate_post);
+}
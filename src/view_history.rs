@@ -0,0 +1,76 @@
+use std::collections::VecDeque;
+
+use actix_web::cookie::{time::Duration, Cookie};
+use actix_web::{Error, HttpRequest};
+use sea_orm::{entity::*, query::*, DatabaseConnection};
+use serde::Serialize;
+
+use entity::post::{self, Entity as Post, PostStatus};
+
+use crate::api_error::ApiError;
+use crate::tenants::tenanted_query;
+
+/// Name of the cookie holding the ids of the posts a visitor viewed last, newest first.
+const VIEW_HISTORY_COOKIE: &str = "view_history";
+const MAX_ENTRIES: usize = 10;
+const VIEW_HISTORY_TTL_DAYS: i64 = 30;
+
+/// The posts a visitor viewed last, newest first and each at most once.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ViewHistory(VecDeque<u64>);
+
+#[derive(Debug, Serialize)]
+pub struct RecentPost {
+    id: u64,
+    title: String,
+}
+
+impl ViewHistory {
+    /// The history in the `view_history` cookie; ids that don't parse are dropped.
+    pub fn from_request(req: &HttpRequest) -> Self {
+        let mut history = ViewHistory::default();
+        if let Some(cookie) = req.cookie(VIEW_HISTORY_COOKIE) {
+            for id in cookie.value().split('.').filter_map(|id| id.parse().ok()) {
+                if !history.0.contains(&id) && history.0.len() < MAX_ENTRIES {
+                    history.0.push_back(id);
+                }
+            }
+        }
+        history
+    }
+
+    /// Moves `post_id` to the front, dropping the oldest view past `MAX_ENTRIES`.
+    pub fn record(&mut self, post_id: u64) {
+        self.0.retain(|id| *id != post_id);
+        self.0.push_front(post_id);
+        self.0.truncate(MAX_ENTRIES);
+    }
+
+    pub fn cookie(&self) -> Cookie<'static> {
+        let ids: Vec<String> = self.0.iter().map(u64::to_string).collect();
+        Cookie::build(VIEW_HISTORY_COOKIE, ids.join("."))
+            .path("/")
+            .http_only(true)
+            .max_age(Duration::days(VIEW_HISTORY_TTL_DAYS))
+            .finish()
+    }
+}
+
+/// Titles of the published posts in `history`, in its order. The cookie can be edited, so
+/// unpublished posts are left out rather than trusted to have been viewed.
+pub async fn recent_posts(conn: &DatabaseConnection,
+                          tenant_id: &str,
+                          history: &ViewHistory,
+) -> Result<Vec<RecentPost>, Error> {
+    if history.0.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut posts = tenanted_query::<Post>(tenant_id)
+        .filter(post::Column::Id.is_in(history.0.iter().copied()))
+        .filter(post::Column::Status.eq(PostStatus::Published))
+        .all(conn)
+        .await
+        .map_err(|_| ApiError::database("could not retrieve recent posts"))?;
+    posts.sort_by_key(|post| history.0.iter().position(|id| *id == post.id));
+    Ok(posts.into_iter().map(|post| RecentPost { id: post.id, title: post.title }).collect())
+}
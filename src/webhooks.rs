@@ -0,0 +1,189 @@
+use std::collections::BTreeSet;
+use std::env;
+
+use actix_web::{post, web, Error, HttpRequest, HttpResponse};
+use actix_web::http::header::{self, HeaderName};
+use actix_web::web::{Bytes, Data};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+
+use entity::post::PostStatus;
+
+use crate::api::{self, UpsertPostInput, MAX_TITLE_LEN};
+use crate::api_error::ApiError;
+use crate::auth::AuthUser;
+use crate::tenants::Tenant;
+use crate::AppState;
+
+const SIGNATURE_HEADER: HeaderName = HeaderName::from_static("x-hub-signature-256");
+const EVENT_HEADER: HeaderName = HeaderName::from_static("x-github-event");
+const CONTENTS_URL: &str = "https://api.github.com/repos";
+/// Asks the contents API for the file itself rather than JSON wrapping it in base64.
+const RAW_MEDIA_TYPE: &str = "application/vnd.github.raw";
+
+/// Settings of `POST /webhooks/github`, which is disabled unless they are set.
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    secret: String,
+    /// The user synced posts are written as, who owns the posts the webhook creates.
+    user_id: u64,
+    /// Lets the contents of private repositories be fetched.
+    token: Option<String>,
+}
+
+/// Reads `GITHUB_WEBHOOK_SECRET`, `GITHUB_WEBHOOK_USER_ID` and `GITHUB_TOKEN`.
+///
+/// Returns `None` when no secret is set, which leaves the webhook disabled.
+pub fn config_from_env() -> Option<WebhookConfig> {
+    let secret = env::var("GITHUB_WEBHOOK_SECRET").ok()?;
+    let user_id = env::var("GITHUB_WEBHOOK_USER_ID")
+        .expect("GITHUB_WEBHOOK_USER_ID is not set in .env file")
+        .parse()
+        .expect("GITHUB_WEBHOOK_USER_ID must be a user id");
+    Some(WebhookConfig { secret, user_id, token: env::var("GITHUB_TOKEN").ok() })
+}
+
+#[derive(Debug, Deserialize)]
+struct Repository {
+    full_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Commit {
+    #[serde(default)]
+    added: Vec<String>,
+    #[serde(default)]
+    modified: Vec<String>,
+    #[serde(default)]
+    removed: Vec<String>,
+}
+
+/// The parts of a push event payload the webhook uses.
+#[derive(Debug, Deserialize)]
+struct PushEvent {
+    /// The commit the branch points to after the push.
+    after: String,
+    repository: Repository,
+    #[serde(default)]
+    commits: Vec<Commit>,
+}
+
+/// Whether `signature`, as sent in `X-Hub-Signature-256`, is the HMAC-SHA256 of `body`
+/// under `secret`. The comparison takes the same time wherever the first wrong byte is.
+fn verify_signature(secret: &[u8], body: &[u8], signature: Option<&str>) -> bool {
+    let Some(signature) = signature.and_then(|value| value.strip_prefix("sha256=")) else {
+        return false;
+    };
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC takes keys of any length");
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Markdown files the push leaves added or modified, in path order. Commits are applied
+/// oldest first, so a file removed after it was changed is left out.
+fn markdown_paths(commits: &[Commit]) -> BTreeSet<&str> {
+    let mut paths = BTreeSet::new();
+    for commit in commits {
+        for path in commit.added.iter().chain(&commit.modified) {
+            if path.to_ascii_lowercase().ends_with(".md") {
+                paths.insert(path.as_str());
+            }
+        }
+        for path in &commit.removed {
+            paths.remove(path.as_str());
+        }
+    }
+    paths
+}
+
+/// The first `# ` heading of `markdown`, or else the file name without its extension.
+fn title_of(path: &str, markdown: &str) -> String {
+    let heading = markdown
+        .lines()
+        .find_map(|line| line.strip_prefix("# "))
+        .map(str::trim)
+        .filter(|heading| !heading.is_empty());
+    let title = heading.unwrap_or_else(|| {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        name.rsplit_once('.').map_or(name, |(stem, _)| stem)
+    });
+    title.chars().take(MAX_TITLE_LEN).collect()
+}
+
+/// Fetches `path` of `repository` as of commit `sha`.
+async fn fetch_file(config: &WebhookConfig, repository: &str, sha: &str, path: &str) -> Result<String, Error> {
+    let mut url = reqwest::Url::parse(CONTENTS_URL).expect("CONTENTS_URL is a valid url");
+    url.path_segments_mut()
+        .expect("CONTENTS_URL can have a path")
+        .extend(repository.split('/'))
+        .push("contents")
+        .extend(path.split('/'));
+    url.query_pairs_mut().append_pair("ref", sha);
+    let mut request = reqwest::Client::new()
+        .get(url)
+        .header(header::ACCEPT.as_str(), RAW_MEDIA_TYPE)
+        .header(header::USER_AGENT.as_str(), "sea-orm-demo");
+    if let Some(token) = &config.token {
+        request = request.bearer_auth(token);
+    }
+    request
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|_| ApiError::upstream(format!("could not fetch {} from github", path)))?
+        .text()
+        .await
+        .map_err(|_| ApiError::upstream(format!("could not read {} from github", path)).into())
+}
+
+/// Receives GitHub push events and publishes each Markdown file the push adds or
+/// modifies as a post, matched to the post from earlier pushes by repository and path.
+/// Files removed from the repository keep their posts.
+#[post("/webhooks/github")]
+async fn github_push(req: HttpRequest,
+                     data: Data<AppState>,
+                     tenant: Tenant,
+                     body: Bytes,
+) -> Result<HttpResponse, Error> {
+    let config = data
+        .github_webhook
+        .as_ref()
+        .ok_or_else(|| ApiError::not_found("github webhooks are not configured"))?;
+    let signature = req.headers().get(SIGNATURE_HEADER).and_then(|value| value.to_str().ok());
+    if !verify_signature(config.secret.as_bytes(), &body, signature) {
+        return Err(ApiError::unauthorized("invalid webhook signature").into());
+    }
+    // `ping` is sent when the hook is created; other events have nothing to sync
+    let event = req.headers().get(EVENT_HEADER).and_then(|value| value.to_str().ok());
+    if event != Some("push") {
+        return Ok(HttpResponse::NoContent().finish());
+    }
+    let push: PushEvent = serde_json::from_slice(&body)
+        .map_err(|_| ApiError::bad_request("invalid push event payload"))?;
+
+    let repository = &push.repository.full_name;
+    let mut inputs = Vec::new();
+    for path in markdown_paths(&push.commits) {
+        let text = fetch_file(config, repository, &push.after, path).await?;
+        inputs.push(UpsertPostInput {
+            external_id: Some(format!("github:{}:{}", repository, path)),
+            title: title_of(path, &text),
+            text,
+            status: PostStatus::Published,
+        });
+    }
+    if inputs.is_empty() {
+        return Ok(HttpResponse::NoContent().finish());
+    }
+    let user = AuthUser { id: config.user_id, admin: false };
+    api::upsert_batch(&data, &tenant, &user, inputs).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(github_push);
+}